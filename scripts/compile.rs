@@ -1,109 +1,65 @@
 //! Compile YAML package database to binary format
 //!
 //! This script:
-//! 1. Loads all YAML files from packages/, mappings/, etc.
-//! 2. Validates cross-references
-//! 3. Builds indexes
-//! 4. Serializes to Bincode format
-//! 5. Generates SHA-256 checksum
+//! 1. Loads all YAML files from packages/, mappings/, dependencies/, etc.
+//! 2. Merges `mappings/` platform names into packages (package files win)
+//! 3. Merges `dependencies/` into each package's dependency lists
+//! 4. Validates cross-references
+//! 5. Builds indexes, including the alias index and dependency graph, and
+//!    resolves the package lists of profiles and templates
+//! 6. Serializes to Bincode format
+//! 7. Generates SHA-256 checksum
+//! 8. Optionally writes a zstd-compressed copy
+//! 9. Optionally signs the outputs with a minisign key
+//! 10. Optionally generates a delta patch from a previous release
 //!
-//! Usage: cargo run --bin compile
+//! Usage: cargo run --bin compile -- [--compress] [--sign-key KEY] [--previous OLD_PACKAGES_DB]
 
 use anyhow::{Context, Result};
+use clap::Parser;
 use colored::*;
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use heimdal_packages::alias::AliasIndex;
+use heimdal_packages::conflicts;
+use heimdal_packages::database::{self, compress, CompiledDatabase};
+use heimdal_packages::delta::Patch;
+use heimdal_packages::deprecation;
+use heimdal_packages::graph::DependencyGraph;
+use heimdal_packages::loader::{
+    load_dependencies, load_groups, load_mappings, load_packages, load_profiles, load_suggestions,
+    load_templates,
+};
+use heimdal_packages::model::{Package, PackageGroup};
+use heimdal_packages::reconcile::{self, reconcile, Discrepancy};
+use heimdal_packages::resolve::Resolver;
+use heimdal_packages::signature::SecretKey;
 use std::fs;
-use std::path::Path;
-use walkdir::WalkDir;
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct Package {
-    name: String,
-    description: String,
-    category: String,
-    popularity: u8,
-    platforms: Platforms,
-    #[serde(default)]
-    dependencies: Dependencies,
-    #[serde(default)]
-    alternatives: Vec<String>,
-    #[serde(default)]
-    related: Vec<String>,
-    tags: Vec<String>,
-    website: Option<String>,
-    license: Option<String>,
-    source: Option<String>,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct Platforms {
-    apt: Option<String>,
-    brew: Option<String>,
-    dnf: Option<String>,
-    pacman: Option<String>,
-    mas: Option<i64>,
-}
-
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
-struct Dependencies {
-    #[serde(default)]
-    required: Vec<Dependency>,
-    #[serde(default)]
-    optional: Vec<Dependency>,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct Dependency {
-    package: String,
-    reason: String,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-struct PackageGroup {
-    id: String,
-    name: String,
-    description: String,
-    category: String,
-    packages: GroupPackages,
-    #[serde(default)]
-    platform_overrides: HashMap<String, PlatformOverride>,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-struct GroupPackages {
-    required: Vec<String>,
-    #[serde(default)]
-    optional: Vec<String>,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-struct PlatformOverride {
-    #[serde(default)]
-    packages: Vec<String>,
-    #[serde(default)]
-    casks: Vec<String>,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-struct CompiledDatabase {
-    version: u32,
-    last_updated: String,
-    packages: Vec<Package>,
-    groups: Vec<PackageGroup>,
-    // Indexes
-    index_by_name: HashMap<String, usize>,
-    index_by_category: HashMap<String, Vec<usize>>,
-    index_by_tag: HashMap<String, Vec<usize>>,
+use std::path::PathBuf;
+
+#[derive(Debug, Parser)]
+#[command(about = "Compile the YAML package database to binary format")]
+struct Args {
+    /// Also write a zstd-compressed target/packages.db.zst
+    #[arg(long)]
+    compress: bool,
+
+    /// Unencrypted minisign secret key; writes detached .minisig signatures
+    #[arg(long)]
+    sign_key: Option<PathBuf>,
+
+    /// Previous release's packages.db; writes a delta patch to it
+    #[arg(long)]
+    previous: Option<PathBuf>,
 }
 
 fn main() -> Result<()> {
+    let args = Args::parse();
+
     println!("{}", "Compiling Heimdal Packages Database".bold().cyan());
     println!();
 
     // Load packages
     print!("Loading packages... ");
-    let packages = load_packages("packages")?;
+    let mut packages = load_packages("packages")?;
     println!("{}", format!("✓ {} packages", packages.len()).green());
 
     // Load groups
@@ -111,37 +67,101 @@ fn main() -> Result<()> {
     let groups = load_groups("groups")?;
     println!("{}", format!("✓ {} groups", groups.len()).green());
 
+    // Load mappings
+    print!("Loading mappings... ");
+    let mappings = load_mappings("mappings")?;
+    println!("{}", format!("✓ {} mappings", mappings.len()).green());
+
+    // One platform name per package and manager: the package file wins,
+    // mappings fill in the gaps
+    print!("Merging mapping platform names... ");
+    let conflicts = reconcile(&packages, &mappings)
+        .iter()
+        .filter(|d| matches!(d, Discrepancy::Conflict { .. }))
+        .count();
+    reconcile::merge(&mut packages, &mappings);
+    println!(
+        "{}",
+        format!(
+            "✓ {} conflicts resolved in favour of package files",
+            conflicts
+        )
+        .green()
+    );
+
+    // Load profiles, templates and suggestions (for reverse-dependency queries)
+    print!("Loading profiles and templates... ");
+    let profiles = load_profiles("profiles")?;
+    let templates = load_templates("templates")?;
+    let suggestions = load_suggestions("suggestions")?;
+    println!(
+        "{}",
+        format!(
+            "✓ {} profiles, {} templates, {} suggestion patterns",
+            profiles.len(),
+            templates.len(),
+            suggestions.len()
+        )
+        .green()
+    );
+
+    // Load dependency files
+    print!("Loading dependency files... ");
+    let dependencies = load_dependencies("dependencies")?;
+    println!("{}", format!("✓ {} packages", dependencies.len()).green());
+
+    // One dependency graph: an edge is required if either source requires it
+    print!("Merging dependency files... ");
+    let graph = DependencyGraph::build(&packages, &dependencies);
+    let contradictions = graph.contradictions().len();
+    graph.apply(&mut packages);
+    println!(
+        "{}",
+        format!(
+            "✓ {} edges, {} contradictions resolved as required",
+            graph.edges.values().map(Vec::len).sum::<usize>(),
+            contradictions
+        )
+        .green()
+    );
+
     // Validate cross-references
     print!("Validating references... ");
-    validate_references(&packages, &groups)?;
+    validate_references(&packages, &groups, &graph)?;
     println!("{}", "✓ All references valid".green());
 
+    // Resolve aliases and platform names to canonical packages
+    print!("Building alias index... ");
+    let aliases = AliasIndex::build(&packages, &mappings);
+    if let Some(collision) = aliases.collisions.first() {
+        anyhow::bail!("Ambiguous alias: {}", collision);
+    }
+    if let Some(error) = deprecation::check(&packages).first() {
+        anyhow::bail!("Invalid deprecation: {}", error);
+    }
+    if let Some(error) = conflicts::check(&packages).first() {
+        anyhow::bail!("Invalid conflict: {}", error);
+    }
+    println!("{}", format!("✓ {} names", aliases.entries().len()).green());
+
     // Build indexes
     print!("Building indexes... ");
-    let index_by_name = build_name_index(&packages);
-    let index_by_category = build_category_index(&packages);
-    let index_by_tag = build_tag_index(&packages);
+    let resolver = Resolver::new(&profiles, &groups).with_templates(&templates);
+    let db = CompiledDatabase::build(packages, groups.clone())
+        .with_aliases(&aliases)
+        .with_dependencies(&graph)
+        .with_profiles(&resolver, &profiles, &templates)?
+        .with_suggestions(suggestions);
     println!("{}", "✓ Indexes built".green());
 
-    // Create database
-    let db = CompiledDatabase {
-        version: 1,
-        last_updated: chrono::Utc::now().to_rfc3339(),
-        packages,
-        groups,
-        index_by_name,
-        index_by_category,
-        index_by_tag,
-    };
-
     // Serialize to bincode (using default config for simplicity)
     print!("Serializing to bincode... ");
-    let encoded = bincode::serialize(&db)?;
+    let encoded = db.to_bytes()?;
     println!("{}", format!("✓ {} bytes", encoded.len()).green());
 
     // Test deserialization immediately
     print!("Testing deserialization... ");
-    let decoded: CompiledDatabase = bincode::deserialize(&encoded)?;
+    let decoded = CompiledDatabase::from_bytes(&encoded)?;
     println!(
         "{}",
         format!(
@@ -158,76 +178,118 @@ fn main() -> Result<()> {
     println!("{}", "✓ Wrote target/packages.db".green());
 
     // Generate checksum
-    use sha2::{Digest, Sha256};
-    let hash = Sha256::digest(&encoded);
-    let checksum = format!("{:x}", hash);
+    let checksum = database::checksum(&encoded);
     fs::write("target/packages.db.sha256", &checksum)?;
     println!("{}", "✓ Wrote target/packages.db.sha256".green());
 
+    // Compress for distribution; packages.db.sha256 still verifies the
+    // decompressed bytes, packages.db.zst.sha256 the download itself
+    let mut compressed_len = None;
+    if args.compress {
+        print!("Compressing with zstd... ");
+        let compressed = compress(&encoded)?;
+        CompiledDatabase::from_bytes(&compressed)
+            .context("Compressed database does not round-trip")?;
+        fs::write("target/packages.db.zst", &compressed)?;
+        fs::write(
+            "target/packages.db.zst.sha256",
+            database::checksum(&compressed),
+        )?;
+        println!(
+            "{}",
+            format!(
+                "✓ Wrote target/packages.db.zst ({} bytes) and target/packages.db.zst.sha256",
+                compressed.len()
+            )
+            .green()
+        );
+        compressed_len = Some(compressed.len());
+    }
+
+    // Sign every published form of the database
+    if let Some(key_path) = &args.sign_key {
+        print!("Signing database... ");
+        let key = fs::read_to_string(key_path)
+            .with_context(|| format!("Failed to read {}", key_path.display()))?;
+        let key = SecretKey::from_minisign(&key)?;
+        let timestamp = chrono::Utc::now().timestamp();
+
+        let mut outputs = vec!["packages.db"];
+        if compressed_len.is_some() {
+            outputs.push("packages.db.zst");
+        }
+        for file in outputs {
+            let data = fs::read(format!("target/{}", file))?;
+            let comment = format!(
+                "timestamp:{}\tfile:{}\tversion:{}",
+                timestamp, file, db.version
+            );
+            let signature = key.sign(&data, &comment);
+            fs::write(format!("target/{}.minisig", file), signature.to_minisign())?;
+        }
+        println!(
+            "{}",
+            format!("✓ Signed with key {}", key.public_key().key_id()).green()
+        );
+    }
+
+    // Generate delta from the previous release
+    if let Some(previous) = &args.previous {
+        print!("Generating delta patch... ");
+        let old =
+            fs::read(previous).with_context(|| format!("Failed to read {}", previous.display()))?;
+        let patch = Patch::diff(&old, &encoded);
+        let patch_bytes = patch.to_bytes();
+        patch
+            .apply(&old)
+            .context("Generated patch does not reproduce the database")?;
+        let path = format!("target/{}", patch.file_name());
+        fs::write(&path, &patch_bytes)?;
+        println!(
+            "{}",
+            format!(
+                "✓ Wrote {} ({} bytes, {:.0}% of full)",
+                path,
+                patch_bytes.len(),
+                patch_bytes.len() as f64 / encoded.len().max(1) as f64 * 100.0
+            )
+            .green()
+        );
+    }
+
     println!();
     println!("{}", "Database compiled successfully!".bold().green());
     println!("  Version: {}", db.version);
     println!("  Packages: {}", db.packages.len());
     println!("  Groups: {}", db.groups.len());
     println!("  Size: {} KB", encoded.len() / 1024);
+    if let Some(len) = compressed_len {
+        println!(
+            "  Compressed: {} KB ({:.0}% of raw)",
+            len / 1024,
+            len as f64 / encoded.len().max(1) as f64 * 100.0
+        );
+    }
     println!("  SHA-256: {}", &checksum[..16]);
 
     Ok(())
 }
 
-fn load_packages(dir: &str) -> Result<Vec<Package>> {
-    let mut packages = Vec::new();
-
-    for entry in WalkDir::new(dir)
-        .into_iter()
-        .filter_map(|e| e.ok())
-        .filter(|e| e.path().extension().is_some_and(|ext| ext == "yaml"))
-    {
-        let content = fs::read_to_string(entry.path())?;
-        let package: Package = serde_yaml::from_str(&content)
-            .with_context(|| format!("Failed to parse {}", entry.path().display()))?;
+fn validate_references(
+    packages: &[Package],
+    groups: &[PackageGroup],
+    graph: &DependencyGraph,
+) -> Result<()> {
+    let package_names: std::collections::HashSet<_> = packages.iter().map(|p| &p.name).collect();
 
-        // Validate filename matches package name
-        let expected_name = format!("{}.yaml", package.name);
-        let actual_name = entry.path().file_name().unwrap().to_str().unwrap();
-        if actual_name != expected_name {
-            anyhow::bail!(
-                "Filename mismatch: {} should be {}",
-                entry.path().display(),
-                expected_name
-            );
+    // Validate dependency file entries (their edges are checked below, once
+    // merged into the packages)
+    for package in graph.edges.keys() {
+        if !package_names.contains(package) {
+            anyhow::bail!("Dependency file entry for unknown package: '{}'", package);
         }
-
-        packages.push(package);
-    }
-
-    Ok(packages)
-}
-
-fn load_groups(dir: &str) -> Result<Vec<PackageGroup>> {
-    let mut groups = Vec::new();
-
-    if !Path::new(dir).exists() {
-        return Ok(groups);
-    }
-
-    for entry in WalkDir::new(dir)
-        .into_iter()
-        .filter_map(|e| e.ok())
-        .filter(|e| e.path().extension().is_some_and(|ext| ext == "yaml"))
-    {
-        let content = fs::read_to_string(entry.path())?;
-        let group: PackageGroup = serde_yaml::from_str(&content)
-            .with_context(|| format!("Failed to parse {}", entry.path().display()))?;
-        groups.push(group);
     }
 
-    Ok(groups)
-}
-
-fn validate_references(packages: &[Package], groups: &[PackageGroup]) -> Result<()> {
-    let package_names: std::collections::HashSet<_> = packages.iter().map(|p| &p.name).collect();
-
     // Validate package dependencies
     for pkg in packages {
         for dep in &pkg.dependencies.required {
@@ -250,7 +312,11 @@ fn validate_references(packages: &[Package], groups: &[PackageGroup]) -> Result<
         }
     }
 
-    // Validate group packages
+    // Validate group packages, which may still use former names
+    let package_names: std::collections::HashSet<_> = packages
+        .iter()
+        .flat_map(|p| std::iter::once(&p.name).chain(&p.renamed_from))
+        .collect();
     for group in groups {
         for pkg_name in &group.packages.required {
             if !package_names.contains(pkg_name) {
@@ -274,29 +340,3 @@ fn validate_references(packages: &[Package], groups: &[PackageGroup]) -> Result<
 
     Ok(())
 }
-
-fn build_name_index(packages: &[Package]) -> HashMap<String, usize> {
-    packages
-        .iter()
-        .enumerate()
-        .map(|(i, p)| (p.name.clone(), i))
-        .collect()
-}
-
-fn build_category_index(packages: &[Package]) -> HashMap<String, Vec<usize>> {
-    let mut index: HashMap<String, Vec<usize>> = HashMap::new();
-    for (i, pkg) in packages.iter().enumerate() {
-        index.entry(pkg.category.clone()).or_default().push(i);
-    }
-    index
-}
-
-fn build_tag_index(packages: &[Package]) -> HashMap<String, Vec<usize>> {
-    let mut index: HashMap<String, Vec<usize>> = HashMap::new();
-    for (i, pkg) in packages.iter().enumerate() {
-        for tag in &pkg.tags {
-            index.entry(tag.clone()).or_default().push(i);
-        }
-    }
-    index
-}
@@ -0,0 +1,121 @@
+//! Generate a Dockerfile or devcontainer from a group or profile
+//!
+//! This script:
+//! 1. Loads the YAML source tree
+//! 2. Flattens the selected group, profile or template into its packages
+//! 3. Looks up each package's name on the base image family, failing when a
+//!    required package has none
+//! 4. Prints a Dockerfile with one cached install layer, or writes
+//!    `.devcontainer/devcontainer.json` and its Dockerfile
+//!
+//! Usage: cargo run --bin container -- (--group ID | --profile ID | --template ID) [--choose SLOT=PACKAGE]... --family debian|fedora|arch|alpine [--format dockerfile|devcontainer]
+
+use anyhow::Result;
+use clap::{ArgGroup, Parser, ValueEnum};
+use colored::*;
+use heimdal_packages::choice::{self, Choices};
+use heimdal_packages::container::{ContainerSpec, Family};
+use heimdal_packages::export::{PackageSet, Selection};
+use heimdal_packages::loader::SourceTree;
+use std::fs;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Format {
+    Dockerfile,
+    Devcontainer,
+}
+
+#[derive(Debug, Parser)]
+#[command(about = "Generate a Dockerfile or devcontainer from a group or profile")]
+#[command(group(ArgGroup::new("selection").required(true).args(["group", "profile", "template"])))]
+struct Args {
+    #[arg(long)]
+    group: Option<String>,
+
+    #[arg(long)]
+    profile: Option<String>,
+
+    #[arg(long)]
+    template: Option<String>,
+
+    /// Pick an option for a choice slot (repeatable), e.g. `editor=helix`
+    #[arg(long, value_name = "SLOT=PACKAGE", value_parser = choice::parse)]
+    choose: Vec<(String, String)>,
+
+    /// Base image family: debian, fedora, arch or alpine
+    #[arg(long)]
+    family: Family,
+
+    /// Base image (defaults to the family's image, e.g. debian:bookworm-slim)
+    #[arg(long)]
+    image: Option<String>,
+
+    /// Also install a group's optional packages that have a name on the family
+    #[arg(long)]
+    optional: bool,
+
+    #[arg(long, value_enum, default_value = "dockerfile")]
+    format: Format,
+
+    /// Dockerfile path, or the directory to create `.devcontainer/` in
+    /// (defaults to stdout / target/devcontainer/<id>)
+    #[arg(long)]
+    output: Option<PathBuf>,
+}
+
+impl Args {
+    fn selection(&self) -> Selection {
+        match (&self.group, &self.profile, &self.template) {
+            (Some(id), _, _) => Selection::Group(id.clone()),
+            (_, Some(id), _) => Selection::Profile(id.clone()),
+            (_, _, Some(id)) => Selection::Template(id.clone()),
+            _ => unreachable!("clap requires one selection"),
+        }
+    }
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    let selection = args.selection();
+
+    let tree = SourceTree::load(".")?;
+    let choices: Choices = args.choose.iter().cloned().collect();
+    let set = PackageSet::load_with_choices(&tree, &selection, &choices)?;
+    let mut spec = ContainerSpec::build(&tree, &set, args.family, args.optional)?;
+    if let Some(image) = &args.image {
+        spec = spec.with_image(image);
+    }
+
+    match args.format {
+        Format::Dockerfile => match &args.output {
+            Some(path) => fs::write(path, spec.dockerfile())?,
+            None => print!("{}", spec.dockerfile()),
+        },
+        Format::Devcontainer => {
+            let root = args
+                .output
+                .clone()
+                .unwrap_or_else(|| PathBuf::from("target/devcontainer").join(selection.id()));
+            let dir = root.join(".devcontainer");
+            fs::create_dir_all(&dir)?;
+            fs::write(dir.join("Dockerfile"), spec.dockerfile())?;
+            fs::write(
+                dir.join("devcontainer.json"),
+                serde_json::to_string_pretty(&spec.devcontainer())? + "\n",
+            )?;
+            println!("{}", format!("✓ Wrote {}", dir.display()).green());
+        }
+    }
+
+    if !spec.skipped.is_empty() {
+        eprintln!(
+            "{} skipped optional packages with no {} name: {}",
+            "⚠".yellow(),
+            spec.family,
+            spec.skipped.join(", ")
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,111 @@
+//! Generate a platform coverage report
+//!
+//! This script:
+//! 1. Loads packages, mappings, groups and profiles
+//! 2. Builds a package × platform matrix
+//! 3. Lists packages missing on each platform
+//! 4. Computes per-group and per-profile coverage
+//! 5. Writes the report as markdown, HTML or CSV
+//!
+//! Usage: cargo run --bin coverage -- [--format markdown|html|csv] [--output PATH]
+
+use anyhow::Result;
+use clap::{Parser, ValueEnum};
+use colored::*;
+use heimdal_packages::coverage::CoverageReport;
+use heimdal_packages::loader::SourceTree;
+use heimdal_packages::model::Manager;
+use std::fs;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Format {
+    Markdown,
+    Html,
+    Csv,
+}
+
+impl Format {
+    fn extension(&self) -> &'static str {
+        match self {
+            Format::Markdown => "md",
+            Format::Html => "html",
+            Format::Csv => "csv",
+        }
+    }
+}
+
+#[derive(Debug, Parser)]
+#[command(about = "Generate a platform coverage report")]
+struct Args {
+    /// Report format (CSV contains the matrix only)
+    #[arg(long, value_enum, default_value = "markdown")]
+    format: Format,
+
+    /// Output file (defaults to target/coverage.<ext>)
+    #[arg(long)]
+    output: Option<PathBuf>,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+
+    println!("{}", "Heimdal Packages Platform Coverage".bold().cyan());
+    println!();
+
+    print!("Loading source tree... ");
+    let tree = SourceTree::load(".")?;
+    println!(
+        "{}",
+        format!(
+            "✓ {} packages, {} groups, {} profiles",
+            tree.packages.len(),
+            tree.groups.len(),
+            tree.profiles.len()
+        )
+        .green()
+    );
+
+    print!("Building coverage matrix... ");
+    let report = CoverageReport::build(&tree)?;
+    println!("{}", "✓".green());
+
+    println!();
+    println!("{}", "Missing Packages:".bold().white());
+    for manager in Manager::ALL {
+        let missing = report.matrix.missing(manager);
+        if missing.is_empty() {
+            println!("  {:10} {}", format!("{}:", manager).cyan(), "none".green());
+        } else {
+            println!(
+                "  {:10} {}",
+                format!("{}:", manager).cyan(),
+                missing.join(", ").yellow()
+            );
+        }
+    }
+
+    println!();
+    println!("{}", "Groups & Profiles:".bold().white());
+    for set in report.groups.iter().chain(report.profiles.iter()) {
+        println!("  {}", set.summary());
+    }
+
+    let output = args
+        .output
+        .unwrap_or_else(|| PathBuf::from(format!("target/coverage.{}", args.format.extension())));
+    let content = match args.format {
+        Format::Markdown => report.to_markdown(),
+        Format::Html => report.to_html(),
+        Format::Csv => report.matrix.to_csv(),
+    };
+    if let Some(parent) = output.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&output, content)?;
+
+    println!();
+    println!("{}", format!("✓ Wrote {}", output.display()).green());
+
+    Ok(())
+}
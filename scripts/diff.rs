@@ -0,0 +1,102 @@
+//! Generate a changelog by diffing two database versions
+//!
+//! This script:
+//! 1. Loads both sides from compiled `packages.db` files or git revisions,
+//!    merging mappings and dependency files into a git tree like `compile`
+//! 2. Detects added, removed and renamed packages
+//! 3. Detects platform name, dependency and group membership changes
+//! 4. Prints a Keep-a-Changelog section or JSON
+//!
+//! Usage: cargo run --bin diff -- <OLD> <NEW> [--git] [--format markdown|json]
+//!
+//! Database files can only be compared in this build's format version; to
+//! compare across a format change, pass the two release tags with `--git`.
+
+use anyhow::{Context, Result};
+use clap::{Parser, ValueEnum};
+use heimdal_packages::database::{self, CompiledDatabase, DATABASE_VERSION};
+use heimdal_packages::diff::{self, DatabaseDiff};
+use heimdal_packages::loader::SourceTree;
+use heimdal_packages::model::{Package, PackageGroup};
+use std::fs;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Format {
+    Markdown,
+    Json,
+}
+
+#[derive(Debug, Parser)]
+#[command(about = "Compare two database versions and print a changelog")]
+struct Args {
+    /// Old `packages.db` (or git revision with --git)
+    old: String,
+
+    /// New `packages.db` (or git revision with --git)
+    new: String,
+
+    /// Treat OLD and NEW as git revisions of the YAML tree
+    #[arg(long)]
+    git: bool,
+
+    #[arg(long, value_enum, default_value = "markdown")]
+    format: Format,
+
+    /// Release heading used in markdown output
+    #[arg(long, default_value = "Unreleased")]
+    release: String,
+
+    /// Write to a file instead of stdout
+    #[arg(long)]
+    output: Option<PathBuf>,
+}
+
+fn load(side: &str, git: bool) -> Result<(Vec<Package>, Vec<PackageGroup>)> {
+    if git {
+        let tree = SourceTree::from_git(".", side)?;
+        Ok((diff::compiled_packages(&tree), tree.groups))
+    } else {
+        let bytes = fs::read(side).with_context(|| format!("Failed to read {}", side))?;
+        let version = database::format_version(&bytes)?;
+        if version != DATABASE_VERSION {
+            anyhow::bail!(
+                "{} is database format version {} but this build reads version {}; \
+                 compare the release tags with --git instead",
+                side,
+                version,
+                DATABASE_VERSION
+            );
+        }
+        let db = CompiledDatabase::from_bytes(&bytes)?;
+        Ok((db.packages, db.groups))
+    }
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+
+    let (old_packages, old_groups) = load(&args.old, args.git)?;
+    let (new_packages, new_groups) = load(&args.new, args.git)?;
+    let diff = DatabaseDiff::compute(&old_packages, &old_groups, &new_packages, &new_groups);
+
+    let date = chrono::Utc::now().format("%Y-%m-%d").to_string();
+    let content = match args.format {
+        Format::Markdown => diff.to_markdown(&args.release, &date),
+        Format::Json => {
+            let value = serde_json::json!({
+                "release": args.release,
+                "date": date,
+                "changes": diff,
+            });
+            serde_json::to_string_pretty(&value)? + "\n"
+        }
+    };
+
+    match args.output {
+        Some(path) => fs::write(path, content)?,
+        None => print!("{}", content),
+    }
+
+    Ok(())
+}
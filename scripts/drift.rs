@@ -0,0 +1,88 @@
+//! Compare a machine's installed packages with a group, profile or template
+//!
+//! This script:
+//! 1. Loads the YAML source tree
+//! 2. Reads the installed packages from captured package-manager state (dpkg
+//!    `status`, the pacman local database or `pacman -Q`, `rpm -qa`, or
+//!    `brew list --versions`) and maps them to canonical packages
+//! 3. Resolves the selection, with any `--choose` picks
+//! 4. Reports missing required and optional packages, packages outside the
+//!    selection, and packages installed under an alias, as text or JSON
+//!
+//! Usage: cargo run --bin drift -- (--group ID | --profile ID | --template ID) --manager apt|brew|dnf|pacman --state PATH [--choose SLOT=PACKAGE]... [--format text|json]
+
+use anyhow::Result;
+use clap::{ArgGroup, Parser, ValueEnum};
+use heimdal_packages::alias::AliasIndex;
+use heimdal_packages::choice::{self, Choices};
+use heimdal_packages::drift::DriftReport;
+use heimdal_packages::export::Selection;
+use heimdal_packages::installed::{self, Installed};
+use heimdal_packages::loader::SourceTree;
+use heimdal_packages::model::Manager;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Format {
+    Text,
+    Json,
+}
+
+#[derive(Debug, Parser)]
+#[command(about = "Compare installed packages with a group, profile or template")]
+#[command(group(ArgGroup::new("selection").required(true).args(["group", "profile", "template"])))]
+struct Args {
+    #[arg(long)]
+    group: Option<String>,
+
+    #[arg(long)]
+    profile: Option<String>,
+
+    #[arg(long)]
+    template: Option<String>,
+
+    /// Package manager the state comes from: apt, brew, dnf or pacman
+    #[arg(long)]
+    manager: Manager,
+
+    /// dpkg status file, pacman local database directory, or captured
+    /// `pacman -Q`, `rpm -qa` or `brew list --versions` output
+    #[arg(long)]
+    state: PathBuf,
+
+    /// Pick an option for a choice slot (repeatable), e.g. `editor=helix`
+    #[arg(long, value_name = "SLOT=PACKAGE", value_parser = choice::parse)]
+    choose: Vec<(String, String)>,
+
+    #[arg(long, value_enum, default_value = "text")]
+    format: Format,
+}
+
+impl Args {
+    fn selection(&self) -> Selection {
+        match (&self.group, &self.profile, &self.template) {
+            (Some(id), _, _) => Selection::Group(id.clone()),
+            (_, Some(id), _) => Selection::Profile(id.clone()),
+            (_, _, Some(id)) => Selection::Template(id.clone()),
+            _ => unreachable!("clap requires one selection"),
+        }
+    }
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    let selection = args.selection();
+
+    let tree = SourceTree::load(".")?;
+    let natives = installed::read(args.manager, &args.state)?;
+    let installed = Installed::resolve(&AliasIndex::from_tree(&tree), args.manager, natives);
+    let choices: Choices = args.choose.iter().cloned().collect();
+    let report = DriftReport::compute(&tree, &selection, &choices, &installed)?;
+
+    match args.format {
+        Format::Text => print!("{}", report.to_text()),
+        Format::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+    }
+
+    Ok(())
+}
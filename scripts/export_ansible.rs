@@ -0,0 +1,107 @@
+//! Export a group, profile or template as an Ansible role
+//!
+//! This script:
+//! 1. Loads the YAML source tree
+//! 2. Flattens the selected group, profile or template into its packages,
+//!    dotfiles and hooks
+//! 3. Writes a role installing the mapped names per `ansible_os_family`
+//! 4. Lists packages that have no name on a manager
+//!
+//! Usage: cargo run --bin export-ansible -- (--group ID | --profile ID | --template ID) [--choose SLOT=PACKAGE]... [--output DIR]
+
+use anyhow::Result;
+use clap::{ArgGroup, Parser};
+use colored::*;
+use heimdal_packages::ansible::AnsibleRole;
+use heimdal_packages::choice::{self, Choices};
+use heimdal_packages::export::{PackageSet, Selection};
+use heimdal_packages::loader::SourceTree;
+use std::path::PathBuf;
+
+#[derive(Debug, Parser)]
+#[command(about = "Export a group, profile or template as an Ansible role")]
+#[command(group(ArgGroup::new("selection").required(true).args(["group", "profile", "template"])))]
+struct Args {
+    #[arg(long)]
+    group: Option<String>,
+
+    #[arg(long)]
+    profile: Option<String>,
+
+    #[arg(long)]
+    template: Option<String>,
+
+    /// Pick an option for a choice slot (repeatable), e.g. `editor=helix`
+    #[arg(long, value_name = "SLOT=PACKAGE", value_parser = choice::parse)]
+    choose: Vec<(String, String)>,
+
+    /// Role directory (defaults to target/ansible/<role>)
+    #[arg(long)]
+    output: Option<PathBuf>,
+}
+
+impl Args {
+    fn selection(&self) -> Selection {
+        match (&self.group, &self.profile, &self.template) {
+            (Some(id), _, _) => Selection::Group(id.clone()),
+            (_, Some(id), _) => Selection::Profile(id.clone()),
+            (_, _, Some(id)) => Selection::Template(id.clone()),
+            _ => unreachable!("clap requires one selection"),
+        }
+    }
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    let selection = args.selection();
+
+    println!("{}", "Heimdal Packages Ansible Export".bold().cyan());
+    println!();
+
+    print!("Loading {}... ", selection);
+    let tree = SourceTree::load(".")?;
+    let choices: Choices = args.choose.iter().cloned().collect();
+    let set = PackageSet::load_with_choices(&tree, &selection, &choices)?;
+    println!(
+        "{}",
+        format!(
+            "✓ {} packages, {} dotfiles",
+            set.packages.len(),
+            set.dotfiles.len()
+        )
+        .green()
+    );
+
+    let role = AnsibleRole::build(&set);
+    let output = args
+        .output
+        .unwrap_or_else(|| PathBuf::from("target/ansible").join(&role.name));
+    role.write(&output)?;
+
+    for &manager in &set.managers {
+        let unavailable = set.unavailable(manager, true);
+        if !unavailable.is_empty() {
+            println!(
+                "  {} not available on {}: {}",
+                "⚠".yellow(),
+                manager,
+                unavailable.join(", ")
+            );
+        }
+    }
+
+    println!();
+    println!(
+        "{}",
+        format!("✓ Wrote role {} to {}", role.name, output.display()).green()
+    );
+    if !role.dotfile_sources.is_empty() {
+        println!(
+            "  Copy these dotfiles into {}: {}",
+            output.join("files").display(),
+            role.dotfile_sources.join(", ")
+        );
+    }
+
+    Ok(())
+}
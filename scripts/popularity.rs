@@ -0,0 +1,102 @@
+//! Recompute package popularity from usage data
+//!
+//! This script:
+//! 1. Loads the YAML source tree
+//! 2. Reads whichever inputs are given: a Debian popcon `by_inst` dump, a
+//!    Homebrew analytics JSON export, and a `name,stars` CSV of GitHub stars
+//! 3. Maps their names to packages and scores each source on a log scale
+//!    (see `heimdal_packages::popularity`)
+//! 4. Reports packages whose hand-set `popularity` differs from the computed
+//!    score by `--threshold` or more, or with `--write` rewrites the scores
+//!
+//! Usage: cargo run --bin popularity -- [--popcon PATH] [--brew PATH] [--stars PATH] [--threshold 20] [--write] [--format text|json]
+
+use anyhow::{Context, Result};
+use clap::{ArgGroup, Parser, ValueEnum};
+use colored::*;
+use heimdal_packages::alias::AliasIndex;
+use heimdal_packages::loader::SourceTree;
+use heimdal_packages::popularity::{self, Counts, PopularityReport, Source};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Format {
+    Text,
+    Json,
+}
+
+#[derive(Debug, Parser)]
+#[command(about = "Recompute package popularity from usage data")]
+#[command(group(ArgGroup::new("inputs").required(true).multiple(true).args(["popcon", "brew", "stars"])))]
+struct Args {
+    /// Debian popcon `by_inst` dump
+    #[arg(long)]
+    popcon: Option<PathBuf>,
+
+    /// Homebrew analytics JSON export (install or install-on-request)
+    #[arg(long)]
+    brew: Option<PathBuf>,
+
+    /// CSV of `name,stars`, where `name` is a package or `owner/repo`
+    #[arg(long)]
+    stars: Option<PathBuf>,
+
+    /// Report scores differing from the hand-set value by at least this much
+    #[arg(long, default_value_t = 20)]
+    threshold: u8,
+
+    /// Write the computed scores into the package files
+    #[arg(long)]
+    write: bool,
+
+    #[arg(long, value_enum, default_value = "text")]
+    format: Format,
+}
+
+fn read(path: &Path) -> Result<String> {
+    fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+
+    let tree = SourceTree::load(".")?;
+    let index = AliasIndex::from_tree(&tree);
+    let mut counts = Vec::new();
+    let mut add = |source: Source, raw: Vec<(String, u64)>| {
+        counts.push(Counts::resolve(source, &index, &tree.packages, raw));
+    };
+    if let Some(path) = &args.popcon {
+        add(Source::Popcon, popularity::parse_popcon(&read(path)?));
+    }
+    if let Some(path) = &args.brew {
+        add(
+            Source::Homebrew,
+            popularity::parse_brew_analytics(&read(path)?)
+                .with_context(|| format!("Failed to parse {}", path.display()))?,
+        );
+    }
+    if let Some(path) = &args.stars {
+        add(
+            Source::GithubStars,
+            popularity::parse_stars_csv(&read(path)?),
+        );
+    }
+
+    let report = PopularityReport::compute(&tree.packages, &counts);
+    match args.format {
+        Format::Text => print!("{}", report.to_text(args.threshold)),
+        Format::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+    }
+
+    if args.write {
+        let changed = popularity::write_scores("packages", &report.scores)?;
+        eprintln!(
+            "{}",
+            format!("✓ Updated popularity in {} package files", changed).green()
+        );
+    }
+
+    Ok(())
+}
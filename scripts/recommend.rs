@@ -0,0 +1,77 @@
+//! Recommend packages for an installed set
+//!
+//! This script:
+//! 1. Reads a compiled `packages.db` (raw or zstd-compressed)
+//! 2. Resolves the installed names (canonical, alias or platform names), from
+//!    the command line or a file with one name per line
+//! 3. Scores the other packages by `related` links, shared groups, profiles
+//!    and tags, and popularity
+//! 4. Prints the best matches with the reasons behind each
+//!
+//! Usage: cargo run --bin recommend -- [PACKAGE]... [--file PATH] [--db target/packages.db] [--limit 10] [--format text|json]
+
+use anyhow::{Context, Result};
+use clap::{Parser, ValueEnum};
+use heimdal_packages::database::CompiledDatabase;
+use heimdal_packages::recommend::Recommendations;
+use std::fs;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Format {
+    Text,
+    Json,
+}
+
+#[derive(Debug, Parser)]
+#[command(about = "Suggest packages that go with an installed set")]
+struct Args {
+    /// Installed packages (names, aliases or platform names)
+    packages: Vec<String>,
+
+    /// Read installed packages from a file, one per line (`#` starts a comment)
+    #[arg(long)]
+    file: Option<PathBuf>,
+
+    /// Compiled database to query
+    #[arg(long, default_value = "target/packages.db")]
+    db: PathBuf,
+
+    /// Number of recommendations
+    #[arg(long, default_value_t = 10)]
+    limit: usize,
+
+    #[arg(long, value_enum, default_value = "text")]
+    format: Format,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+
+    let mut installed = args.packages.clone();
+    if let Some(path) = &args.file {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        installed.extend(
+            content
+                .lines()
+                .map(|l| l.split('#').next().unwrap_or_default().trim())
+                .filter(|l| !l.is_empty())
+                .map(String::from),
+        );
+    }
+    if installed.is_empty() {
+        anyhow::bail!("No installed packages given");
+    }
+
+    let db = CompiledDatabase::read(&args.db)?;
+    let recommendations =
+        Recommendations::query(&db, installed.iter().map(String::as_str), args.limit);
+
+    match args.format {
+        Format::Text => print!("{}", recommendations.to_text()),
+        Format::Json => println!("{}", serde_json::to_string_pretty(&recommendations)?),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,62 @@
+//! Render a template into a concrete environment spec
+//!
+//! This script:
+//! 1. Loads the YAML source tree
+//! 2. Resolves the template's base profile chain, groups and custom packages,
+//!    installing the chosen option of each choice slot
+//! 3. Looks up each package's name on the template's platform
+//! 4. Prints the flattened document as YAML or JSON
+//!
+//! Usage: cargo run --bin render-template -- <ID> [--choose SLOT=PACKAGE]... [--format yaml|json]
+
+use anyhow::Result;
+use clap::{Parser, ValueEnum};
+use heimdal_packages::choice::{self, Choices};
+use heimdal_packages::loader::SourceTree;
+use heimdal_packages::template::RenderedTemplate;
+use std::fs;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Format {
+    Yaml,
+    Json,
+}
+
+#[derive(Debug, Parser)]
+#[command(about = "Render a template into a flattened environment spec")]
+struct Args {
+    /// Template id (file name in templates/ without .yaml)
+    id: String,
+
+    /// Pick an option for a choice slot (repeatable), e.g. `editor=helix`
+    #[arg(long, value_name = "SLOT=PACKAGE", value_parser = choice::parse)]
+    choose: Vec<(String, String)>,
+
+    #[arg(long, value_enum, default_value = "yaml")]
+    format: Format,
+
+    /// Write to a file instead of stdout
+    #[arg(long)]
+    output: Option<PathBuf>,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+
+    let tree = SourceTree::load(".")?;
+    let choices: Choices = args.choose.into_iter().collect();
+    let rendered = RenderedTemplate::render_with_choices(&tree, &args.id, &choices)?;
+
+    let content = match args.format {
+        Format::Yaml => serde_yaml::to_string(&rendered)?,
+        Format::Json => serde_json::to_string_pretty(&rendered)? + "\n",
+    };
+
+    match args.output {
+        Some(path) => fs::write(path, content)?,
+        None => print!("{}", content),
+    }
+
+    Ok(())
+}
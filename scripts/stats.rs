@@ -5,12 +5,18 @@
 //! 2. Calculates database size
 //! 3. Shows platform coverage
 //! 4. Displays validation status
-//! 5. Generates markdown output for badges
+//! 5. Compares raw and zstd-compressed database size
+//! 6. Lists packages without maintainers, never verified, or last verified
+//!    more than `--stale-after` days ago
+//! 7. Generates markdown output for badges
 //!
-//! Usage: cargo run --bin stats
+//! Usage: cargo run --bin stats [-- --stale-after 365]
 
 use anyhow::{Context, Result};
+use clap::Parser;
 use colored::*;
+use heimdal_packages::loader::load_packages;
+use heimdal_packages::maintenance::{MaintenanceReport, DEFAULT_STALE_DAYS};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
@@ -63,6 +69,14 @@ struct PackageGroup {
     name: String,
 }
 
+#[derive(Debug, Parser)]
+#[command(about = "Show statistics for the package database")]
+struct Args {
+    /// Report packages last verified more than this many days ago as stale
+    #[arg(long, value_name = "DAYS", default_value_t = DEFAULT_STALE_DAYS)]
+    stale_after: i64,
+}
+
 struct DatabaseStats {
     total_packages: usize,
     packages_by_category: HashMap<String, Vec<String>>,
@@ -71,7 +85,9 @@ struct DatabaseStats {
     total_mappings: usize,
     platform_coverage: PlatformCoverage,
     database_size: Option<u64>,
+    compressed_size: Option<u64>,
     total_tags: usize,
+    maintenance: Option<MaintenanceReport>,
 }
 
 struct PlatformCoverage {
@@ -82,6 +98,8 @@ struct PlatformCoverage {
 }
 
 fn main() -> Result<()> {
+    let args = Args::parse();
+
     println!("{}", "Heimdal Packages Database Statistics".bold().cyan());
     println!();
 
@@ -98,7 +116,9 @@ fn main() -> Result<()> {
             pacman: 0,
         },
         database_size: None,
+        compressed_size: None,
         total_tags: 0,
+        maintenance: None,
     };
 
     // Count packages
@@ -121,14 +141,25 @@ fn main() -> Result<()> {
     scan_mappings(&mut stats)?;
     println!("{}", format!("✓ {} mappings", stats.total_mappings).green());
 
+    // Check maintainers and verification dates
+    print!("Checking maintenance... ");
+    let today = chrono::Local::now().date_naive();
+    let report = MaintenanceReport::compute(&load_packages("packages")?, today, args.stale_after);
+    println!(
+        "{}",
+        format!("✓ {} unmaintained", report.unmaintained.len()).green()
+    );
+    stats.maintenance = Some(report);
+
     // Check database size
     print!("Checking database size... ");
-    if let Some(size) = get_database_size() {
+    if let Some(size) = get_file_size("target/packages.db") {
         stats.database_size = Some(size);
         println!("{}", format!("✓ {} bytes", size).green());
     } else {
         println!("{}", "⚠ Not compiled yet".yellow());
     }
+    stats.compressed_size = get_file_size("target/packages.db.zst");
 
     println!();
     display_stats(&stats);
@@ -241,8 +272,8 @@ fn scan_mappings(stats: &mut DatabaseStats) -> Result<()> {
     Ok(())
 }
 
-fn get_database_size() -> Option<u64> {
-    let db_path = Path::new("target/packages.db");
+fn get_file_size(path: &str) -> Option<u64> {
+    let db_path = Path::new(path);
     if db_path.exists() {
         fs::metadata(db_path).ok().map(|m| m.len())
     } else {
@@ -275,12 +306,21 @@ fn display_stats(stats: &DatabaseStats) {
         println!("  DB Size:   {}", "Not compiled".yellow());
     }
 
+    if let (Some(raw), Some(compressed)) = (stats.database_size, stats.compressed_size) {
+        println!(
+            "  Zstd:      {} ({:.0}% of raw, saves {} bytes)",
+            format!("{} bytes", compressed).green(),
+            compressed as f64 / raw.max(1) as f64 * 100.0,
+            raw.saturating_sub(compressed)
+        );
+    }
+
     println!();
 
     // Categories
     println!("{}", "Packages by Category:".bold().white());
     let mut categories: Vec<_> = stats.packages_by_category.iter().collect();
-    categories.sort_by(|a, b| b.1.len().cmp(&a.1.len()));
+    categories.sort_by_key(|(_, packages)| std::cmp::Reverse(packages.len()));
 
     for (category, packages) in categories {
         println!(
@@ -327,6 +367,31 @@ fn display_stats(stats: &DatabaseStats) {
 
     println!();
 
+    // Maintenance
+    if let Some(report) = &stats.maintenance {
+        println!("{}", "Maintenance:".bold().white());
+        println!(
+            "  Unmaintained: {} of {} packages",
+            report.unmaintained.len().to_string().yellow(),
+            report.packages
+        );
+        println!(
+            "  Unverified:   {} packages",
+            report.unverified.len().to_string().yellow()
+        );
+        println!(
+            "  Stale:        {} packages",
+            report.stale.len().to_string().yellow()
+        );
+        if !report.unmaintained.is_empty() {
+            println!("  Without maintainers: {}", report.unmaintained.join(", "));
+        }
+        for entry in &report.stale {
+            println!("  {}", entry.to_string().yellow());
+        }
+        println!();
+    }
+
     // Badge markdown
     println!("{}", "Badge Markdown (for README):".bold().white());
     println!(
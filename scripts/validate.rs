@@ -7,11 +7,51 @@
 //! 4. Verifies cross-references between packages
 //! 5. Validates filename matches package name
 //! 6. Ensures minimum platform coverage
+//! 7. Checks that alternatives are symmetric, never self-referencing and
+//!    within one category
+//! 8. Checks that mapping aliases collide with no package or platform name
+//!    and that mappings agree with package platforms
+//! 9. Checks `replaced_by` targets of deprecated packages and warns where
+//!    groups, profiles or templates still use deprecated packages or former
+//!    package names
+//! 10. Checks that package `conflicts` are symmetric and that no group
+//!     requires conflicting packages
+//! 11. Checks that group and profile choice slots offer known alternatives
+//!     of one another and default to one of them
+//! 12. Checks package `maintainers` handles and `last_verified` dates, and
+//!     warns about entries last verified more than `--stale-after` days ago
+//! 13. Checks `dependencies/` entries and where they contradict the package
+//!     files about a required dependency
+//! 14. Resolves profile inheritance (unknown parents, cycles, unknown packages,
+//!     conflicting packages) and checks dotfile paths, packages and target
+//!     collisions
+//! 15. Validates templates (including typed settings) and renders them
+//! 16. Lints profile hook commands
 //!
-//! Usage: cargo run --bin validate
+//! Steps 7-16 need the whole tree; if a file fails to load they are skipped
+//! and the load error is reported with the other findings.
+//!
+//! With `--fix`, missing `alternatives` back-links are first written into
+//! the package files.
+//!
+//! Usage: cargo run --bin validate [-- --fix] [--stale-after 365]
 
 use anyhow::{Context, Result};
+use clap::Parser;
 use colored::*;
+use heimdal_packages::alias::AliasIndex;
+use heimdal_packages::choice;
+use heimdal_packages::conflicts;
+use heimdal_packages::deprecation::{self, deprecated_uses};
+use heimdal_packages::dotfiles::path_errors;
+use heimdal_packages::graph::DependencyGraph;
+use heimdal_packages::hooks::{lint_hook, unused_allows};
+use heimdal_packages::loader::{load_packages, yaml_files, SourceTree};
+use heimdal_packages::maintenance::{self, DEFAULT_STALE_DAYS};
+use heimdal_packages::reconcile::reconcile;
+use heimdal_packages::relations;
+use heimdal_packages::resolve::{Origin, Resolver};
+use heimdal_packages::template::RenderedTemplate;
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 use std::fs;
@@ -32,6 +72,8 @@ struct Package {
     #[serde(default)]
     related: Vec<String>,
     tags: Vec<String>,
+    #[serde(default)]
+    renamed_from: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -70,20 +112,48 @@ struct GroupPackages {
     optional: Vec<String>,
 }
 
+#[derive(Debug, Parser)]
+#[command(about = "Validate the YAML package database")]
+struct Args {
+    /// Write missing `alternatives` back-links into package files first
+    #[arg(long)]
+    fix: bool,
+
+    /// Warn about packages last verified more than this many days ago
+    #[arg(long, value_name = "DAYS", default_value_t = DEFAULT_STALE_DAYS)]
+    stale_after: i64,
+}
+
 struct ValidationStats {
     packages_validated: usize,
     groups_validated: usize,
+    profiles_validated: usize,
+    templates_validated: usize,
     errors: Vec<String>,
     warnings: Vec<String>,
 }
 
 fn main() -> Result<()> {
+    let args = Args::parse();
+
     println!("{}", "Validating Heimdal Packages Database".bold().cyan());
     println!();
 
+    if args.fix {
+        print!("Adding alternatives back-links... ");
+        let links = relations::missing_back_links(&load_packages("packages")?);
+        let files = relations::add_back_links("packages", &links)?;
+        println!(
+            "{}",
+            format!("✓ {} links in {} files", links.len(), files).green()
+        );
+    }
+
     let mut stats = ValidationStats {
         packages_validated: 0,
         groups_validated: 0,
+        profiles_validated: 0,
+        templates_validated: 0,
         errors: Vec::new(),
         warnings: Vec::new(),
     };
@@ -92,6 +162,7 @@ fn main() -> Result<()> {
     print!("Loading JSON schemas... ");
     let package_schema_value = Box::leak(Box::new(load_schema("schemas/package.schema.json")?));
     let group_schema_value = Box::leak(Box::new(load_schema("schemas/group.schema.json")?));
+    let template_schema_value = Box::leak(Box::new(load_schema("schemas/template.schema.json")?));
 
     let package_schema = jsonschema::JSONSchema::options()
         .compile(package_schema_value)
@@ -99,6 +170,9 @@ fn main() -> Result<()> {
     let group_schema = jsonschema::JSONSchema::options()
         .compile(group_schema_value)
         .context("Failed to compile group schema")?;
+    let template_schema = jsonschema::JSONSchema::options()
+        .compile(template_schema_value)
+        .context("Failed to compile template schema")?;
     println!("{}", "✓".green());
 
     // Validate packages
@@ -126,11 +200,33 @@ fn main() -> Result<()> {
     validate_platform_coverage(&packages, &mut stats)?;
     println!("{}", "✓".green());
 
+    // The remaining checks need the whole tree, which only loads once every
+    // file parses; otherwise report why and print what was found so far
+    print!("Loading source tree... ");
+    match SourceTree::load(".") {
+        Ok(tree) => {
+            println!("{}", "✓".green());
+            validate_tree(
+                &tree,
+                &packages,
+                &template_schema,
+                args.stale_after,
+                &mut stats,
+            )?;
+        }
+        Err(e) => {
+            println!("{}", "✗ skipping checks of the whole tree".red());
+            stats.errors.push(format!("{:#}", e));
+        }
+    }
+
     // Print summary
     println!();
     println!("{}", "Validation Summary".bold());
     println!("  Packages: {}", stats.packages_validated);
     println!("  Groups: {}", stats.groups_validated);
+    println!("  Profiles: {}", stats.profiles_validated);
+    println!("  Templates: {}", stats.templates_validated);
 
     if !stats.warnings.is_empty() {
         println!();
@@ -160,6 +256,84 @@ fn main() -> Result<()> {
     Ok(())
 }
 
+/// Checks that need the whole source tree
+fn validate_tree(
+    tree: &SourceTree,
+    packages: &[Package],
+    template_schema: &jsonschema::JSONSchema,
+    stale_after: i64,
+    stats: &mut ValidationStats,
+) -> Result<()> {
+    // Check alternatives symmetry and categories
+    print!("Checking alternatives... ");
+    validate_alternatives(tree, stats)?;
+    println!("{}", "✓".green());
+
+    // Check aliases
+    print!("Checking aliases... ");
+    let aliases = validate_aliases(tree, stats)?;
+    println!("{}", format!("✓ {} names", aliases).green());
+
+    // Reconcile mappings with package platforms
+    print!("Reconciling mappings... ");
+    let discrepancies = validate_mappings(tree, stats)?;
+    println!("{}", format!("✓ {} discrepancies", discrepancies).green());
+
+    // Check deprecations
+    print!("Checking deprecations... ");
+    let deprecated = validate_deprecations(tree, stats)?;
+    println!(
+        "{}",
+        format!("✓ {} deprecated packages", deprecated).green()
+    );
+
+    // Check conflicts
+    print!("Checking conflicts... ");
+    let conflicting = validate_conflicts(tree, stats)?;
+    println!(
+        "{}",
+        format!("✓ {} conflicting packages", conflicting).green()
+    );
+
+    // Check choice slots
+    print!("Checking choices... ");
+    let slots = validate_choices(tree, stats)?;
+    println!("{}", format!("✓ {} choice slots", slots).green());
+
+    // Check maintainers and verification dates
+    print!("Checking maintenance... ");
+    let stale = validate_maintenance(tree, stale_after, stats)?;
+    println!("{}", format!("✓ {} stale packages", stale).green());
+
+    // Merge dependency files with package dependency lists
+    print!("Checking dependency files... ");
+    let contradictions = validate_dependency_files(tree, stats)?;
+    println!("{}", format!("✓ {} contradictions", contradictions).green());
+
+    // Resolve profiles
+    print!("Resolving profiles... ");
+    validate_profiles(tree, packages, stats)?;
+    println!(
+        "{}",
+        format!("✓ {} profiles", stats.profiles_validated).green()
+    );
+
+    // Render templates
+    print!("Rendering templates... ");
+    validate_templates(tree, template_schema, stats)?;
+    println!(
+        "{}",
+        format!("✓ {} templates", stats.templates_validated).green()
+    );
+
+    // Lint hooks
+    print!("Linting hooks... ");
+    let hooks = validate_hooks(tree, stats)?;
+    println!("{}", format!("✓ {} hooks", hooks).green());
+
+    Ok(())
+}
+
 fn load_schema(path: &str) -> Result<serde_json::Value> {
     let content =
         fs::read_to_string(path).with_context(|| format!("Failed to read schema: {}", path))?;
@@ -350,7 +524,12 @@ fn validate_cross_references(
         }
     }
 
-    // Validate group packages
+    // Validate group packages; former names still resolve, and are reported
+    // as deprecated uses
+    let package_names: HashSet<_> = packages
+        .iter()
+        .flat_map(|p| std::iter::once(&p.name).chain(&p.renamed_from))
+        .collect();
     for group in groups {
         for pkg_name in &group.packages.required {
             if !package_names.contains(pkg_name) {
@@ -373,6 +552,141 @@ fn validate_cross_references(
     Ok(())
 }
 
+fn validate_alternatives(tree: &SourceTree, stats: &mut ValidationStats) -> Result<()> {
+    for error in relations::check(&tree.packages) {
+        stats.errors.push(format!("Alternatives: {}", error));
+    }
+    for mismatch in relations::category_mismatches(&tree.packages) {
+        stats.warnings.push(format!("Alternatives: {}", mismatch));
+    }
+
+    Ok(())
+}
+
+fn validate_aliases(tree: &SourceTree, stats: &mut ValidationStats) -> Result<usize> {
+    let index = AliasIndex::from_tree(tree);
+
+    for collision in &index.collisions {
+        stats.errors.push(format!("Mapping {}", collision));
+    }
+
+    Ok(index.entries().len())
+}
+
+fn validate_deprecations(tree: &SourceTree, stats: &mut ValidationStats) -> Result<usize> {
+    for error in deprecation::check(&tree.packages) {
+        stats.errors.push(format!("Deprecation: {}", error));
+    }
+    for usage in deprecated_uses(tree) {
+        stats.warnings.push(format!("Deprecation: {}", usage));
+    }
+
+    Ok(tree.packages.iter().filter(|p| p.deprecated).count())
+}
+
+fn validate_maintenance(
+    tree: &SourceTree,
+    stale_after: i64,
+    stats: &mut ValidationStats,
+) -> Result<usize> {
+    let today = chrono::Local::now().date_naive();
+
+    for error in maintenance::check(&tree.packages, today) {
+        stats.errors.push(format!("Maintenance: {}", error));
+    }
+    let stale = maintenance::stale(&tree.packages, today, stale_after);
+    for entry in &stale {
+        stats.warnings.push(format!("Stale: {}", entry));
+    }
+
+    Ok(stale.len())
+}
+
+fn validate_mappings(tree: &SourceTree, stats: &mut ValidationStats) -> Result<usize> {
+    let discrepancies = reconcile(&tree.packages, &tree.mappings);
+
+    for discrepancy in &discrepancies {
+        stats.warnings.push(format!("Mapping: {}", discrepancy));
+    }
+
+    Ok(discrepancies.len())
+}
+
+fn validate_conflicts(tree: &SourceTree, stats: &mut ValidationStats) -> Result<usize> {
+    for error in conflicts::check(&tree.packages) {
+        stats.errors.push(format!("Conflict: {}", error));
+    }
+    for group in &tree.groups {
+        let required = group.packages.required.iter().map(String::as_str);
+        for conflict in conflicts::find(&tree.packages, required) {
+            stats.errors.push(format!(
+                "Group '{}' requires conflicting packages: {}",
+                group.id, conflict
+            ));
+        }
+    }
+
+    Ok(tree
+        .packages
+        .iter()
+        .filter(|p| !p.conflicts.is_empty())
+        .count())
+}
+
+fn validate_choices(tree: &SourceTree, stats: &mut ValidationStats) -> Result<usize> {
+    for group in &tree.groups {
+        let listed = group
+            .packages
+            .required
+            .iter()
+            .chain(&group.packages.optional);
+        for error in choice::check(&group.choices, listed, &tree.packages) {
+            stats.errors.push(format!("Group '{}' {}", group.id, error));
+        }
+    }
+    for profile in &tree.profiles {
+        let listed = profile.packages.sections().into_iter().flat_map(|(_, p)| p);
+        for error in choice::check(&profile.choices, listed, &tree.packages) {
+            stats
+                .errors
+                .push(format!("Profile '{}' {}", profile.id, error));
+        }
+    }
+
+    Ok(tree.groups.iter().map(|g| g.choices.len()).sum::<usize>()
+        + tree.profiles.iter().map(|p| p.choices.len()).sum::<usize>())
+}
+
+fn validate_dependency_files(tree: &SourceTree, stats: &mut ValidationStats) -> Result<usize> {
+    let graph = DependencyGraph::from_tree(tree);
+
+    for (package, dependencies) in &tree.dependencies {
+        if tree.package(package).is_none() {
+            stats.errors.push(format!(
+                "Dependency file entry for unknown package: {}",
+                package
+            ));
+        }
+        for dep in dependencies.required.iter().chain(&dependencies.optional) {
+            if tree.package(&dep.package).is_none() {
+                stats.errors.push(format!(
+                    "Dependency file entry {} has unknown dependency: {}",
+                    package, dep.package
+                ));
+            }
+        }
+    }
+
+    let contradictions = graph.contradictions();
+    for contradiction in &contradictions {
+        stats
+            .warnings
+            .push(format!("Dependencies: {}", contradiction));
+    }
+
+    Ok(contradictions.len())
+}
+
 fn validate_platform_coverage(packages: &[Package], stats: &mut ValidationStats) -> Result<()> {
     for package in packages {
         let mut platform_count = 0;
@@ -399,3 +713,202 @@ fn validate_platform_coverage(packages: &[Package], stats: &mut ValidationStats)
 
     Ok(())
 }
+
+fn validate_profiles(
+    tree: &SourceTree,
+    packages: &[Package],
+    stats: &mut ValidationStats,
+) -> Result<()> {
+    // Former names still resolve, and are reported as deprecated uses
+    let package_names: HashSet<_> = packages
+        .iter()
+        .flat_map(|p| std::iter::once(&p.name).chain(&p.renamed_from))
+        .map(String::as_str)
+        .collect();
+    let resolver = Resolver::from_tree(tree);
+    // Conflicts are inherited, so report each one once
+    let mut reported_conflicts = HashSet::new();
+
+    for profile in &tree.profiles {
+        let resolved = match resolver.resolve_profile(&profile.id) {
+            Ok(resolved) => resolved,
+            Err(e) => {
+                stats
+                    .errors
+                    .push(format!("Profile '{}': {}", profile.id, e));
+                continue;
+            }
+        };
+
+        for package in &resolved.packages {
+            if !package_names.contains(package.name.as_str()) {
+                stats.errors.push(format!(
+                    "Profile '{}' references unknown package: '{}' (from {})",
+                    profile.id,
+                    package.name,
+                    package.origin()
+                ));
+            }
+
+            // Listed again by the profile itself although already inherited
+            let own = |o: &Origin| matches!(o, Origin::Profile { id, .. } if *id == profile.id);
+            if package.sources.iter().any(own) && !own(package.origin()) {
+                stats.warnings.push(format!(
+                    "Profile '{}' lists '{}', already inherited from {}",
+                    profile.id,
+                    package.name,
+                    package.origin()
+                ));
+            }
+        }
+
+        for dotfile in &profile.dotfiles {
+            for error in path_errors(dotfile) {
+                stats.errors.push(format!(
+                    "Profile '{}' dotfile {}: {}",
+                    profile.id, dotfile.target, error
+                ));
+            }
+            match &dotfile.package {
+                None => stats.errors.push(format!(
+                    "Profile '{}' dotfile {} does not name the package it configures",
+                    profile.id, dotfile.target
+                )),
+                Some(package) if resolved.package(package).is_none() => stats.errors.push(format!(
+                    "Profile '{}' dotfile {} configures '{}', which the profile does not install",
+                    profile.id, dotfile.target, package
+                )),
+                Some(_) => {}
+            }
+        }
+        for conflict in &resolved.dotfile_conflicts {
+            if reported_conflicts.insert(conflict.to_string()) {
+                stats
+                    .errors
+                    .push(format!("Profile '{}': {}", profile.id, conflict));
+            }
+        }
+
+        for conflict in &resolved.package_conflicts {
+            if reported_conflicts.insert(conflict.to_string()) {
+                let origin = |name: &str| resolved.package(name).expect("resolved").origin();
+                stats.errors.push(format!(
+                    "Profile '{}' installs conflicting packages: {} (from {} and {})",
+                    profile.id,
+                    conflict,
+                    origin(&conflict.package),
+                    origin(&conflict.conflicts_with)
+                ));
+            }
+        }
+
+        stats.profiles_validated += 1;
+    }
+
+    Ok(())
+}
+
+fn validate_templates(
+    tree: &SourceTree,
+    schema: &jsonschema::JSONSchema,
+    stats: &mut ValidationStats,
+) -> Result<()> {
+    let mut schema_errors = false;
+    for path in yaml_files("templates") {
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let yaml_value: serde_yaml::Value = serde_yaml::from_str(&content)
+            .with_context(|| format!("Failed to parse YAML: {}", path.display()))?;
+        let json_value: serde_json::Value =
+            serde_json::to_value(&yaml_value).context("Failed to convert YAML to JSON")?;
+
+        if let Err(errors) = schema.validate(&json_value) {
+            for error in errors {
+                stats.errors.push(format!(
+                    "{}: {} (at {})",
+                    path.display(),
+                    error,
+                    error.instance_path
+                ));
+            }
+            schema_errors = true;
+        };
+    }
+    // Templates failing the schema (e.g. unknown settings keys) cannot be loaded
+    if schema_errors {
+        return Ok(());
+    }
+
+    for template in &tree.templates {
+        let rendered = match RenderedTemplate::render(tree, &template.id) {
+            Ok(rendered) => rendered,
+            Err(e) => {
+                stats
+                    .errors
+                    .push(format!("Template '{}': {}", template.id, e));
+                continue;
+            }
+        };
+
+        for package in &rendered.packages {
+            for manager in &package.unavailable {
+                stats.warnings.push(format!(
+                    "Template '{}' includes '{}', which has no {} name",
+                    template.id, package.name, manager
+                ));
+            }
+        }
+
+        stats.templates_validated += 1;
+    }
+
+    Ok(())
+}
+
+fn validate_hooks(tree: &SourceTree, stats: &mut ValidationStats) -> Result<usize> {
+    let resolver = Resolver::from_tree(tree);
+    let mut linted = 0;
+
+    for profile in &tree.profiles {
+        // Unresolvable profiles are already reported
+        let Ok(resolved) = resolver.resolve_profile(&profile.id) else {
+            continue;
+        };
+        let binaries: HashSet<&str> = resolved
+            .names()
+            .filter_map(|name| tree.package(name))
+            .flat_map(|package| package.provided_binaries())
+            .collect();
+
+        for (stage, hooks) in [
+            ("pre_install", &profile.hooks.pre_install),
+            ("post_install", &profile.hooks.post_install),
+        ] {
+            for hook in hooks {
+                let context = format!("Profile '{}' {} hook `{}`", profile.id, stage, hook.command);
+                match lint_hook(hook, &binaries) {
+                    Ok(findings) => {
+                        for finding in findings {
+                            let message = format!("{}: {}", context, finding);
+                            if finding.lint.is_error() {
+                                stats.errors.push(message);
+                            } else {
+                                stats.warnings.push(message);
+                            }
+                        }
+                    }
+                    Err(e) => stats.errors.push(format!("{}: {}", context, e)),
+                }
+                for lint in unused_allows(hook, &binaries) {
+                    stats.warnings.push(format!(
+                        "{}: allows '{}' but nothing triggers it",
+                        context, lint
+                    ));
+                }
+                linted += 1;
+            }
+        }
+    }
+
+    Ok(linted)
+}
@@ -0,0 +1,75 @@
+//! Verify a signed database release
+//!
+//! This script:
+//! 1. Loads the minisign public key given with `--public-key`
+//! 2. Checks the detached signature before reading the database
+//! 3. Deserializes the database only once the signature is valid
+//!
+//! Usage: cargo run --bin verify -- [DB] [--signature SIG] --public-key KEY
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use colored::*;
+use heimdal_packages::signature::{verify_database, PublicKey, Signature};
+use std::fs;
+use std::path::PathBuf;
+
+#[derive(Debug, Parser)]
+#[command(about = "Verify the minisign signature of a compiled database")]
+struct Args {
+    /// Database to verify (raw or .zst)
+    #[arg(default_value = "target/packages.db")]
+    database: PathBuf,
+
+    /// Detached signature (defaults to <DATABASE>.minisig)
+    #[arg(long)]
+    signature: Option<PathBuf>,
+
+    /// Minisign public key file of the release signer
+    #[arg(long)]
+    public_key: PathBuf,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+
+    println!("{}", "Verifying Heimdal Packages Database".bold().cyan());
+    println!();
+
+    let signature_path = args.signature.unwrap_or_else(|| {
+        let mut path = args.database.clone().into_os_string();
+        path.push(".minisig");
+        PathBuf::from(path)
+    });
+
+    print!("Loading public key... ");
+    let public_key = fs::read_to_string(&args.public_key)
+        .with_context(|| format!("Failed to read {}", args.public_key.display()))?;
+    let public_key = PublicKey::from_minisign(&public_key)?;
+    println!("{}", format!("✓ key {}", public_key.key_id()).green());
+
+    print!("Loading signature... ");
+    let signature = fs::read_to_string(&signature_path)
+        .with_context(|| format!("Failed to read {}", signature_path.display()))?;
+    let signature = Signature::from_minisign(&signature)?;
+    println!("{}", "✓".green());
+
+    print!("Checking signature... ");
+    let data = fs::read(&args.database)
+        .with_context(|| format!("Failed to read {}", args.database.display()))?;
+    match verify_database(&data, &signature, &public_key) {
+        Ok(db) => {
+            println!("{}", "✓ Signature valid".green());
+            println!();
+            println!("  Trusted comment: {}", signature.trusted_comment);
+            println!("  Version: {}", db.version);
+            println!("  Packages: {}", db.packages.len());
+            println!("  Groups: {}", db.groups.len());
+            Ok(())
+        }
+        Err(e) => {
+            println!("{}", format!("✗ {}", e).red().bold());
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,50 @@
+//! Show everything that refers to a package
+//!
+//! This script:
+//! 1. Reads a compiled `packages.db` (raw or zstd-compressed)
+//! 2. Resolves the package name, alias or platform name to its canonical package
+//! 3. Lists its direct and transitive dependents, the groups, profiles,
+//!    templates and suggestion patterns including it, and the packages naming
+//!    it in `alternatives` or `related`
+//!
+//! Usage: cargo run --bin why -- <PACKAGE> [--db target/packages.db] [--format text|json]
+
+use anyhow::Result;
+use clap::{Parser, ValueEnum};
+use heimdal_packages::database::CompiledDatabase;
+use heimdal_packages::rdeps::ReverseDependencies;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Format {
+    Text,
+    Json,
+}
+
+#[derive(Debug, Parser)]
+#[command(about = "Show what depends on or includes a package")]
+struct Args {
+    /// Package name, alias or platform name
+    package: String,
+
+    /// Compiled database to query
+    #[arg(long, default_value = "target/packages.db")]
+    db: PathBuf,
+
+    #[arg(long, value_enum, default_value = "text")]
+    format: Format,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+
+    let db = CompiledDatabase::read(&args.db)?;
+    let rdeps = ReverseDependencies::query(&db, &args.package)?;
+
+    match args.format {
+        Format::Text => print!("{}", rdeps.to_text()),
+        Format::Json => println!("{}", serde_json::to_string_pretty(&rdeps)?),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,239 @@
+//! Alias-aware package name resolution
+//!
+//! Users and package lists rarely use our canonical names: a Brewfile says
+//! `git-core`, a Makefile wants `gmake`, an apt list has `docker.io`. The
+//! alias index maps every `aliases` entry in `mappings/`, every former name
+//! in a package's `renamed_from` and every native name in a package's
+//! `platforms` back to the canonical package.
+//!
+//! Canonical names win over former names and aliases, and those over platform
+//! names. A platform name shared by two packages (on different managers) is
+//! ambiguous and only resolves through [`AliasIndex::resolve_on`].
+
+use crate::loader::SourceTree;
+use crate::model::{Manager, Mapping, Package};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+/// How a name relates to its canonical package
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NameKind {
+    Canonical,
+    Alias,
+    /// Former name, from the package's `renamed_from`
+    Renamed,
+    /// Native name on these managers
+    Platform(Vec<Manager>),
+}
+
+/// A name resolved to its canonical package
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AliasEntry {
+    pub canonical: String,
+    pub kind: NameKind,
+}
+
+/// An alias that would make a name ambiguous
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AliasCollision {
+    /// The alias is another package's canonical name
+    PackageName { alias: String, canonical: String },
+    /// The alias is another package's native name on `manager`
+    PlatformName {
+        alias: String,
+        canonical: String,
+        owner: String,
+        manager: Manager,
+    },
+    /// Two packages claim the same alias
+    Duplicate {
+        alias: String,
+        first: String,
+        second: String,
+    },
+}
+
+impl fmt::Display for AliasCollision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AliasCollision::PackageName { alias, canonical } => write!(
+                f,
+                "alias '{}' of '{}' is the name of another package",
+                alias, canonical
+            ),
+            AliasCollision::PlatformName {
+                alias,
+                canonical,
+                owner,
+                manager,
+            } => write!(
+                f,
+                "alias '{}' of '{}' is the {} name of '{}'",
+                alias, canonical, manager, owner
+            ),
+            AliasCollision::Duplicate {
+                alias,
+                first,
+                second,
+            } => write!(
+                f,
+                "alias '{}' is claimed by both '{}' and '{}'",
+                alias, first, second
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AliasIndex {
+    packages: BTreeSet<String>,
+    /// Aliases and unambiguous platform names
+    names: BTreeMap<String, AliasEntry>,
+    /// Native name → canonical, per manager
+    platforms: BTreeMap<Manager, BTreeMap<String, String>>,
+    pub collisions: Vec<AliasCollision>,
+}
+
+impl AliasIndex {
+    pub fn build(packages: &[Package], mappings: &[Mapping]) -> Self {
+        let mut index = AliasIndex {
+            packages: packages.iter().map(|p| p.name.clone()).collect(),
+            ..Default::default()
+        };
+
+        // Former names first, so a rename cannot be shadowed by an alias
+        let renames = packages.iter().flat_map(|p| {
+            p.renamed_from
+                .iter()
+                .map(move |old| (old, &p.name, NameKind::Renamed))
+        });
+        let aliases = mappings.iter().flat_map(|m| {
+            m.aliases
+                .iter()
+                .map(move |alias| (alias, &m.canonical, NameKind::Alias))
+        });
+        for (alias, canonical, kind) in renames.chain(aliases) {
+            if alias == canonical {
+                continue;
+            }
+            if index.packages.contains(alias) {
+                index.collisions.push(AliasCollision::PackageName {
+                    alias: alias.clone(),
+                    canonical: canonical.clone(),
+                });
+                continue;
+            }
+            match index.names.get(alias) {
+                Some(existing) if existing.canonical != *canonical => {
+                    index.collisions.push(AliasCollision::Duplicate {
+                        alias: alias.clone(),
+                        first: existing.canonical.clone(),
+                        second: canonical.clone(),
+                    });
+                }
+                Some(_) => {}
+                None => {
+                    index.names.insert(
+                        alias.clone(),
+                        AliasEntry {
+                            canonical: canonical.clone(),
+                            kind,
+                        },
+                    );
+                }
+            }
+        }
+
+        // Native names from packages, then from mappings for managers the
+        // package itself leaves empty
+        let by_canonical: BTreeMap<&str, &Mapping> =
+            mappings.iter().map(|m| (m.canonical.as_str(), m)).collect();
+        let mut native: BTreeMap<&str, BTreeMap<&str, Vec<Manager>>> = BTreeMap::new();
+        for package in packages {
+            let mapping = by_canonical.get(package.name.as_str());
+            for manager in Manager::ALL {
+                let name = package
+                    .platforms
+                    .get(manager)
+                    .or_else(|| mapping.and_then(|m| m.platforms.get(manager)));
+                let Some(name) = name else {
+                    continue;
+                };
+                index
+                    .platforms
+                    .entry(manager)
+                    .or_default()
+                    .entry(name.to_string())
+                    .or_insert_with(|| package.name.clone());
+                native
+                    .entry(name)
+                    .or_default()
+                    .entry(&package.name)
+                    .or_default()
+                    .push(manager);
+            }
+        }
+        for (name, owners) in native {
+            if let Some(entry) = index.names.get(name) {
+                for (owner, managers) in &owners {
+                    if *owner != entry.canonical {
+                        index.collisions.push(AliasCollision::PlatformName {
+                            alias: name.to_string(),
+                            canonical: entry.canonical.clone(),
+                            owner: owner.to_string(),
+                            manager: managers[0],
+                        });
+                    }
+                }
+                continue;
+            }
+            if owners.len() > 1 || index.packages.contains(name) {
+                continue;
+            }
+            let (canonical, managers) = owners.into_iter().next().expect("one owner");
+            index.names.insert(
+                name.to_string(),
+                AliasEntry {
+                    canonical: canonical.to_string(),
+                    kind: NameKind::Platform(managers),
+                },
+            );
+        }
+
+        index
+    }
+
+    pub fn from_tree(tree: &SourceTree) -> Self {
+        Self::build(&tree.packages, &tree.mappings)
+    }
+
+    /// Resolve a canonical name, alias or unambiguous platform name
+    pub fn resolve(&self, name: &str) -> Option<AliasEntry> {
+        if self.packages.contains(name) {
+            return Some(AliasEntry {
+                canonical: name.to_string(),
+                kind: NameKind::Canonical,
+            });
+        }
+        self.names.get(name).cloned()
+    }
+
+    /// Resolve a name as found on `manager` (e.g. a `brew list` entry):
+    /// that manager's native names first, then [`AliasIndex::resolve`]
+    pub fn resolve_on(&self, manager: Manager, name: &str) -> Option<AliasEntry> {
+        match self.platforms.get(&manager).and_then(|m| m.get(name)) {
+            Some(canonical) if canonical != name => Some(AliasEntry {
+                canonical: canonical.clone(),
+                kind: NameKind::Platform(vec![manager]),
+            }),
+            _ => self.resolve(name),
+        }
+    }
+
+    /// Aliases and unambiguous platform names, as stored in `packages.db`
+    pub fn entries(&self) -> &BTreeMap<String, AliasEntry> {
+        &self.names
+    }
+}
@@ -0,0 +1,321 @@
+//! Ansible role export
+//!
+//! Turns a [`PackageSet`] into a role: `vars/main.yml` holds the native
+//! package names per manager, `tasks/main.yml` installs them with the module
+//! matching `ansible_os_family` (falling back to `ansible.builtin.package`
+//! with canonical names), post-install hooks become handlers notified by the
+//! install tasks, and dotfiles are copied from the role's `files/` directory.
+
+use crate::export::PackageSet;
+use crate::model::{Hook, Manager};
+use serde::Serialize;
+use serde_yaml::{Mapping, Value};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// `ansible_os_family`, install module and whether it needs `become`
+fn family(manager: Manager) -> (&'static str, &'static str, bool) {
+    match manager {
+        Manager::Apt => ("Debian", "ansible.builtin.apt", true),
+        Manager::Dnf => ("RedHat", "ansible.builtin.dnf", true),
+        Manager::Pacman => ("Archlinux", "community.general.pacman", true),
+        Manager::Brew => ("Darwin", "community.general.homebrew", false),
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Task {
+    name: String,
+    #[serde(flatten)]
+    module: BTreeMap<&'static str, Value>,
+    #[serde(rename = "become", skip_serializing_if = "Option::is_none")]
+    become_root: Option<bool>,
+    #[serde(rename = "loop", skip_serializing_if = "Option::is_none")]
+    loop_items: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    when: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    notify: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    listen: Option<String>,
+}
+
+impl Task {
+    fn new(name: impl Into<String>, module: &'static str, args: Value) -> Self {
+        Task {
+            name: name.into(),
+            module: BTreeMap::from([(module, args)]),
+            become_root: None,
+            loop_items: None,
+            when: None,
+            notify: None,
+            listen: None,
+        }
+    }
+}
+
+fn mapping<const N: usize>(entries: [(&str, Value); N]) -> Value {
+    let mut map = Mapping::new();
+    for (key, value) in entries {
+        map.insert(Value::from(key), value);
+    }
+    Value::Mapping(map)
+}
+
+fn strings<'a>(items: impl IntoIterator<Item = &'a str>) -> Value {
+    Value::Sequence(items.into_iter().map(Value::from).collect())
+}
+
+fn shell_task(hook: &Hook) -> Task {
+    let name = hook
+        .description
+        .clone()
+        .unwrap_or_else(|| hook.command.clone());
+    Task::new(
+        name,
+        "ansible.builtin.shell",
+        Value::from(hook.command.as_str()),
+    )
+}
+
+fn document<T: Serialize>(value: &T) -> String {
+    format!(
+        "---\n{}",
+        serde_yaml::to_string(value).expect("role documents serialize")
+    )
+}
+
+/// A generated role: file contents keyed by path inside the role
+#[derive(Debug, Clone)]
+pub struct AnsibleRole {
+    pub name: String,
+    pub files: BTreeMap<String, String>,
+    /// Dotfile sources the user must place in `files/`
+    pub dotfile_sources: Vec<String>,
+}
+
+impl AnsibleRole {
+    pub fn build(set: &PackageSet) -> Self {
+        let name = set.selection.id().replace('-', "_");
+        let var = |suffix: &str| format!("{}_{}", name, suffix);
+        let optional = var("install_optional");
+        let installed = format!("{} packages installed", name);
+        let has_optional = set.packages.iter().any(|p| !p.required);
+
+        let mut files = BTreeMap::new();
+
+        // Package names per manager
+        let mut names = Mapping::new();
+        for &manager in &set.managers {
+            let mut entry = Mapping::new();
+            entry.insert("required".into(), strings(set.names(manager, true)));
+            if has_optional {
+                entry.insert("optional".into(), strings(set.names(manager, false)));
+            }
+            names.insert(manager.as_str().into(), Value::Mapping(entry));
+        }
+        if !set.casks().is_empty() {
+            names.insert("casks".into(), strings(set.casks()));
+        }
+        let linux = set.managers.iter().any(|m| *m != Manager::Brew);
+        if linux {
+            let canonical = |required: bool| {
+                strings(
+                    set.packages
+                        .iter()
+                        .filter(|p| p.required == required)
+                        .map(|p| p.name.as_str()),
+                )
+            };
+            let mut entry = Mapping::new();
+            entry.insert("required".into(), canonical(true));
+            if has_optional {
+                entry.insert("optional".into(), canonical(false));
+            }
+            names.insert("package".into(), Value::Mapping(entry));
+        }
+        let mut vars = Mapping::new();
+        vars.insert(var("packages").into(), Value::Mapping(names));
+        if !set.dotfiles.is_empty() {
+            let dotfiles = set
+                .dotfiles
+                .iter()
+                .map(|d| {
+                    mapping([
+                        ("src", d.source.as_str().into()),
+                        ("dest", d.target.trim_start_matches("~/").into()),
+                    ])
+                })
+                .collect();
+            vars.insert(var("dotfiles").into(), Value::Sequence(dotfiles));
+        }
+        files.insert("vars/main.yml".to_string(), document(&vars));
+
+        if has_optional {
+            let defaults = mapping([(optional.as_str(), false.into())]);
+            files.insert("defaults/main.yml".to_string(), document(&defaults));
+        }
+
+        // Tasks
+        let mut tasks: Vec<Task> = set.hooks.pre_install.iter().map(shell_task).collect();
+        let list = |key: &str| {
+            let required = format!("{}.{}.required", var("packages"), key);
+            if has_optional {
+                format!(
+                    "{{{{ {} + ({}.{}.optional if {} | bool else []) }}}}",
+                    required,
+                    var("packages"),
+                    key,
+                    optional
+                )
+            } else {
+                format!("{{{{ {} }}}}", required)
+            }
+        };
+        let notify = (!set.hooks.post_install.is_empty()).then(|| installed.clone());
+        let mut families = Vec::new();
+        for &manager in &set.managers {
+            let (os_family, module, become_root) = family(manager);
+            families.push(format!("\"{}\"", os_family));
+
+            let mut args = Mapping::new();
+            args.insert("name".into(), list(manager.as_str()).into());
+            args.insert("state".into(), "present".into());
+            if matches!(manager, Manager::Apt | Manager::Pacman) {
+                args.insert("update_cache".into(), true.into());
+            }
+            let mut task = Task::new(
+                format!("Install packages ({})", manager),
+                module,
+                Value::Mapping(args),
+            );
+            task.become_root = become_root.then_some(true);
+            task.when = Some(format!("ansible_os_family == \"{}\"", os_family));
+            task.notify = notify.clone();
+            tasks.push(task);
+        }
+        if !set.casks().is_empty() {
+            let mut task = Task::new(
+                "Install casks (brew)",
+                "community.general.homebrew_cask",
+                mapping([
+                    (
+                        "name",
+                        format!("{{{{ {}.casks }}}}", var("packages")).into(),
+                    ),
+                    ("state", "present".into()),
+                ]),
+            );
+            task.when = Some("ansible_os_family == \"Darwin\"".to_string());
+            task.notify = notify.clone();
+            tasks.push(task);
+        }
+        if linux {
+            let mut task = Task::new(
+                "Install packages (other distributions)",
+                "ansible.builtin.package",
+                mapping([
+                    ("name", list("package").into()),
+                    ("state", "present".into()),
+                ]),
+            );
+            task.become_root = Some(true);
+            task.when = Some(format!(
+                "ansible_os_family not in [{}]",
+                families.join(", ")
+            ));
+            task.notify = notify;
+            tasks.push(task);
+        }
+
+        if !set.dotfiles.is_empty() {
+            let items = Some(Value::from(format!("{{{{ {} }}}}", var("dotfiles"))));
+            let mut parents = Task::new(
+                "Create dotfile directories",
+                "ansible.builtin.file",
+                mapping([
+                    (
+                        "path",
+                        "{{ ansible_env.HOME }}/{{ item.dest | dirname }}".into(),
+                    ),
+                    ("state", "directory".into()),
+                    ("mode", "0755".into()),
+                ]),
+            );
+            parents.loop_items = items.clone();
+            tasks.push(parents);
+
+            // A trailing slash copies a directory's contents into `dest`
+            let mut copy = Task::new(
+                "Install dotfiles",
+                "ansible.builtin.copy",
+                mapping([
+                    (
+                        "src",
+                        "{{ item.src }}{{ '/' if (role_path ~ '/files/' ~ item.src) is directory else '' }}"
+                            .into(),
+                    ),
+                    ("dest", "{{ ansible_env.HOME }}/{{ item.dest }}".into()),
+                    ("mode", "preserve".into()),
+                ]),
+            );
+            copy.loop_items = items;
+            tasks.push(copy);
+        }
+        files.insert("tasks/main.yml".to_string(), document(&tasks));
+
+        // Post-install hooks run once after any install task changed
+        if !set.hooks.post_install.is_empty() {
+            let handlers: Vec<Task> = set
+                .hooks
+                .post_install
+                .iter()
+                .map(|hook| {
+                    let mut handler = shell_task(hook);
+                    handler.listen = Some(installed.clone());
+                    handler
+                })
+                .collect();
+            files.insert("handlers/main.yml".to_string(), document(&handlers));
+        }
+
+        let meta = mapping([
+            (
+                "galaxy_info",
+                mapping([
+                    ("role_name", name.as_str().into()),
+                    ("author", "heimdal-packages".into()),
+                    ("description", set.description.as_str().into()),
+                    ("license", "MIT".into()),
+                    ("min_ansible_version", "2.14".into()),
+                ]),
+            ),
+            ("dependencies", Value::Sequence(Vec::new())),
+        ]);
+        files.insert("meta/main.yml".to_string(), document(&meta));
+
+        AnsibleRole {
+            name,
+            files,
+            dotfile_sources: set.dotfiles.iter().map(|d| d.source.clone()).collect(),
+        }
+    }
+
+    /// Write the role into `dir`, creating `files/` for the dotfiles
+    pub fn write(&self, dir: impl AsRef<Path>) -> io::Result<()> {
+        let dir = dir.as_ref();
+        for (path, content) in &self.files {
+            let path = dir.join(path);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(path, content)?;
+        }
+        if !self.dotfile_sources.is_empty() {
+            fs::create_dir_all(dir.join("files"))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,164 @@
+//! Choice slots: install one of several alternatives
+//!
+//! A group or profile may declare a slot such as `editor` with options
+//! `[neovim, helix, vim]` and a default. Resolution installs the user's pick
+//! for the slot, or the default when there is none. Options must be
+//! alternatives of one another, so a slot cannot offer unrelated tools;
+//! [`check`] enforces that and the rest of the slot's shape.
+
+use crate::model::{Choice, Package};
+use crate::resolve::ResolveError;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+
+/// User picks, by slot id
+pub type Choices = BTreeMap<String, String>;
+
+/// Parse a `SLOT=PACKAGE` command-line pick
+pub fn parse(arg: &str) -> Result<(String, String), String> {
+    match arg.split_once('=') {
+        Some((slot, package)) if !slot.is_empty() && !package.is_empty() => {
+            Ok((slot.to_string(), package.to_string()))
+        }
+        _ => Err(format!("expected SLOT=PACKAGE, got '{}'", arg)),
+    }
+}
+
+/// The option `choice` resolves to: the user's pick, or else its default
+pub fn pick<'a>(choice: &'a Choice, choices: &'a Choices) -> Result<&'a String, ResolveError> {
+    match choices.get(&choice.id) {
+        Some(pick) if !choice.options.contains(pick) => Err(ResolveError::InvalidChoice {
+            slot: choice.id.clone(),
+            package: pick.clone(),
+            options: choice.options.clone(),
+        }),
+        Some(pick) => Ok(pick),
+        None => Ok(&choice.default),
+    }
+}
+
+/// The option of every slot in `slots`, for a selection that declares them
+/// all (a group); a pick naming none of them is an error
+pub fn pick_all<'a>(
+    slots: &'a [Choice],
+    choices: &'a Choices,
+) -> Result<Vec<&'a String>, ResolveError> {
+    if let Some(slot) = choices
+        .keys()
+        .find(|slot| !slots.iter().any(|c| &c.id == *slot))
+    {
+        return Err(ResolveError::UnknownSlot(slot.clone()));
+    }
+    slots.iter().map(|c| pick(c, choices)).collect()
+}
+
+/// A malformed choice slot
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChoiceError {
+    /// Two slots of one group or profile share an id
+    DuplicateSlot { slot: String },
+    /// An option names no package
+    UnknownOption { slot: String, option: String },
+    /// `default` is not among the options
+    DefaultNotAnOption { slot: String, default: String },
+    /// Neither option lists the other in `alternatives`
+    NotAlternatives {
+        slot: String,
+        first: String,
+        second: String,
+    },
+    /// An option is also installed unconditionally, defeating the choice
+    AlsoListed { slot: String, option: String },
+}
+
+impl fmt::Display for ChoiceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChoiceError::DuplicateSlot { slot } => {
+                write!(f, "choice '{}' is declared more than once", slot)
+            }
+            ChoiceError::UnknownOption { slot, option } => {
+                write!(f, "choice '{}' offers unknown package '{}'", slot, option)
+            }
+            ChoiceError::DefaultNotAnOption { slot, default } => write!(
+                f,
+                "choice '{}' defaults to '{}', which is not one of its options",
+                slot, default
+            ),
+            ChoiceError::NotAlternatives {
+                slot,
+                first,
+                second,
+            } => write!(
+                f,
+                "choice '{}' offers '{}' and '{}', but neither lists the other in its alternatives",
+                slot, first, second
+            ),
+            ChoiceError::AlsoListed { slot, option } => write!(
+                f,
+                "choice '{}' offers '{}', which is also listed as a package",
+                slot, option
+            ),
+        }
+    }
+}
+
+/// Check the slots of one group or profile. `listed` are the packages the
+/// owner installs outside its slots.
+pub fn check<'a>(
+    choices: &[Choice],
+    listed: impl IntoIterator<Item = &'a String>,
+    packages: &[Package],
+) -> Vec<ChoiceError> {
+    let by_name: HashMap<&str, &Package> = packages.iter().map(|p| (p.name.as_str(), p)).collect();
+    let listed: HashSet<&String> = listed.into_iter().collect();
+    let lists = |a: &str, b: &str| {
+        by_name
+            .get(a)
+            .is_some_and(|p| p.alternatives.iter().any(|alt| alt == b))
+    };
+
+    let mut errors = Vec::new();
+    let mut seen = HashSet::new();
+    for choice in choices {
+        let slot = || choice.id.clone();
+        if !seen.insert(&choice.id) {
+            errors.push(ChoiceError::DuplicateSlot { slot: slot() });
+        }
+        if !choice.options.contains(&choice.default) {
+            errors.push(ChoiceError::DefaultNotAnOption {
+                slot: slot(),
+                default: choice.default.clone(),
+            });
+        }
+        for option in &choice.options {
+            if !by_name.contains_key(option.as_str()) {
+                errors.push(ChoiceError::UnknownOption {
+                    slot: slot(),
+                    option: option.clone(),
+                });
+            }
+            if listed.contains(option) {
+                errors.push(ChoiceError::AlsoListed {
+                    slot: slot(),
+                    option: option.clone(),
+                });
+            }
+        }
+        for (i, first) in choice.options.iter().enumerate() {
+            for second in &choice.options[i + 1..] {
+                let known =
+                    by_name.contains_key(first.as_str()) && by_name.contains_key(second.as_str());
+                if known && !lists(first, second) && !lists(second, first) {
+                    errors.push(ChoiceError::NotAlternatives {
+                        slot: slot(),
+                        first: first.clone(),
+                        second: second.clone(),
+                    });
+                }
+            }
+        }
+    }
+
+    errors
+}
@@ -0,0 +1,123 @@
+//! Packages that cannot be installed together
+//!
+//! A package lists the packages it cannot coexist with in `conflicts` (e.g.
+//! two distributions of the same engine). The relation must be declared on
+//! both sides; [`check`] enforces that, and [`find`] reports the conflicting
+//! pairs in a set of packages about to be installed.
+
+use crate::model::Package;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Two packages in one installation that conflict
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PackageConflict {
+    /// Listed first in the installation
+    pub package: String,
+    pub conflicts_with: String,
+}
+
+impl fmt::Display for PackageConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "'{}' conflicts with '{}'",
+            self.package, self.conflicts_with
+        )
+    }
+}
+
+/// A malformed `conflicts` declaration
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConflictError {
+    /// The conflicting package does not exist
+    Unknown { package: String, conflict: String },
+    /// A package conflicting with itself
+    SelfConflict { package: String },
+    /// `conflict` does not list `package` back
+    Asymmetric { package: String, conflict: String },
+}
+
+impl fmt::Display for ConflictError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConflictError::Unknown { package, conflict } => write!(
+                f,
+                "'{}' conflicts with unknown package '{}'",
+                package, conflict
+            ),
+            ConflictError::SelfConflict { package } => {
+                write!(f, "'{}' conflicts with itself", package)
+            }
+            ConflictError::Asymmetric { package, conflict } => write!(
+                f,
+                "'{}' conflicts with '{}', but '{}' does not list '{}' in its conflicts",
+                package, conflict, conflict, package
+            ),
+        }
+    }
+}
+
+/// Check that every conflict names another existing package, which lists
+/// the conflict back
+pub fn check(packages: &[Package]) -> Vec<ConflictError> {
+    let by_name: HashMap<&str, &Package> = packages.iter().map(|p| (p.name.as_str(), p)).collect();
+    let mut errors = Vec::new();
+
+    for package in packages {
+        for conflict in &package.conflicts {
+            if *conflict == package.name {
+                errors.push(ConflictError::SelfConflict {
+                    package: package.name.clone(),
+                });
+                continue;
+            }
+            match by_name.get(conflict.as_str()) {
+                None => errors.push(ConflictError::Unknown {
+                    package: package.name.clone(),
+                    conflict: conflict.clone(),
+                }),
+                Some(other) if !other.conflicts.contains(&package.name) => {
+                    errors.push(ConflictError::Asymmetric {
+                        package: package.name.clone(),
+                        conflict: conflict.clone(),
+                    })
+                }
+                Some(_) => {}
+            }
+        }
+    }
+
+    errors
+}
+
+/// Conflicting pairs among `names`, in installation order. A conflict
+/// declared on either side counts, so an asymmetric declaration is still
+/// caught.
+pub fn find<'a>(
+    packages: &[Package],
+    names: impl IntoIterator<Item = &'a str>,
+) -> Vec<PackageConflict> {
+    let by_name: HashMap<&str, &Package> = packages.iter().map(|p| (p.name.as_str(), p)).collect();
+    let declares = |a: &str, b: &str| {
+        by_name
+            .get(a)
+            .is_some_and(|p| p.conflicts.iter().any(|c| c == b))
+    };
+
+    let names: Vec<&str> = names.into_iter().collect();
+    let mut conflicts = Vec::new();
+    for (i, first) in names.iter().enumerate() {
+        for second in &names[i + 1..] {
+            if declares(first, second) || declares(second, first) {
+                conflicts.push(PackageConflict {
+                    package: first.to_string(),
+                    conflicts_with: second.to_string(),
+                });
+            }
+        }
+    }
+
+    conflicts
+}
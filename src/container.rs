@@ -0,0 +1,218 @@
+//! Dockerfile and devcontainer generation
+//!
+//! A [`PackageSet`] becomes one cached install layer for a base image family.
+//! Debian, Fedora and Arch use the `apt`, `dnf` and `pacman` names; Alpine
+//! uses the packages' `apk` names. A required package without a name on the
+//! family is an error rather than a silently smaller image.
+
+use crate::coverage::availability;
+use crate::export::PackageSet;
+use crate::loader::SourceTree;
+use crate::model::{Manager, Mapping, Package};
+use crate::reconcile::merged_platforms;
+use serde::Serialize;
+use std::fmt;
+use thiserror::Error;
+
+/// Base image family
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Family {
+    Debian,
+    Fedora,
+    Arch,
+    Alpine,
+}
+
+impl Family {
+    pub const ALL: [Family; 4] = [Family::Debian, Family::Fedora, Family::Arch, Family::Alpine];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Family::Debian => "debian",
+            Family::Fedora => "fedora",
+            Family::Arch => "arch",
+            Family::Alpine => "alpine",
+        }
+    }
+
+    /// Package manager whose names the family uses; Alpine has its own field
+    pub fn manager(&self) -> Option<Manager> {
+        match self {
+            Family::Debian => Some(Manager::Apt),
+            Family::Fedora => Some(Manager::Dnf),
+            Family::Arch => Some(Manager::Pacman),
+            Family::Alpine => None,
+        }
+    }
+
+    pub fn default_image(&self) -> &'static str {
+        match self {
+            Family::Debian => "debian:bookworm-slim",
+            Family::Fedora => "fedora:40",
+            Family::Arch => "archlinux:base",
+            Family::Alpine => "alpine:3.20",
+        }
+    }
+
+    /// The package's name on this family, from its `platforms` or mapping
+    pub fn package_name(&self, package: &Package, mapping: Option<&Mapping>) -> Option<String> {
+        match self.manager() {
+            Some(manager) => availability(package, mapping, manager).map(|a| a.name),
+            None => merged_platforms(package, mapping).apk,
+        }
+    }
+
+    /// `RUN` instruction installing `packages` with the manager's cache kept
+    /// in a BuildKit cache mount
+    fn install(&self, packages: &[String]) -> String {
+        let (mounts, command): (&[&str], &str) = match self {
+            Family::Debian => (
+                &["/var/cache/apt", "/var/lib/apt"],
+                "rm -f /etc/apt/apt.conf.d/docker-clean \\\n    && apt-get update \\\n    && apt-get install -y --no-install-recommends",
+            ),
+            Family::Fedora => (&["/var/cache/dnf"], "dnf install -y --setopt=keepcache=1"),
+            Family::Arch => (&["/var/cache/pacman/pkg"], "pacman -Syu --noconfirm --needed"),
+            Family::Alpine => (&["/etc/apk/cache"], "apk add --update-cache"),
+        };
+
+        let mut run = String::from("RUN");
+        for mount in mounts {
+            run.push_str(&format!(
+                " --mount=type=cache,target={},sharing=locked \\\n   ",
+                mount
+            ));
+        }
+        run.push(' ');
+        run.push_str(command);
+        for package in packages {
+            run.push_str(" \\\n        ");
+            run.push_str(package);
+        }
+        run.push('\n');
+        run
+    }
+}
+
+impl fmt::Display for Family {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for Family {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Family::ALL
+            .into_iter()
+            .find(|f| f.as_str() == s)
+            .ok_or_else(|| anyhow::anyhow!("Unknown image family: '{}'", s))
+    }
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ContainerError {
+    #[error("required packages have no {family} name: {}", .packages.join(", "))]
+    Unmapped {
+        family: Family,
+        packages: Vec<String>,
+    },
+}
+
+/// An install layer for one image family
+#[derive(Debug, Clone, Serialize)]
+pub struct ContainerSpec {
+    /// What the image was generated from, e.g. `group devops`
+    pub source: String,
+    pub family: Family,
+    pub image: String,
+    /// Native package names, sorted so the layer only changes with the list
+    pub packages: Vec<String>,
+    /// Optional packages left out for lack of a name on the family
+    pub skipped: Vec<String>,
+    /// Post-install hooks, run when the devcontainer is created
+    pub post_create: Vec<String>,
+}
+
+impl ContainerSpec {
+    pub fn build(
+        tree: &SourceTree,
+        set: &PackageSet,
+        family: Family,
+        include_optional: bool,
+    ) -> Result<Self, ContainerError> {
+        let mut packages = Vec::new();
+        let mut unmapped = Vec::new();
+        let mut skipped = Vec::new();
+        for package in &set.packages {
+            if !package.required && !include_optional {
+                continue;
+            }
+            let name = tree
+                .package(&package.name)
+                .and_then(|p| family.package_name(p, tree.mapping(&p.name)));
+            match name {
+                Some(name) => packages.push(name),
+                None if package.required => unmapped.push(package.name.clone()),
+                None => skipped.push(package.name.clone()),
+            }
+        }
+        if !unmapped.is_empty() {
+            return Err(ContainerError::Unmapped {
+                family,
+                packages: unmapped,
+            });
+        }
+
+        if let Some(overrides) = set.platform_overrides.get("linux") {
+            packages.extend(overrides.packages.iter().cloned());
+        }
+        packages.sort();
+        packages.dedup();
+
+        Ok(ContainerSpec {
+            source: set.selection.to_string(),
+            family,
+            image: family.default_image().to_string(),
+            packages,
+            skipped,
+            post_create: set
+                .hooks
+                .post_install
+                .iter()
+                .map(|h| h.command.clone())
+                .collect(),
+        })
+    }
+
+    pub fn with_image(mut self, image: impl Into<String>) -> Self {
+        self.image = image.into();
+        self
+    }
+
+    pub fn dockerfile(&self) -> String {
+        let mut out = String::from("# syntax=docker/dockerfile:1\n");
+        out.push_str(&format!(
+            "# Generated by heimdal-packages from {}\n",
+            self.source
+        ));
+        out.push_str(&format!("FROM {}\n\n", self.image));
+        if !self.packages.is_empty() {
+            out.push_str(&self.family.install(&self.packages));
+        }
+        out
+    }
+
+    /// `devcontainer.json` building the Dockerfile next to it
+    pub fn devcontainer(&self) -> serde_json::Value {
+        let mut value = serde_json::json!({
+            "name": self.source,
+            "build": { "dockerfile": "Dockerfile" },
+        });
+        if !self.post_create.is_empty() {
+            value["postCreateCommand"] = self.post_create.join(" && ").into();
+        }
+        value
+    }
+}
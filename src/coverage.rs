@@ -0,0 +1,424 @@
+//! Platform coverage matrix and gap report
+//!
+//! A package is available on a manager when its own `platforms` entry names
+//! it, or failing that when its `mappings/` entry does. Groups may further
+//! cover a package through `platform_overrides` (e.g. a Homebrew cask).
+
+use crate::loader::SourceTree;
+use crate::model::{Manager, Mapping, Package, PackageGroup};
+use crate::resolve::{ResolveError, ResolvedProfile, Resolver};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Write;
+
+/// Where a platform name was found
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Source {
+    Package,
+    Mapping,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Availability {
+    pub name: String,
+    pub source: Source,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MatrixRow {
+    pub package: String,
+    pub platforms: BTreeMap<Manager, Availability>,
+}
+
+/// Package × platform matrix over the whole tree
+#[derive(Debug, Clone, Serialize)]
+pub struct CoverageMatrix {
+    pub rows: Vec<MatrixRow>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ManagerCoverage {
+    pub manager: Manager,
+    pub available: usize,
+    pub missing: Vec<String>,
+}
+
+/// Coverage of one group or profile
+#[derive(Debug, Clone, Serialize)]
+pub struct SetCoverage {
+    pub kind: &'static str,
+    pub id: String,
+    pub total: usize,
+    pub managers: Vec<ManagerCoverage>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CoverageReport {
+    pub matrix: CoverageMatrix,
+    pub groups: Vec<SetCoverage>,
+    pub profiles: Vec<SetCoverage>,
+}
+
+/// Resolve the platform name of `package` on `manager`
+pub fn availability(
+    package: &Package,
+    mapping: Option<&Mapping>,
+    manager: Manager,
+) -> Option<Availability> {
+    if let Some(name) = package.platforms.get(manager) {
+        return Some(Availability {
+            name: name.to_string(),
+            source: Source::Package,
+        });
+    }
+
+    mapping
+        .and_then(|m| m.platforms.get(manager))
+        .map(|name| Availability {
+            name: name.to_string(),
+            source: Source::Mapping,
+        })
+}
+
+impl CoverageMatrix {
+    pub fn build(tree: &SourceTree) -> Self {
+        let mut rows: Vec<MatrixRow> = tree
+            .packages
+            .iter()
+            .map(|pkg| {
+                let mapping = tree.mapping(&pkg.name);
+                let platforms = Manager::ALL
+                    .into_iter()
+                    .filter_map(|m| availability(pkg, mapping, m).map(|a| (m, a)))
+                    .collect();
+                MatrixRow {
+                    package: pkg.name.clone(),
+                    platforms,
+                }
+            })
+            .collect();
+        rows.sort_by(|a, b| a.package.cmp(&b.package));
+
+        CoverageMatrix { rows }
+    }
+
+    pub fn row(&self, package: &str) -> Option<&MatrixRow> {
+        self.rows.iter().find(|r| r.package == package)
+    }
+
+    /// Packages with no name on `manager`
+    pub fn missing(&self, manager: Manager) -> Vec<&str> {
+        self.rows
+            .iter()
+            .filter(|r| !r.platforms.contains_key(&manager))
+            .map(|r| r.package.as_str())
+            .collect()
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut out = String::from("package");
+        for manager in Manager::ALL {
+            out.push(',');
+            out.push_str(manager.as_str());
+        }
+        out.push('\n');
+
+        for row in &self.rows {
+            out.push_str(&csv_field(&row.package));
+            for manager in Manager::ALL {
+                out.push(',');
+                if let Some(a) = row.platforms.get(&manager) {
+                    out.push_str(&csv_field(&a.name));
+                }
+            }
+            out.push('\n');
+        }
+
+        out
+    }
+}
+
+impl ManagerCoverage {
+    pub fn percent(&self, total: usize) -> f64 {
+        if total == 0 {
+            return 100.0;
+        }
+        self.available as f64 / total as f64 * 100.0
+    }
+}
+
+impl SetCoverage {
+    fn build<'a>(
+        kind: &'static str,
+        id: &str,
+        packages: impl Iterator<Item = &'a str>,
+        matrix: &CoverageMatrix,
+        groups: &[&PackageGroup],
+    ) -> Self {
+        let mut seen = HashSet::new();
+        let packages: Vec<&str> = packages.filter(|p| seen.insert(*p)).collect();
+
+        let managers = Manager::ALL
+            .into_iter()
+            .map(|manager| {
+                let missing: Vec<String> = packages
+                    .iter()
+                    .filter(|name| {
+                        let in_matrix = matrix
+                            .row(name)
+                            .is_some_and(|r| r.platforms.contains_key(&manager));
+                        !in_matrix && !groups.iter().any(|g| overrides(g, manager, name))
+                    })
+                    .map(|name| name.to_string())
+                    .collect();
+                ManagerCoverage {
+                    manager,
+                    available: packages.len() - missing.len(),
+                    missing,
+                }
+            })
+            .collect();
+
+        SetCoverage {
+            kind,
+            id: id.to_string(),
+            total: packages.len(),
+            managers,
+        }
+    }
+
+    pub fn for_group(group: &PackageGroup, matrix: &CoverageMatrix) -> Self {
+        Self::build(
+            "group",
+            &group.id,
+            group.all_packages().map(String::as_str),
+            matrix,
+            &[group],
+        )
+    }
+
+    /// Coverage of a profile's packages, including inherited ones, with the
+    /// `platform_overrides` of `groups` (see [`ResolvedProfile::groups`])
+    pub fn for_profile(
+        profile: &ResolvedProfile,
+        groups: &[&PackageGroup],
+        matrix: &CoverageMatrix,
+    ) -> Self {
+        Self::build("profile", &profile.id, profile.names(), matrix, groups)
+    }
+
+    /// Packages missing on at least one manager, in first-seen order
+    pub fn missing(&self) -> Vec<&str> {
+        let mut seen = HashSet::new();
+        self.managers
+            .iter()
+            .flat_map(|m| m.missing.iter())
+            .filter(|name| seen.insert(name.as_str()))
+            .map(|name| name.as_str())
+            .collect()
+    }
+
+    /// One-line summary, e.g. `devops group: 100% brew, 88% dnf, missing: k9s`
+    pub fn summary(&self) -> String {
+        let percentages: Vec<String> = self
+            .managers
+            .iter()
+            .map(|m| format!("{:.0}% {}", m.percent(self.total), m.manager))
+            .collect();
+        let mut line = format!("{} {}: {}", self.id, self.kind, percentages.join(", "));
+
+        let missing = self.missing();
+        if !missing.is_empty() {
+            let _ = write!(line, ", missing: {}", missing.join(", "));
+        }
+
+        line
+    }
+}
+
+/// Whether a group's `platform_overrides` provide `package` on `manager`
+fn overrides(group: &PackageGroup, manager: Manager, package: &str) -> bool {
+    group
+        .platform_overrides
+        .get(manager.override_key())
+        .is_some_and(|o| {
+            o.packages.iter().any(|p| p == package)
+                || (manager == Manager::Brew && o.casks.iter().any(|c| c == package))
+        })
+}
+
+impl CoverageReport {
+    pub fn build(tree: &SourceTree) -> Result<Self, ResolveError> {
+        let matrix = CoverageMatrix::build(tree);
+        let resolver = Resolver::from_tree(tree);
+        let groups = tree
+            .groups
+            .iter()
+            .map(|g| SetCoverage::for_group(g, &matrix))
+            .collect();
+        let profiles = tree
+            .profiles
+            .iter()
+            .map(|p| {
+                let resolved = resolver.resolve_profile(&p.id)?;
+                let groups = resolved.groups(tree);
+                Ok(SetCoverage::for_profile(&resolved, &groups, &matrix))
+            })
+            .collect::<Result<_, ResolveError>>()?;
+
+        Ok(CoverageReport {
+            matrix,
+            groups,
+            profiles,
+        })
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::new();
+        let total = self.matrix.rows.len();
+
+        let _ = writeln!(out, "# Platform Coverage Report\n");
+
+        let _ = writeln!(out, "## Missing Packages\n");
+        for manager in Manager::ALL {
+            let missing = self.matrix.missing(manager);
+            let percent = (total - missing.len()) as f64 / total.max(1) as f64 * 100.0;
+            if missing.is_empty() {
+                let _ = writeln!(out, "- **{}** ({:.0}%): none", manager, percent);
+            } else {
+                let _ = writeln!(
+                    out,
+                    "- **{}** ({:.0}%): {}",
+                    manager,
+                    percent,
+                    missing.join(", ")
+                );
+            }
+        }
+        out.push('\n');
+
+        for (title, sets) in [("Groups", &self.groups), ("Profiles", &self.profiles)] {
+            let _ = writeln!(out, "## {}\n", title);
+            out.push_str(&markdown_set_table(sets));
+            out.push('\n');
+        }
+
+        let _ = writeln!(out, "## Matrix\n");
+        out.push_str("| Package |");
+        for manager in Manager::ALL {
+            let _ = write!(out, " {} |", manager);
+        }
+        out.push_str("\n|---------|");
+        for _ in Manager::ALL {
+            out.push_str("-----|");
+        }
+        out.push('\n');
+        for row in &self.matrix.rows {
+            let _ = write!(out, "| {} |", row.package);
+            for manager in Manager::ALL {
+                let _ = write!(out, " {} |", cell_text(row.platforms.get(&manager)));
+            }
+            out.push('\n');
+        }
+        out.push_str("\n† name taken from `mappings/`\n");
+
+        out
+    }
+
+    pub fn to_html(&self) -> String {
+        let mut out = String::from(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+             <title>Platform Coverage Report</title>\n\
+             <style>td.missing { background: #fdd; } td.mapping { background: #ffd; }</style>\n\
+             </head>\n<body>\n<h1>Platform Coverage Report</h1>\n",
+        );
+
+        for (title, sets) in [("Groups", &self.groups), ("Profiles", &self.profiles)] {
+            let _ = writeln!(out, "<h2>{}</h2>\n<ul>", title);
+            for set in sets.iter() {
+                let _ = writeln!(out, "<li>{}</li>", escape_html(&set.summary()));
+            }
+            out.push_str("</ul>\n");
+        }
+
+        out.push_str("<h2>Matrix</h2>\n<table>\n<tr><th>Package</th>");
+        for manager in Manager::ALL {
+            let _ = write!(out, "<th>{}</th>", manager);
+        }
+        out.push_str("</tr>\n");
+        for row in &self.matrix.rows {
+            let _ = write!(out, "<tr><td>{}</td>", escape_html(&row.package));
+            for manager in Manager::ALL {
+                match row.platforms.get(&manager) {
+                    Some(a) if a.source == Source::Mapping => {
+                        let _ = write!(out, "<td class=\"mapping\">{}</td>", escape_html(&a.name));
+                    }
+                    Some(a) => {
+                        let _ = write!(out, "<td>{}</td>", escape_html(&a.name));
+                    }
+                    None => out.push_str("<td class=\"missing\">✗</td>"),
+                }
+            }
+            out.push_str("</tr>\n");
+        }
+        out.push_str("</table>\n</body>\n</html>\n");
+
+        out
+    }
+}
+
+fn markdown_set_table(sets: &[SetCoverage]) -> String {
+    let mut out = String::from("| Id |");
+    for manager in Manager::ALL {
+        let _ = write!(out, " {} |", manager);
+    }
+    out.push_str(" Missing |\n|----|");
+    for _ in Manager::ALL {
+        out.push_str("-----|");
+    }
+    out.push_str("---------|\n");
+
+    for set in sets {
+        let _ = write!(out, "| {} |", set.id);
+        for m in &set.managers {
+            let _ = write!(out, " {:.0}% |", m.percent(set.total));
+        }
+        let missing = set.missing();
+        let _ = writeln!(
+            out,
+            " {} |",
+            if missing.is_empty() {
+                "—".to_string()
+            } else {
+                missing.join(", ")
+            }
+        );
+    }
+
+    out
+}
+
+fn cell_text(availability: Option<&Availability>) -> String {
+    match availability {
+        Some(a) if a.source == Source::Mapping => format!("{} †", a.name),
+        Some(a) => a.name.clone(),
+        None => "✗".to_string(),
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn escape_html(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,295 @@
+//! The compiled binary database (`packages.db`)
+//!
+//! The database may also be distributed zstd-compressed (`packages.db.zst`);
+//! the readers here detect the zstd frame header and decompress transparently.
+
+use crate::alias::{AliasEntry, AliasIndex, NameKind};
+use crate::deprecation::Notice;
+use crate::graph::{DependencyEdge, DependencyGraph};
+use crate::model::{Package, PackageGroup, Profile, SuggestionPattern, Template};
+use crate::resolve::{ResolveError, Resolver};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+/// Format version written into every compiled database
+///
+/// Bump it whenever the serialized layout changes (a field added to
+/// [`CompiledDatabase`] or a type it contains); readers reject any other
+/// version rather than misreading the bytes.
+pub const DATABASE_VERSION: u32 = 10;
+
+/// `last_updated` of a new database: `SOURCE_DATE_EPOCH` when set, so that
+/// rebuilding a commit reproduces it, otherwise the current time
+fn build_timestamp() -> String {
+    std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|secs| secs.trim().parse().ok())
+        .and_then(|secs| chrono::DateTime::from_timestamp(secs, 0))
+        .unwrap_or_else(chrono::Utc::now)
+        .to_rfc3339()
+}
+
+/// The format version of a raw or zstd-compressed database, read without
+/// decoding the rest (`version` is serialized first)
+pub fn format_version(bytes: &[u8]) -> Result<u32> {
+    let raw = decompress(bytes)?;
+    bincode::deserialize(&raw).context("Failed to read database version")
+}
+
+/// A package found by [`CompiledDatabase::lookup`]
+#[derive(Debug, Clone)]
+pub struct Lookup<'a> {
+    /// The package to install, after following renames and replacements
+    pub package: &'a Package,
+    /// Redirects followed to get there, in order
+    pub notices: Vec<Notice>,
+}
+
+/// Magic number opening every zstd frame
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// Compression level used for `packages.db.zst`
+const ZSTD_LEVEL: i32 = 19;
+
+/// Upper bound on the decompressed size, to refuse decompression bombs
+pub(crate) const MAX_DECOMPRESSED_SIZE: u64 = 64 * 1024 * 1024;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CompiledDatabase {
+    pub version: u32,
+    pub last_updated: String,
+    pub packages: Vec<Package>,
+    pub groups: Vec<PackageGroup>,
+    // Indexes (ordered so that compiling the same data with the same
+    // `SOURCE_DATE_EPOCH` is byte-for-byte reproducible, which keeps release
+    // deltas small)
+    pub index_by_name: BTreeMap<String, usize>,
+    pub index_by_category: BTreeMap<String, Vec<usize>>,
+    pub index_by_tag: BTreeMap<String, Vec<usize>>,
+    /// Aliases and platform names → canonical package name
+    pub index_by_alias: BTreeMap<String, AliasEntry>,
+    /// Dependent package → dependency edges with their provenance
+    pub dependency_graph: BTreeMap<String, Vec<DependencyEdge>>,
+    /// Profile id → packages of the resolved profile
+    pub profile_packages: BTreeMap<String, Vec<String>>,
+    /// Template id → packages of the resolved template
+    pub template_packages: BTreeMap<String, Vec<String>>,
+    pub suggestions: Vec<SuggestionPattern>,
+}
+
+impl CompiledDatabase {
+    /// Assemble a database and build its indexes
+    pub fn build(packages: Vec<Package>, groups: Vec<PackageGroup>) -> Self {
+        let index_by_name = build_name_index(&packages);
+        let index_by_category = build_category_index(&packages);
+        let index_by_tag = build_tag_index(&packages);
+
+        CompiledDatabase {
+            version: DATABASE_VERSION,
+            last_updated: build_timestamp(),
+            packages,
+            groups,
+            index_by_name,
+            index_by_category,
+            index_by_tag,
+            index_by_alias: BTreeMap::new(),
+            dependency_graph: BTreeMap::new(),
+            profile_packages: BTreeMap::new(),
+            template_packages: BTreeMap::new(),
+            suggestions: Vec::new(),
+        }
+    }
+
+    /// Add the alias index (aliases and platform names → canonical name)
+    pub fn with_aliases(mut self, aliases: &AliasIndex) -> Self {
+        self.index_by_alias = aliases.entries().clone();
+        self
+    }
+
+    /// Add the unified dependency graph; packages should already carry the
+    /// merged lists (see [`DependencyGraph::apply`])
+    pub fn with_dependencies(mut self, graph: &DependencyGraph) -> Self {
+        self.dependency_graph = graph.edges.clone();
+        self
+    }
+
+    /// Add the package lists of every profile and template, with
+    /// inheritance and included groups resolved
+    pub fn with_profiles(
+        mut self,
+        resolver: &Resolver<'_>,
+        profiles: &[Profile],
+        templates: &[Template],
+    ) -> Result<Self, ResolveError> {
+        for profile in profiles {
+            let resolved = resolver.resolve_profile(&profile.id)?;
+            self.profile_packages.insert(
+                profile.id.clone(),
+                resolved.names().map(String::from).collect(),
+            );
+        }
+        for template in templates {
+            let resolved = resolver.resolve_template(&template.id)?;
+            self.template_packages.insert(
+                template.id.clone(),
+                resolved.names().map(String::from).collect(),
+            );
+        }
+        Ok(self)
+    }
+
+    pub fn with_suggestions(mut self, suggestions: Vec<SuggestionPattern>) -> Self {
+        self.suggestions = suggestions;
+        self
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        bincode::serialize(self).context("Failed to serialize database")
+    }
+
+    /// Deserialize a raw or zstd-compressed database of the current
+    /// [`DATABASE_VERSION`]
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let raw = decompress(bytes)?;
+        let version = format_version(&raw)?;
+        if version != DATABASE_VERSION {
+            anyhow::bail!(
+                "Unsupported database version {} (this build reads version {})",
+                version,
+                DATABASE_VERSION
+            );
+        }
+        bincode::deserialize(&raw).context("Failed to deserialize database")
+    }
+
+    /// Read a database file, refusing other format versions (see
+    /// [`CompiledDatabase::from_bytes`])
+    pub fn read(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let bytes = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        Self::from_bytes(&bytes).with_context(|| format!("Invalid database: {}", path.display()))
+    }
+
+    pub fn package(&self, name: &str) -> Option<&Package> {
+        self.index_by_name.get(name).map(|&i| &self.packages[i])
+    }
+
+    /// Look a package up by canonical name, former name, alias or platform
+    /// name
+    pub fn resolve(&self, name: &str) -> Option<&Package> {
+        self.package(name).or_else(|| {
+            self.index_by_alias
+                .get(name)
+                .and_then(|entry| self.package(&entry.canonical))
+        })
+    }
+
+    /// Look a name up like [`CompiledDatabase::resolve`], then follow
+    /// `replaced_by` from deprecated packages, reporting every rename and
+    /// deprecation on the way. A deprecated package without a (known)
+    /// replacement is returned as is, with its notice.
+    pub fn lookup(&self, name: &str) -> Option<Lookup<'_>> {
+        let mut package = self.resolve(name)?;
+        let mut notices = Vec::new();
+        if let Some(entry) = self.index_by_alias.get(name) {
+            if entry.kind == NameKind::Renamed {
+                notices.push(Notice::Renamed {
+                    from: name.to_string(),
+                    to: entry.canonical.clone(),
+                });
+            }
+        }
+
+        let mut seen = vec![package.name.as_str()];
+        while package.deprecated {
+            notices.push(Notice::Deprecated {
+                package: package.name.clone(),
+                replaced_by: package.replaced_by.clone(),
+            });
+            match package.replaced_by.as_deref().and_then(|r| self.package(r)) {
+                Some(next) if !seen.contains(&next.name.as_str()) => {
+                    seen.push(&next.name);
+                    package = next;
+                }
+                _ => break,
+            }
+        }
+
+        Some(Lookup { package, notices })
+    }
+
+    /// Dependency edges of `package`, with the source of each declaration
+    pub fn dependencies(&self, package: &str) -> &[DependencyEdge] {
+        self.dependency_graph
+            .get(package)
+            .map_or(&[], |e| e.as_slice())
+    }
+}
+
+pub fn is_compressed(bytes: &[u8]) -> bool {
+    bytes.starts_with(&ZSTD_MAGIC)
+}
+
+/// Compress a serialized database for distribution
+pub fn compress(bytes: &[u8]) -> Result<Vec<u8>> {
+    zstd::encode_all(bytes, ZSTD_LEVEL).context("Failed to compress database")
+}
+
+/// Return the raw database bytes, decompressing them if needed
+pub fn decompress(bytes: &[u8]) -> Result<Cow<'_, [u8]>> {
+    if !is_compressed(bytes) {
+        return Ok(Cow::Borrowed(bytes));
+    }
+
+    let mut raw = Vec::new();
+    zstd::Decoder::new(bytes)
+        .context("Failed to open compressed database")?
+        .take(MAX_DECOMPRESSED_SIZE + 1)
+        .read_to_end(&mut raw)
+        .context("Failed to decompress database")?;
+    if raw.len() as u64 > MAX_DECOMPRESSED_SIZE {
+        anyhow::bail!(
+            "Decompressed database exceeds {} bytes",
+            MAX_DECOMPRESSED_SIZE
+        );
+    }
+
+    Ok(Cow::Owned(raw))
+}
+
+/// Hex-encoded SHA-256 of `bytes`, as written to `packages.db.sha256`
+pub fn checksum(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+fn build_name_index(packages: &[Package]) -> BTreeMap<String, usize> {
+    packages
+        .iter()
+        .enumerate()
+        .map(|(i, p)| (p.name.clone(), i))
+        .collect()
+}
+
+fn build_category_index(packages: &[Package]) -> BTreeMap<String, Vec<usize>> {
+    let mut index: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+    for (i, pkg) in packages.iter().enumerate() {
+        index.entry(pkg.category.clone()).or_default().push(i);
+    }
+    index
+}
+
+fn build_tag_index(packages: &[Package]) -> BTreeMap<String, Vec<usize>> {
+    let mut index: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+    for (i, pkg) in packages.iter().enumerate() {
+        for tag in &pkg.tags {
+            index.entry(tag.clone()).or_default().push(i);
+        }
+    }
+    index
+}
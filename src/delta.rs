@@ -0,0 +1,200 @@
+//! Binary delta patches between two database releases
+//!
+//! A patch rebuilds the new `packages.db` from the previous one using a list
+//! of copy (from the old file) and insert (literal bytes) operations. It is
+//! keyed by the SHA-256 of both files: applying it to anything other than the
+//! expected source fails, and the rebuilt file is verified before it is
+//! returned.
+
+use crate::database::{checksum, MAX_DECOMPRESSED_SIZE};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use thiserror::Error;
+
+/// Patch format version
+pub const PATCH_VERSION: u32 = 1;
+
+/// Minimum run of matching bytes worth encoding as a copy
+const BLOCK_SIZE: usize = 32;
+
+#[derive(Debug, Error)]
+pub enum DeltaError {
+    #[error("unsupported patch format version {0} (expected {PATCH_VERSION})")]
+    UnsupportedVersion(u32),
+
+    #[error("patch applies to {expected}, but the local database is {actual}")]
+    SourceMismatch { expected: String, actual: String },
+
+    #[error("patched database checksum {actual} does not match expected {expected}")]
+    ChecksumMismatch { expected: String, actual: String },
+
+    #[error("corrupt patch: {0}")]
+    Corrupt(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Op {
+    /// Copy `len` bytes starting at `offset` in the old file
+    Copy { offset: u64, len: u64 },
+    /// Insert literal bytes
+    Insert(Vec<u8>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Patch {
+    pub version: u32,
+    /// SHA-256 of the database this patch applies to
+    pub old_sha256: String,
+    /// SHA-256 of the database this patch produces
+    pub new_sha256: String,
+    pub new_len: u64,
+    pub ops: Vec<Op>,
+}
+
+impl Patch {
+    /// Compute a patch turning `old` into `new`
+    pub fn diff(old: &[u8], new: &[u8]) -> Self {
+        let mut index: HashMap<&[u8], usize> = HashMap::new();
+        if old.len() >= BLOCK_SIZE {
+            for start in 0..=old.len() - BLOCK_SIZE {
+                index
+                    .entry(&old[start..start + BLOCK_SIZE])
+                    .or_insert(start);
+            }
+        }
+
+        let mut ops = Vec::new();
+        let mut literal = Vec::new();
+        let mut i = 0;
+
+        while i < new.len() {
+            let matched = new
+                .get(i..i + BLOCK_SIZE)
+                .and_then(|block| index.get(block))
+                .copied();
+
+            if let Some(start) = matched {
+                let mut len = BLOCK_SIZE;
+                while start + len < old.len()
+                    && i + len < new.len()
+                    && old[start + len] == new[i + len]
+                {
+                    len += 1;
+                }
+
+                if !literal.is_empty() {
+                    ops.push(Op::Insert(std::mem::take(&mut literal)));
+                }
+                ops.push(Op::Copy {
+                    offset: start as u64,
+                    len: len as u64,
+                });
+                i += len;
+            } else {
+                literal.push(new[i]);
+                i += 1;
+            }
+        }
+
+        if !literal.is_empty() {
+            ops.push(Op::Insert(literal));
+        }
+
+        Patch {
+            version: PATCH_VERSION,
+            old_sha256: checksum(old),
+            new_sha256: checksum(new),
+            new_len: new.len() as u64,
+            ops,
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        bincode::serialize(self).expect("patch serialization cannot fail")
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DeltaError> {
+        bincode::deserialize(bytes).map_err(|e| DeltaError::Corrupt(e.to_string()))
+    }
+
+    /// Rebuild the new database from `old`, verifying both checksums
+    pub fn apply(&self, old: &[u8]) -> Result<Vec<u8>, DeltaError> {
+        if self.version != PATCH_VERSION {
+            return Err(DeltaError::UnsupportedVersion(self.version));
+        }
+
+        let actual = checksum(old);
+        if actual != self.old_sha256 {
+            return Err(DeltaError::SourceMismatch {
+                expected: self.old_sha256.clone(),
+                actual,
+            });
+        }
+
+        // Refuse to build more than a database may decompress to, or more
+        // than the patch claims to produce
+        if self.new_len > MAX_DECOMPRESSED_SIZE {
+            return Err(DeltaError::Corrupt(format!(
+                "target of {} bytes exceeds the {} byte limit",
+                self.new_len, MAX_DECOMPRESSED_SIZE
+            )));
+        }
+        let overflow = || {
+            DeltaError::Corrupt(format!(
+                "operations produce more than the {} byte target",
+                self.new_len
+            ))
+        };
+
+        let mut out = Vec::with_capacity(self.new_len as usize);
+        for op in &self.ops {
+            let len = match op {
+                Op::Copy { len, .. } => *len,
+                Op::Insert(bytes) => bytes.len() as u64,
+            };
+            if out.len() as u64 + len > self.new_len {
+                return Err(overflow());
+            }
+            match op {
+                Op::Copy { offset, len } => {
+                    let range = usize::try_from(*offset)
+                        .ok()
+                        .zip(usize::try_from(*len).ok())
+                        .and_then(|(start, len)| old.get(start..start.checked_add(len)?))
+                        .ok_or_else(|| {
+                            DeltaError::Corrupt(format!(
+                                "copy of {} bytes at {} is out of bounds",
+                                len, offset
+                            ))
+                        })?;
+                    out.extend_from_slice(range);
+                }
+                Op::Insert(bytes) => out.extend_from_slice(bytes),
+            }
+        }
+
+        let actual = checksum(&out);
+        if out.len() as u64 != self.new_len || actual != self.new_sha256 {
+            return Err(DeltaError::ChecksumMismatch {
+                expected: self.new_sha256.clone(),
+                actual,
+            });
+        }
+
+        Ok(out)
+    }
+
+    /// File name used when publishing this patch, keyed by both checksums
+    pub fn file_name(&self) -> String {
+        format!(
+            "packages-{}-{}.delta",
+            &self.old_sha256[..16],
+            &self.new_sha256[..16]
+        )
+    }
+}
+
+/// Apply an encoded patch to `old` and return the verified new database
+pub fn apply_patch(old: &[u8], patch: &[u8]) -> Result<Vec<u8>, DeltaError> {
+    Patch::from_bytes(patch)?.apply(old)
+}
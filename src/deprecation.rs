@@ -0,0 +1,228 @@
+//! Package renames and deprecations
+//!
+//! Renaming a package (`node` → `nodejs`) keeps the old name working: the new
+//! package lists it in `renamed_from`, and the alias index resolves it. A
+//! package that should no longer be used stays in the database with
+//! `deprecated: true` and, usually, a `replaced_by` successor.
+//! [`CompiledDatabase::lookup`](crate::database::CompiledDatabase::lookup)
+//! follows both kinds of redirect and reports each one, so clients can warn
+//! the user without failing their profile.
+
+use crate::alias::{AliasIndex, NameKind};
+use crate::loader::SourceTree;
+use crate::model::Package;
+use crate::resolve::Origin;
+use serde::Serialize;
+use std::fmt;
+
+/// A redirect followed while looking a name up
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum Notice {
+    /// `from` is a former name of `to`
+    Renamed { from: String, to: String },
+    /// `package` is deprecated, in favour of `replaced_by` if set
+    Deprecated {
+        package: String,
+        replaced_by: Option<String>,
+    },
+}
+
+impl fmt::Display for Notice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Notice::Renamed { from, to } => write!(f, "'{}' was renamed to '{}'", from, to),
+            Notice::Deprecated {
+                package,
+                replaced_by: Some(replacement),
+            } => write!(
+                f,
+                "'{}' is deprecated; use '{}' instead",
+                package, replacement
+            ),
+            Notice::Deprecated {
+                package,
+                replaced_by: None,
+            } => write!(f, "'{}' is deprecated", package),
+        }
+    }
+}
+
+/// A malformed `deprecated`/`replaced_by` declaration
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeprecationError {
+    /// `replaced_by` names no package
+    UnknownReplacement {
+        package: String,
+        replaced_by: String,
+    },
+    /// `replaced_by` without `deprecated: true`
+    NotDeprecated {
+        package: String,
+        replaced_by: String,
+    },
+    /// Following `replaced_by` leads back to the package
+    ReplacementCycle(Vec<String>),
+}
+
+impl fmt::Display for DeprecationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DeprecationError::UnknownReplacement {
+                package,
+                replaced_by,
+            } => write!(
+                f,
+                "'{}' is replaced by unknown package '{}'",
+                package, replaced_by
+            ),
+            DeprecationError::NotDeprecated {
+                package,
+                replaced_by,
+            } => write!(
+                f,
+                "'{}' sets replaced_by '{}' but is not deprecated",
+                package, replaced_by
+            ),
+            DeprecationError::ReplacementCycle(chain) => {
+                write!(f, "replaced_by cycle: {}", chain.join(" → "))
+            }
+        }
+    }
+}
+
+/// A group, profile or template still listing a deprecated package, or a
+/// package by a former name (`package` is then the former name and
+/// `replaced_by` the current one)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeprecatedUse {
+    pub package: String,
+    pub replaced_by: Option<String>,
+    pub used_by: Origin,
+    pub renamed: bool,
+}
+
+impl fmt::Display for DeprecatedUse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let what = if self.renamed {
+            "former package name"
+        } else {
+            "deprecated package"
+        };
+        write!(f, "{} uses {} '{}'", self.used_by, what, self.package)?;
+        if let Some(replacement) = &self.replaced_by {
+            write!(f, " (use '{}')", replacement)?;
+        }
+        Ok(())
+    }
+}
+
+/// Check every package's `deprecated`/`replaced_by` pair
+pub fn check(packages: &[Package]) -> Vec<DeprecationError> {
+    let find = |name: &str| packages.iter().find(|p| p.name == name);
+    let mut errors = Vec::new();
+
+    for package in packages {
+        let Some(replacement) = &package.replaced_by else {
+            continue;
+        };
+        if !package.deprecated {
+            errors.push(DeprecationError::NotDeprecated {
+                package: package.name.clone(),
+                replaced_by: replacement.clone(),
+            });
+        }
+        if find(replacement).is_none() {
+            errors.push(DeprecationError::UnknownReplacement {
+                package: package.name.clone(),
+                replaced_by: replacement.clone(),
+            });
+            continue;
+        }
+
+        let mut chain = vec![package.name.clone()];
+        let mut next = Some(replacement);
+        while let Some(name) = next {
+            if chain.contains(name) {
+                chain.push(name.clone());
+                // Report each cycle once, from its smallest member
+                if chain[0] == *name && chain.iter().min() == Some(&chain[0]) {
+                    errors.push(DeprecationError::ReplacementCycle(chain));
+                }
+                break;
+            }
+            chain.push(name.clone());
+            next = find(name).and_then(|p| p.replaced_by.as_ref());
+        }
+    }
+
+    errors
+}
+
+/// Every group, profile and template entry naming a deprecated package or a
+/// former name from `renamed_from`
+pub fn deprecated_uses(tree: &SourceTree) -> Vec<DeprecatedUse> {
+    let aliases = AliasIndex::from_tree(tree);
+    let mut entries: Vec<(&String, Origin)> = Vec::new();
+    for group in &tree.groups {
+        for name in group
+            .packages
+            .required
+            .iter()
+            .chain(&group.packages.optional)
+        {
+            entries.push((
+                name,
+                Origin::Group {
+                    id: group.id.clone(),
+                },
+            ));
+        }
+    }
+    for profile in &tree.profiles {
+        for (section, packages) in profile.packages.sections() {
+            for name in packages {
+                entries.push((
+                    name,
+                    Origin::Profile {
+                        id: profile.id.clone(),
+                        section,
+                    },
+                ));
+            }
+        }
+    }
+    for template in &tree.templates {
+        for name in &template.custom_packages {
+            entries.push((
+                name,
+                Origin::Template {
+                    id: template.id.clone(),
+                },
+            ));
+        }
+    }
+
+    entries
+        .into_iter()
+        .filter_map(|(name, used_by)| {
+            if let Some(entry) = aliases.resolve(name) {
+                if entry.kind == NameKind::Renamed {
+                    return Some(DeprecatedUse {
+                        package: name.clone(),
+                        replaced_by: Some(entry.canonical),
+                        used_by,
+                        renamed: true,
+                    });
+                }
+            }
+            let package = tree.package(name).filter(|p| p.deprecated)?;
+            Some(DeprecatedUse {
+                package: package.name.clone(),
+                replaced_by: package.replaced_by.clone(),
+                used_by,
+                renamed: false,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,337 @@
+//! Compare two versions of the database
+//!
+//! Works on plain package and group lists so either side can come from a
+//! compiled `packages.db` or a YAML tree at some git revision; a tree is
+//! first merged the way `compile` merges it (see [`compiled_packages`]). A
+//! removed package and an added package are reported as a rename when they
+//! share a `source` URL, when neither has one and their platform names
+//! match, or when the added package lists the removed one in
+//! `renamed_from`.
+
+use crate::graph::DependencyGraph;
+use crate::loader::SourceTree;
+use crate::model::{Package, PackageGroup};
+use crate::reconcile;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+/// The packages of `tree` as `compile` writes them: mapping platform names
+/// fill the gaps and dependency files are merged into the dependency lists
+pub fn compiled_packages(tree: &SourceTree) -> Vec<Package> {
+    let mut packages = tree.packages.clone();
+    reconcile::merge(&mut packages, &tree.mappings);
+    DependencyGraph::build(&packages, &tree.dependencies).apply(&mut packages);
+    packages
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Rename {
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PlatformChange {
+    pub package: String,
+    /// A manager, or `apk`
+    pub platform: &'static str,
+    pub old: Option<String>,
+    pub new: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Change {
+    Added,
+    Removed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DependencyChange {
+    pub package: String,
+    pub dependency: String,
+    /// `required` or `optional`
+    pub kind: &'static str,
+    pub change: Change,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MembershipChange {
+    pub group: String,
+    pub package: String,
+    /// `required` or `optional`
+    pub section: &'static str,
+    pub change: Change,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct DatabaseDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub renamed: Vec<Rename>,
+    pub platform_changes: Vec<PlatformChange>,
+    pub dependency_changes: Vec<DependencyChange>,
+    pub groups_added: Vec<String>,
+    pub groups_removed: Vec<String>,
+    pub membership_changes: Vec<MembershipChange>,
+}
+
+impl DatabaseDiff {
+    pub fn compute(
+        old_packages: &[Package],
+        old_groups: &[PackageGroup],
+        new_packages: &[Package],
+        new_groups: &[PackageGroup],
+    ) -> Self {
+        let old: BTreeMap<&str, &Package> =
+            old_packages.iter().map(|p| (p.name.as_str(), p)).collect();
+        let new: BTreeMap<&str, &Package> =
+            new_packages.iter().map(|p| (p.name.as_str(), p)).collect();
+
+        let mut added: Vec<&str> = new
+            .keys()
+            .filter(|n| !old.contains_key(*n))
+            .copied()
+            .collect();
+        let mut removed: Vec<&str> = old
+            .keys()
+            .filter(|n| !new.contains_key(*n))
+            .copied()
+            .collect();
+
+        // Pair up removed/added packages that look like the same tool
+        let mut renamed = Vec::new();
+        removed.retain(
+            |from| match added.iter().position(|to| same_tool(old[from], new[to])) {
+                Some(i) => {
+                    renamed.push(Rename {
+                        from: from.to_string(),
+                        to: added.remove(i).to_string(),
+                    });
+                    false
+                }
+                None => true,
+            },
+        );
+
+        let mut diff = DatabaseDiff {
+            added: added.iter().map(|s| s.to_string()).collect(),
+            removed: removed.iter().map(|s| s.to_string()).collect(),
+            ..Default::default()
+        };
+
+        let pairs = old
+            .iter()
+            .filter_map(|(name, pkg)| new.get(name).map(|n| (*pkg, *n)))
+            .chain(
+                renamed
+                    .iter()
+                    .map(|r| (old[r.from.as_str()], new[r.to.as_str()])),
+            );
+        for (before, after) in pairs {
+            diff.compare_package(before, after);
+        }
+        diff.renamed = renamed;
+
+        diff.compare_groups(old_groups, new_groups);
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == DatabaseDiff::default()
+    }
+
+    fn compare_package(&mut self, before: &Package, after: &Package) {
+        let names = before
+            .platforms
+            .names()
+            .into_iter()
+            .zip(after.platforms.names());
+        for ((platform, old), (_, new)) in names {
+            if old != new {
+                self.platform_changes.push(PlatformChange {
+                    package: after.name.clone(),
+                    platform,
+                    old: old.map(String::from),
+                    new: new.map(String::from),
+                });
+            }
+        }
+
+        let sections = [
+            (
+                "required",
+                &before.dependencies.required,
+                &after.dependencies.required,
+            ),
+            (
+                "optional",
+                &before.dependencies.optional,
+                &after.dependencies.optional,
+            ),
+        ];
+        for (kind, old, new) in sections {
+            let old: BTreeSet<&str> = old.iter().map(|d| d.package.as_str()).collect();
+            let new: BTreeSet<&str> = new.iter().map(|d| d.package.as_str()).collect();
+            for (set, other, change) in [(&new, &old, Change::Added), (&old, &new, Change::Removed)]
+            {
+                for dep in set.difference(other) {
+                    self.dependency_changes.push(DependencyChange {
+                        package: after.name.clone(),
+                        dependency: dep.to_string(),
+                        kind,
+                        change,
+                    });
+                }
+            }
+        }
+    }
+
+    fn compare_groups(&mut self, old_groups: &[PackageGroup], new_groups: &[PackageGroup]) {
+        let old: BTreeMap<&str, &PackageGroup> =
+            old_groups.iter().map(|g| (g.id.as_str(), g)).collect();
+        let new: BTreeMap<&str, &PackageGroup> =
+            new_groups.iter().map(|g| (g.id.as_str(), g)).collect();
+
+        self.groups_added = new
+            .keys()
+            .filter(|id| !old.contains_key(*id))
+            .map(|id| id.to_string())
+            .collect();
+        self.groups_removed = old
+            .keys()
+            .filter(|id| !new.contains_key(*id))
+            .map(|id| id.to_string())
+            .collect();
+
+        for (id, before) in &old {
+            let Some(after) = new.get(id) else {
+                continue;
+            };
+            let sections = [
+                (
+                    "required",
+                    &before.packages.required,
+                    &after.packages.required,
+                ),
+                (
+                    "optional",
+                    &before.packages.optional,
+                    &after.packages.optional,
+                ),
+            ];
+            for (section, old, new) in sections {
+                let old: BTreeSet<&String> = old.iter().collect();
+                let new: BTreeSet<&String> = new.iter().collect();
+                for (set, other, change) in
+                    [(&new, &old, Change::Added), (&old, &new, Change::Removed)]
+                {
+                    for pkg in set.difference(other) {
+                        self.membership_changes.push(MembershipChange {
+                            group: id.to_string(),
+                            package: pkg.to_string(),
+                            section,
+                            change,
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    /// Render as a Keep-a-Changelog release section
+    pub fn to_markdown(&self, version: &str, date: &str) -> String {
+        let mut added = Vec::new();
+        let mut changed = Vec::new();
+        let mut removed = Vec::new();
+
+        for name in &self.added {
+            added.push(format!("Package `{}`", name));
+        }
+        for id in &self.groups_added {
+            added.push(format!("Group `{}`", id));
+        }
+        for rename in &self.renamed {
+            changed.push(format!(
+                "Renamed package `{}` to `{}`",
+                rename.from, rename.to
+            ));
+        }
+        for c in &self.platform_changes {
+            changed.push(match (&c.old, &c.new) {
+                (None, Some(new)) => format!(
+                    "`{}` is now available on {} as `{}`",
+                    c.package, c.platform, new
+                ),
+                (Some(old), None) => format!(
+                    "`{}` is no longer available on {} (was `{}`)",
+                    c.package, c.platform, old
+                ),
+                (Some(old), Some(new)) => format!(
+                    "`{}` {} name changed from `{}` to `{}`",
+                    c.package, c.platform, old, new
+                ),
+                (None, None) => unreachable!("platform change without a name on either side"),
+            });
+        }
+        for c in &self.dependency_changes {
+            changed.push(match c.change {
+                Change::Added => format!(
+                    "`{}` gained {} dependency `{}`",
+                    c.package, c.kind, c.dependency
+                ),
+                Change::Removed => format!(
+                    "`{}` dropped {} dependency `{}`",
+                    c.package, c.kind, c.dependency
+                ),
+            });
+        }
+        for c in &self.membership_changes {
+            changed.push(match c.change {
+                Change::Added => {
+                    format!("Group `{}`: added `{}` ({})", c.group, c.package, c.section)
+                }
+                Change::Removed => format!(
+                    "Group `{}`: removed `{}` ({})",
+                    c.group, c.package, c.section
+                ),
+            });
+        }
+        for name in &self.removed {
+            removed.push(format!("Package `{}`", name));
+        }
+        for id in &self.groups_removed {
+            removed.push(format!("Group `{}`", id));
+        }
+
+        let mut out = format!("## [{}] - {}\n", version, date);
+        if self.is_empty() {
+            out.push_str("\nNo package data changes.\n");
+        }
+        for (title, entries) in [("Added", added), ("Changed", changed), ("Removed", removed)] {
+            if entries.is_empty() {
+                continue;
+            }
+            let _ = writeln!(out, "\n### {}", title);
+            for entry in entries {
+                let _ = writeln!(out, "- {}", entry);
+            }
+        }
+
+        out
+    }
+}
+
+fn same_tool(old: &Package, new: &Package) -> bool {
+    if new.renamed_from.contains(&old.name) {
+        return true;
+    }
+    match (&old.source, &new.source) {
+        (Some(a), Some(b)) => a == b,
+        (None, None) => {
+            old.platforms != Default::default() && old.platforms.names() == new.platforms.names()
+        }
+        _ => false,
+    }
+}
@@ -0,0 +1,147 @@
+//! Dotfile path checks and merge conflicts
+//!
+//! A dotfile's `source` is a relative path inside the user's dotfiles
+//! repository and its `target` must stay below `~/`. When profiles are merged
+//! (through `extends` or a template), two dotfiles with the same target, or a
+//! target nested inside another, collide unless a profile deliberately
+//! overrides one it inherited.
+
+use crate::model::Dotfile;
+use serde::Serialize;
+use std::fmt;
+use std::path::{Component, Path};
+
+/// Problems with a dotfile's paths, as human-readable messages
+pub fn path_errors(dotfile: &Dotfile) -> Vec<String> {
+    let mut errors = Vec::new();
+
+    let source = Path::new(&dotfile.source);
+    if dotfile.source.is_empty() {
+        errors.push("source is empty".to_string());
+    } else if source.is_absolute() || dotfile.source.starts_with('~') {
+        errors.push(format!(
+            "source '{}' must be relative to the dotfiles repository",
+            dotfile.source
+        ));
+    }
+    if has_parent_dir(&dotfile.source) {
+        errors.push(format!("source '{}' contains '..'", dotfile.source));
+    }
+
+    match dotfile.target.strip_prefix("~/") {
+        Some(rest) if !rest.is_empty() => {}
+        _ => errors.push(format!(
+            "target '{}' must be a path below '~/'",
+            dotfile.target
+        )),
+    }
+    if has_parent_dir(&dotfile.target) {
+        errors.push(format!(
+            "target '{}' contains '..' and could escape the home directory",
+            dotfile.target
+        ));
+    }
+
+    errors
+}
+
+fn has_parent_dir(path: &str) -> bool {
+    Path::new(path)
+        .components()
+        .any(|c| matches!(c, Component::ParentDir))
+}
+
+/// Whether one target is the other or lies inside it
+fn overlaps(a: &str, b: &str) -> bool {
+    let (a, b) = (a.trim_end_matches('/'), b.trim_end_matches('/'));
+    a == b || Path::new(a).starts_with(b) || Path::new(b).starts_with(a)
+}
+
+/// A dotfile in a resolved profile, with the profile that declared it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ResolvedDotfile {
+    pub source: String,
+    pub target: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub package: Option<String>,
+    pub profile: String,
+}
+
+impl ResolvedDotfile {
+    pub fn new(dotfile: &Dotfile, profile: &str) -> Self {
+        ResolvedDotfile {
+            source: dotfile.source.clone(),
+            target: dotfile.target.clone(),
+            package: dotfile.package.clone(),
+            profile: profile.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DotfileConflict {
+    pub first: ResolvedDotfile,
+    pub second: ResolvedDotfile,
+}
+
+impl fmt::Display for DotfileConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let verb = if self.first.target == self.second.target {
+            "collides with"
+        } else {
+            "overlaps"
+        };
+        write!(
+            f,
+            "dotfile {} ({}, profile {}) {} {} ({}, profile {})",
+            self.second.target,
+            self.second.source,
+            self.second.profile,
+            verb,
+            self.first.target,
+            self.first.source,
+            self.first.profile
+        )
+    }
+}
+
+/// Merge `new` into `merged`, recording collisions in `conflicts`
+///
+/// With `overriding`, a dotfile replaces one with the same target that was
+/// inherited from another profile (a child overriding its parent).
+pub fn merge(
+    merged: &mut Vec<ResolvedDotfile>,
+    conflicts: &mut Vec<DotfileConflict>,
+    new: impl IntoIterator<Item = ResolvedDotfile>,
+    overriding: bool,
+) {
+    for dotfile in new {
+        if let Some(existing) = merged.iter_mut().find(|d| d.target == dotfile.target) {
+            if existing.source == dotfile.source {
+                // The same file, e.g. one ancestor reached through two parents
+                continue;
+            }
+            if overriding && existing.profile != dotfile.profile {
+                // Overriding also settles collisions between inherited copies
+                conflicts.retain(|c| {
+                    c.first.target != dotfile.target || c.second.target != dotfile.target
+                });
+                *existing = dotfile;
+                continue;
+            }
+            conflicts.push(DotfileConflict {
+                first: existing.clone(),
+                second: dotfile,
+            });
+            continue;
+        }
+
+        if let Some(existing) = merged.iter().find(|d| overlaps(&d.target, &dotfile.target)) {
+            conflicts.push(DotfileConflict {
+                first: existing.clone(),
+                second: dotfile.clone(),
+            });
+        }
+        merged.push(dotfile);
+    }
+}
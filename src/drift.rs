@@ -0,0 +1,260 @@
+//! Drift between a machine and the group, profile or template it should match
+//!
+//! Compares what one package manager reports as installed (see
+//! [`crate::installed`]) with a resolved selection:
+//!
+//! - missing required packages: resolved packages, a group's required
+//!   packages and chosen slot options that are not installed. A slot counts
+//!   as satisfied when any of its options is installed.
+//! - missing optional packages: optional packages of the selected groups
+//! - unavailable packages: selected packages the manager has no name for,
+//!   which are left out of the missing lists
+//! - extras: installed packages that are neither selected, offered by a
+//!   selected group or slot, nor a dependency of one of those
+//! - aliased installs: packages installed under an alias or former name
+//!   (`docker-ce` for docker) rather than the manager's own name
+
+use crate::alias::NameKind;
+use crate::choice::{self, Choices};
+use crate::coverage::availability;
+use crate::export::Selection;
+use crate::graph::DependencyGraph;
+use crate::installed::Installed;
+use crate::loader::SourceTree;
+use crate::model::{Manager, PackageGroup};
+use crate::resolve::{ResolveError, Resolver};
+use serde::Serialize;
+use std::collections::BTreeSet;
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum DriftError {
+    #[error(transparent)]
+    Resolve(#[from] ResolveError),
+
+    #[error("unknown group '{0}'")]
+    UnknownGroup(String),
+}
+
+/// A package installed under a non-canonical name
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AliasedInstall {
+    pub package: String,
+    /// Name the manager reports
+    pub native: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DriftReport {
+    /// E.g. `profile developer`
+    pub selection: String,
+    pub manager: Manager,
+    pub missing_required: Vec<String>,
+    pub missing_optional: Vec<String>,
+    /// Selected packages with no name on the manager
+    pub unavailable: Vec<String>,
+    /// Canonical names, in the order the manager listed them
+    pub extra: Vec<String>,
+    pub aliased: Vec<AliasedInstall>,
+    /// Installed native packages the database does not know
+    pub unknown: Vec<String>,
+}
+
+/// The packages a selection expects, before comparing with a machine
+struct Expected {
+    required: Vec<String>,
+    optional: Vec<String>,
+    /// Options of each slot; one installed option satisfies the slot
+    slots: Vec<(String, Vec<String>)>,
+}
+
+impl DriftReport {
+    pub fn compute(
+        tree: &SourceTree,
+        selection: &Selection,
+        choices: &Choices,
+        installed: &Installed,
+    ) -> Result<Self, DriftError> {
+        let expected = expected(tree, selection, choices)?;
+        let is_installed = |name: &String| installed.package(name).is_some();
+        let is_available = |name: &String| {
+            tree.package(name)
+                .is_some_and(|p| availability(p, tree.mapping(name), installed.manager).is_some())
+        };
+
+        let satisfied: Vec<&String> = expected
+            .slots
+            .iter()
+            .filter(|(_, options)| options.iter().any(is_installed))
+            .map(|(chosen, _)| chosen)
+            .collect();
+        let missing_required = expected
+            .required
+            .iter()
+            .filter(|p| !is_installed(p) && !satisfied.contains(p) && is_available(p))
+            .cloned()
+            .collect();
+        let missing_optional = expected
+            .optional
+            .iter()
+            .filter(|p| !is_installed(p) && is_available(p))
+            .cloned()
+            .collect();
+        let mut unavailable: Vec<String> = Vec::new();
+        for name in expected.required.iter().chain(&expected.optional) {
+            if !is_available(name) && !unavailable.contains(name) {
+                unavailable.push(name.clone());
+            }
+        }
+
+        // Everything the selection may install, and what that pulls in
+        let graph = DependencyGraph::from_tree(tree);
+        let mut covered: BTreeSet<&str> = BTreeSet::new();
+        let mut pending: Vec<&str> = expected
+            .required
+            .iter()
+            .chain(&expected.optional)
+            .chain(expected.slots.iter().flat_map(|(_, options)| options))
+            .map(String::as_str)
+            .collect();
+        while let Some(name) = pending.pop() {
+            if covered.insert(name) {
+                pending.extend(graph.dependencies(name).iter().map(|e| e.package.as_str()));
+            }
+        }
+
+        Ok(DriftReport {
+            selection: selection.to_string(),
+            manager: installed.manager,
+            missing_required,
+            missing_optional,
+            unavailable,
+            extra: installed
+                .names()
+                .filter(|name| !covered.contains(name))
+                .map(String::from)
+                .collect(),
+            aliased: installed
+                .packages
+                .iter()
+                .filter(|p| matches!(p.kind, NameKind::Alias | NameKind::Renamed))
+                .map(|p| AliasedInstall {
+                    package: p.package.clone(),
+                    native: p.native.clone(),
+                })
+                .collect(),
+            unknown: installed.unknown.iter().map(|p| p.name.clone()).collect(),
+        })
+    }
+
+    /// Nothing required is missing and nothing unexpected is installed
+    pub fn is_clean(&self) -> bool {
+        self.missing_required.is_empty() && self.extra.is_empty() && self.aliased.is_empty()
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut out = format!("Drift from {} ({}):\n", self.selection, self.manager);
+        if self.is_clean() && self.missing_optional.is_empty() {
+            out.push_str("  none\n");
+        }
+
+        let mut section = |title: &str, lines: Vec<String>| {
+            if !lines.is_empty() {
+                out.push_str(&format!("\n{} ({}):\n", title, lines.len()));
+                for line in lines {
+                    out.push_str(&format!("  {}\n", line));
+                }
+            }
+        };
+        section("Missing required", self.missing_required.clone());
+        section("Missing optional", self.missing_optional.clone());
+        section(
+            &format!("Not available on {}", self.manager),
+            self.unavailable.clone(),
+        );
+        section("Not in the selection", self.extra.clone());
+        section(
+            "Installed under an alias",
+            self.aliased
+                .iter()
+                .map(|a| format!("{} (as {})", a.package, a.native))
+                .collect(),
+        );
+        match self.unknown.len() {
+            0 => {}
+            1 => out.push_str("\n1 installed package is not in the database\n"),
+            n => out.push_str(&format!(
+                "\n{} installed packages are not in the database\n",
+                n
+            )),
+        }
+        out
+    }
+}
+
+fn expected(
+    tree: &SourceTree,
+    selection: &Selection,
+    choices: &Choices,
+) -> Result<Expected, DriftError> {
+    let resolver = Resolver::from_tree(tree).with_choices(choices);
+    let (resolved, groups): (_, Vec<&PackageGroup>) = match selection {
+        Selection::Group(id) => {
+            let group = tree
+                .group(id)
+                .ok_or_else(|| DriftError::UnknownGroup(id.to_string()))?;
+            let chosen = choice::pick_all(&group.choices, choices)?;
+            let mut required = group.packages.required.clone();
+            required.extend(chosen.iter().map(|c| c.to_string()));
+            return Ok(Expected {
+                required,
+                optional: group.packages.optional.clone(),
+                slots: chosen
+                    .into_iter()
+                    .cloned()
+                    .zip(group.choices.iter().map(|c| c.options.clone()))
+                    .collect(),
+            });
+        }
+        Selection::Profile(id) => {
+            let resolved = resolver.resolve_profile(id)?;
+            let groups = profile_groups(tree, &resolved.chain);
+            (resolved, groups)
+        }
+        Selection::Template(id) => {
+            let resolved = resolver.resolve_template(id)?;
+            let template = tree.template(id).expect("resolved template exists");
+            let mut groups = profile_groups(tree, &resolved.chain);
+            groups.extend(template.groups.iter().filter_map(|g| tree.group(g)));
+            (resolved, groups)
+        }
+    };
+
+    let required: Vec<String> = resolved.names().map(String::from).collect();
+    let mut optional: Vec<String> = Vec::new();
+    for name in groups.iter().flat_map(|g| &g.packages.optional) {
+        if !required.contains(name) && !optional.contains(name) {
+            optional.push(name.clone());
+        }
+    }
+
+    Ok(Expected {
+        required,
+        optional,
+        slots: resolved
+            .choices
+            .into_iter()
+            .map(|c| (c.chosen, c.options))
+            .collect(),
+    })
+}
+
+/// Groups included by the profiles of an inheritance chain
+fn profile_groups<'a>(tree: &'a SourceTree, chain: &[String]) -> Vec<&'a PackageGroup> {
+    chain
+        .iter()
+        .filter_map(|p| tree.profile(p))
+        .flat_map(|p| &p.include_groups)
+        .filter_map(|g| tree.group(g))
+        .collect()
+}
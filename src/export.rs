@@ -0,0 +1,269 @@
+//! Package sets for provisioning exporters
+//!
+//! Exporters (Ansible roles, container images) all start from the same
+//! question: which packages does a group, profile or template install, and
+//! what are they called on each package manager? A [`PackageSet`] answers it
+//! once, with the dotfiles, hooks and group `platform_overrides` that come
+//! along.
+
+use crate::choice::{self, Choices};
+use crate::conflicts::{self, PackageConflict};
+use crate::coverage::availability;
+use crate::dotfiles::{DotfileConflict, ResolvedDotfile};
+use crate::loader::SourceTree;
+use crate::model::{Hooks, Manager, PlatformOverride};
+use crate::resolve::{Origin, ResolveError, ResolvedProfile, Resolver};
+use crate::template::{merge_overrides, RenderError, RenderedTemplate};
+use std::collections::BTreeMap;
+use std::fmt;
+use thiserror::Error;
+
+/// What to export
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Selection {
+    Group(String),
+    Profile(String),
+    Template(String),
+}
+
+impl Selection {
+    pub fn id(&self) -> &str {
+        match self {
+            Selection::Group(id) | Selection::Profile(id) | Selection::Template(id) => id,
+        }
+    }
+}
+
+impl fmt::Display for Selection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Selection::Group(id) => write!(f, "group {}", id),
+            Selection::Profile(id) => write!(f, "profile {}", id),
+            Selection::Template(id) => write!(f, "template {}", id),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ExportError {
+    #[error(transparent)]
+    Resolve(#[from] ResolveError),
+
+    #[error(transparent)]
+    Render(#[from] RenderError),
+
+    #[error("unknown group '{0}'")]
+    UnknownGroup(String),
+
+    #[error("unknown package '{package}' (from {origin})")]
+    UnknownPackage { package: String, origin: Origin },
+
+    #[error("{0}")]
+    DotfileConflict(Box<DotfileConflict>),
+
+    #[error("conflicting packages: {0}")]
+    PackageConflict(PackageConflict),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SetPackage {
+    pub name: String,
+    /// Optional group packages are installed only on request
+    pub required: bool,
+    /// Native name on each manager the package is available on
+    pub platforms: BTreeMap<Manager, String>,
+}
+
+/// A group, profile or template flattened for export
+#[derive(Debug, Clone)]
+pub struct PackageSet {
+    pub selection: Selection,
+    pub description: String,
+    /// Managers the set targets: all of them, or a template's platform
+    pub managers: Vec<Manager>,
+    pub packages: Vec<SetPackage>,
+    /// Merged group overrides, keyed `macos` / `linux`
+    pub platform_overrides: BTreeMap<String, PlatformOverride>,
+    pub dotfiles: Vec<ResolvedDotfile>,
+    pub hooks: Hooks,
+}
+
+impl PackageSet {
+    pub fn load(tree: &SourceTree, selection: &Selection) -> Result<Self, ExportError> {
+        Self::load_with_choices(tree, selection, &Choices::new())
+    }
+
+    /// Load with the user's picks for the selection's choice slots
+    pub fn load_with_choices(
+        tree: &SourceTree,
+        selection: &Selection,
+        choices: &Choices,
+    ) -> Result<Self, ExportError> {
+        match selection {
+            Selection::Group(id) => Self::group(tree, id, choices),
+            Selection::Profile(id) => Self::profile(tree, id, choices),
+            Selection::Template(id) => Self::template(tree, id, choices),
+        }
+    }
+
+    fn group(tree: &SourceTree, id: &str, choices: &Choices) -> Result<Self, ExportError> {
+        let group = tree
+            .group(id)
+            .ok_or_else(|| ExportError::UnknownGroup(id.to_string()))?;
+        let chosen = choice::pick_all(&group.choices, choices)?;
+
+        // Optional packages are picked individually, so only the required
+        // ones must be installable together
+        let required_names = group.packages.required.iter().chain(chosen.clone());
+        if let Some(conflict) =
+            conflicts::find(&tree.packages, required_names.map(String::as_str)).first()
+        {
+            return Err(ExportError::PackageConflict(conflict.clone()));
+        }
+
+        let required = group
+            .packages
+            .required
+            .iter()
+            .chain(chosen)
+            .map(|n| (n, true));
+        let optional = group.packages.optional.iter().map(|n| (n, false));
+        let packages = required
+            .chain(optional)
+            .map(|(name, required)| {
+                let origin = Origin::Group { id: id.to_string() };
+                set_package(tree, name, required, &origin)
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(PackageSet {
+            selection: Selection::Group(id.to_string()),
+            description: group.description.clone(),
+            managers: Manager::ALL.to_vec(),
+            packages,
+            platform_overrides: merge_overrides([group], |_| true),
+            dotfiles: Vec::new(),
+            hooks: Hooks::default(),
+        })
+    }
+
+    fn profile(tree: &SourceTree, id: &str, choices: &Choices) -> Result<Self, ExportError> {
+        let resolved = Resolver::from_tree(tree)
+            .with_choices(choices)
+            .resolve_profile(id)?;
+        let profile = tree.profile(id).expect("resolved profile exists");
+        let groups = resolved.groups(tree);
+        let ResolvedProfile {
+            packages,
+            dotfiles,
+            dotfile_conflicts,
+            package_conflicts,
+            hooks,
+            ..
+        } = resolved;
+        if let Some(conflict) = dotfile_conflicts.into_iter().next() {
+            return Err(ExportError::DotfileConflict(Box::new(conflict)));
+        }
+        if let Some(conflict) = package_conflicts.into_iter().next() {
+            return Err(ExportError::PackageConflict(conflict));
+        }
+
+        let packages = packages
+            .iter()
+            .map(|p| set_package(tree, &p.name, true, p.origin()))
+            .collect::<Result<_, _>>()?;
+        Ok(PackageSet {
+            selection: Selection::Profile(id.to_string()),
+            description: profile.description.clone(),
+            managers: Manager::ALL.to_vec(),
+            packages,
+            platform_overrides: merge_overrides(groups, |_| true),
+            dotfiles,
+            hooks,
+        })
+    }
+
+    fn template(tree: &SourceTree, id: &str, choices: &Choices) -> Result<Self, ExportError> {
+        let rendered = RenderedTemplate::render_with_choices(tree, id, choices)?;
+
+        Ok(PackageSet {
+            selection: Selection::Template(id.to_string()),
+            description: rendered.description,
+            managers: rendered.platform.managers(),
+            packages: rendered
+                .packages
+                .into_iter()
+                .map(|p| SetPackage {
+                    name: p.name,
+                    required: true,
+                    platforms: p.platforms,
+                })
+                .collect(),
+            platform_overrides: rendered.platform_overrides,
+            dotfiles: rendered.dotfiles,
+            hooks: rendered.hooks,
+        })
+    }
+
+    /// Native names on `manager`, followed by the manager's override packages
+    /// for required sets
+    pub fn names(&self, manager: Manager, required: bool) -> Vec<&str> {
+        let mut names: Vec<&str> = self
+            .packages
+            .iter()
+            .filter(|p| p.required == required)
+            .filter_map(|p| p.platforms.get(&manager).map(String::as_str))
+            .collect();
+        if required {
+            if let Some(overrides) = self.platform_overrides.get(manager.override_key()) {
+                for name in &overrides.packages {
+                    if !names.contains(&name.as_str()) {
+                        names.push(name);
+                    }
+                }
+            }
+        }
+        names
+    }
+
+    /// Homebrew casks from the `macos` overrides
+    pub fn casks(&self) -> Vec<&str> {
+        self.platform_overrides
+            .get(Manager::Brew.override_key())
+            .map(|o| o.casks.iter().map(String::as_str).collect())
+            .unwrap_or_default()
+    }
+
+    /// Packages (by canonical name) with no name on `manager`
+    pub fn unavailable(&self, manager: Manager, required: bool) -> Vec<&str> {
+        self.packages
+            .iter()
+            .filter(|p| p.required == required && !p.platforms.contains_key(&manager))
+            .map(|p| p.name.as_str())
+            .collect()
+    }
+}
+
+fn set_package(
+    tree: &SourceTree,
+    name: &str,
+    required: bool,
+    origin: &Origin,
+) -> Result<SetPackage, ExportError> {
+    let package = tree
+        .package(name)
+        .ok_or_else(|| ExportError::UnknownPackage {
+            package: name.to_string(),
+            origin: origin.clone(),
+        })?;
+    let mapping = tree.mapping(&package.name);
+
+    Ok(SetPackage {
+        name: package.name.clone(),
+        required,
+        platforms: Manager::ALL
+            .into_iter()
+            .filter_map(|m| availability(package, mapping, m).map(|a| (m, a.name)))
+            .collect(),
+    })
+}
@@ -0,0 +1,238 @@
+//! The unified package dependency graph
+//!
+//! Dependencies are recorded in two places: the `dependencies` block of each
+//! package file and the standalone `dependencies/*.yaml` files. The graph
+//! merges both into one edge per package and dependency, remembering which
+//! source contributed it. An edge is required when either source requires
+//! it; [`DependencyGraph::contradictions`] reports where the sources disagree
+//! about that.
+
+use crate::loader::SourceTree;
+use crate::model::{Dependencies, Dependency, Package};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Where a dependency edge was declared
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DependencySource {
+    /// The `dependencies` block of the package file
+    PackageFile,
+    /// An entry in `dependencies/*.yaml`
+    DependencyFile,
+}
+
+impl fmt::Display for DependencySource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DependencySource::PackageFile => f.write_str("its package file"),
+            DependencySource::DependencyFile => f.write_str("dependencies/"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DependencyKind {
+    Required,
+    Optional,
+}
+
+impl DependencyKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DependencyKind::Required => "required",
+            DependencyKind::Optional => "optional",
+        }
+    }
+}
+
+/// One source's declaration of an edge
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Provenance {
+    pub source: DependencySource,
+    pub kind: DependencyKind,
+    pub reason: String,
+}
+
+/// `package` depends on the edge's owner
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DependencyEdge {
+    pub package: String,
+    /// Required if any source requires it
+    pub kind: DependencyKind,
+    /// Package file first, then dependency files
+    pub provenance: Vec<Provenance>,
+}
+
+impl DependencyEdge {
+    /// How `source` declares the edge, if at all
+    pub fn declared_by(&self, source: DependencySource) -> Option<DependencyKind> {
+        self.provenance
+            .iter()
+            .find(|p| p.source == source)
+            .map(|p| p.kind)
+    }
+}
+
+/// The sources disagree about whether `dependency` is required
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Contradiction {
+    pub package: String,
+    pub dependency: String,
+    /// The source that requires it
+    pub required_by: DependencySource,
+    /// How the other source declares it (`None`: not at all)
+    pub other: Option<DependencyKind>,
+}
+
+impl fmt::Display for Contradiction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let other = match self.required_by {
+            DependencySource::PackageFile => DependencySource::DependencyFile,
+            DependencySource::DependencyFile => DependencySource::PackageFile,
+        };
+        let declared = match self.other {
+            Some(DependencyKind::Optional) => "lists it as optional",
+            _ => "does not list it",
+        };
+        write!(
+            f,
+            "'{}' requires '{}' in {} but {} {}",
+            self.package, self.dependency, self.required_by, other, declared
+        )
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DependencyGraph {
+    /// Dependent package → its dependencies, in declaration order
+    pub edges: BTreeMap<String, Vec<DependencyEdge>>,
+}
+
+impl DependencyGraph {
+    pub fn build(packages: &[Package], files: &BTreeMap<String, Dependencies>) -> Self {
+        let mut graph = DependencyGraph::default();
+
+        for package in packages {
+            graph.add(
+                &package.name,
+                &package.dependencies,
+                DependencySource::PackageFile,
+            );
+        }
+        for (package, dependencies) in files {
+            graph.add(package, dependencies, DependencySource::DependencyFile);
+        }
+
+        graph
+    }
+
+    pub fn from_tree(tree: &SourceTree) -> Self {
+        Self::build(&tree.packages, &tree.dependencies)
+    }
+
+    fn add(&mut self, package: &str, dependencies: &Dependencies, source: DependencySource) {
+        let declared = dependencies
+            .required
+            .iter()
+            .map(|d| (d, DependencyKind::Required))
+            .chain(
+                dependencies
+                    .optional
+                    .iter()
+                    .map(|d| (d, DependencyKind::Optional)),
+            );
+
+        for (dependency, kind) in declared {
+            let edges = self.edges.entry(package.to_string()).or_default();
+            let index = match edges.iter().position(|e| e.package == dependency.package) {
+                Some(index) => index,
+                None => {
+                    edges.push(DependencyEdge {
+                        package: dependency.package.clone(),
+                        kind,
+                        provenance: Vec::new(),
+                    });
+                    edges.len() - 1
+                }
+            };
+            let edge = &mut edges[index];
+            if edge.declared_by(source).is_some() {
+                continue;
+            }
+            if kind == DependencyKind::Required {
+                edge.kind = kind;
+            }
+            edge.provenance.push(Provenance {
+                source,
+                kind,
+                reason: dependency.reason.clone(),
+            });
+        }
+    }
+
+    /// Every edge of `package`
+    pub fn dependencies(&self, package: &str) -> &[DependencyEdge] {
+        self.edges.get(package).map_or(&[], |e| e.as_slice())
+    }
+
+    /// Edges one source requires while the other has them optional or not
+    /// at all
+    pub fn contradictions(&self) -> Vec<Contradiction> {
+        let mut contradictions = Vec::new();
+
+        for (package, edges) in &self.edges {
+            for edge in edges {
+                let package_file = edge.declared_by(DependencySource::PackageFile);
+                let dependency_file = edge.declared_by(DependencySource::DependencyFile);
+                let (required_by, other) = match (package_file, dependency_file) {
+                    (Some(DependencyKind::Required), other)
+                        if other != Some(DependencyKind::Required) =>
+                    {
+                        (DependencySource::PackageFile, other)
+                    }
+                    (other, Some(DependencyKind::Required))
+                        if other != Some(DependencyKind::Required) =>
+                    {
+                        (DependencySource::DependencyFile, other)
+                    }
+                    _ => continue,
+                };
+                contradictions.push(Contradiction {
+                    package: package.clone(),
+                    dependency: edge.package.clone(),
+                    required_by,
+                    other,
+                });
+            }
+        }
+
+        contradictions
+    }
+
+    /// The merged `dependencies` block of `package`; each edge keeps the
+    /// reason of its first source
+    pub fn merged(&self, package: &str) -> Dependencies {
+        let mut merged = Dependencies::default();
+        for edge in self.dependencies(package) {
+            let dependency = Dependency {
+                package: edge.package.clone(),
+                reason: edge.provenance[0].reason.clone(),
+            };
+            match edge.kind {
+                DependencyKind::Required => merged.required.push(dependency),
+                DependencyKind::Optional => merged.optional.push(dependency),
+            }
+        }
+        merged
+    }
+
+    /// Write the merged dependency lists into every package
+    pub fn apply(&self, packages: &mut [Package]) {
+        for package in packages {
+            package.dependencies = self.merged(&package.name);
+        }
+    }
+}
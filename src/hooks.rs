@@ -0,0 +1,602 @@
+//! Safety lints for profile hook commands
+//!
+//! Hooks are shell strings run on users' machines. They are split with a
+//! small POSIX-style tokenizer (quotes, backslash escapes, comments, command
+//! and process substitutions, redirections, subshell parentheses and the
+//! `|`, `|&`, `||`, `&&`, `;`, `&` operators) into simple commands, which are
+//! then checked for dangerous patterns. Shell keywords are skipped and the
+//! script of `sh -c` is checked like the hook itself. A hook may `allow`
+//! lints a reviewer has accepted.
+
+use crate::model::{Hook, HookLint};
+use std::collections::HashSet;
+use std::fmt;
+use thiserror::Error;
+
+/// Shell builtins and base-system utilities a hook may call without a package
+const SYSTEM_COMMANDS: &[&str] = &[
+    ".", "[", "cat", "cd", "chmod", "chown", "command", "cp", "echo", "env", "exit", "export",
+    "false", "grep", "ln", "mkdir", "mv", "printf", "rm", "set", "source", "test", "touch", "true",
+    "unset",
+];
+
+const SHELLS: &[&str] = &["sh", "bash", "zsh", "fish", "dash", "ksh"];
+
+/// Reserved words that may precede a command (`then git pull`)
+const KEYWORDS: &[&str] = &[
+    "if", "then", "else", "elif", "fi", "do", "done", "while", "until", "esac", "{", "}", "!",
+];
+
+/// Reserved words opening a command that runs nothing itself
+const HEADER_KEYWORDS: &[&str] = &["for", "case", "select", "function"];
+
+const NETWORK_COMMANDS: &[&str] = &["curl", "wget", "fetch"];
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum TokenizeError {
+    #[error("unterminated {0} quote")]
+    UnterminatedQuote(char),
+
+    #[error("trailing backslash")]
+    TrailingBackslash,
+
+    #[error("unterminated {0} substitution")]
+    UnterminatedSubstitution(&'static str),
+}
+
+/// A command run inside a word: `$(…)` or backticks, or a `<(…)`/`>(…)`
+/// process substitution
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Substitution {
+    pub body: String,
+    /// `<(…)` or `>(…)`: the word is a path to the command's output
+    pub process: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Token {
+    Word {
+        text: String,
+        /// Contains `*`, `?` or `[` outside quotes
+        unquoted_glob: bool,
+        /// Commands substituted into the word, in order
+        substitutions: Vec<Substitution>,
+    },
+    /// `|`, `|&`, `||`, `&&`, `;`, `&`, `(` or `)`; a line break is a `;`
+    Operator(&'static str),
+    /// `>`, `2>>`, `2>&1`, `&>`, `<` and the like
+    Redirect {
+        text: String,
+        /// The next word is the redirected file (`> out.log`); false for
+        /// descriptor duplication such as `2>&1`
+        takes_target: bool,
+    },
+}
+
+#[derive(Default)]
+struct PartialWord {
+    text: String,
+    unquoted_glob: bool,
+    substitutions: Vec<Substitution>,
+}
+
+type Chars<'a> = std::iter::Peekable<std::str::Chars<'a>>;
+
+/// Read up to the `)` closing an already opened `(`, skipping quoted text
+fn read_parenthesized(chars: &mut Chars) -> Result<String, TokenizeError> {
+    let mut body = String::new();
+    let mut depth = 1;
+    while let Some(c) = chars.next() {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Ok(body);
+                }
+            }
+            '\\' => {
+                body.push(c);
+                if let Some(escaped) = chars.next() {
+                    body.push(escaped);
+                }
+                continue;
+            }
+            '\'' | '"' => {
+                body.push(c);
+                loop {
+                    match chars.next() {
+                        Some(q) if q == c => break,
+                        Some('\\') if c == '"' => {
+                            body.push('\\');
+                            body.extend(chars.next());
+                        }
+                        Some(other) => body.push(other),
+                        None => return Err(TokenizeError::UnterminatedQuote(c)),
+                    }
+                }
+            }
+            _ => {}
+        }
+        body.push(c);
+    }
+    Err(TokenizeError::UnterminatedSubstitution("$("))
+}
+
+/// Read up to the closing backtick
+fn read_backticks(chars: &mut Chars) -> Result<String, TokenizeError> {
+    let mut body = String::new();
+    while let Some(c) = chars.next() {
+        match c {
+            '`' => return Ok(body),
+            '\\' => body.extend(chars.next()),
+            _ => body.push(c),
+        }
+    }
+    Err(TokenizeError::UnterminatedSubstitution("`"))
+}
+
+/// Handle `c` if it opens a substitution (`$(`, a backtick, or `<(`/`>(`
+/// when `process` substitutions are possible), adding it to `word`
+fn substitution(
+    c: char,
+    chars: &mut Chars,
+    word: &mut PartialWord,
+    process: bool,
+) -> Result<bool, TokenizeError> {
+    let (body, process) = match (c, chars.peek()) {
+        ('$', Some('(')) => {
+            chars.next();
+            let body = read_parenthesized(chars)?;
+            word.text.push_str(&format!("$({})", body));
+            // `$((…))` is arithmetic, not a command
+            if body.starts_with('(') && body.ends_with(')') {
+                return Ok(true);
+            }
+            (body, false)
+        }
+        ('`', _) => {
+            let body = read_backticks(chars)?;
+            word.text.push_str(&format!("`{}`", body));
+            (body, false)
+        }
+        ('<' | '>', Some('(')) if process => {
+            chars.next();
+            let body = read_parenthesized(chars)?;
+            word.text.push_str(&format!("{}({})", c, body));
+            (body, true)
+        }
+        _ => return Ok(false),
+    };
+    word.substitutions.push(Substitution { body, process });
+    Ok(true)
+}
+
+/// Read the rest of a redirection whose first character `c` (`<` or `>`)
+/// was just consumed, after an optional descriptor number `text`
+fn redirect(c: char, chars: &mut Chars, mut text: String) -> Token {
+    text.push(c);
+    match (c, chars.peek()) {
+        ('>', Some(&next @ ('>' | '|'))) | ('<', Some(&next @ ('<' | '>'))) => {
+            text.push(next);
+            chars.next();
+            // `<<<` here-string
+            if text.ends_with("<<") && chars.peek() == Some(&'<') {
+                text.push('<');
+                chars.next();
+            }
+        }
+        _ => {}
+    }
+    if chars.peek() != Some(&'&') || text.ends_with("<<") {
+        return Token::Redirect {
+            text,
+            takes_target: true,
+        };
+    }
+    text.push('&');
+    chars.next();
+    let mut duplicated = false;
+    while let Some(&d) = chars.peek().filter(|d| d.is_ascii_digit() || **d == '-') {
+        text.push(d);
+        chars.next();
+        duplicated = true;
+    }
+    Token::Redirect {
+        text,
+        takes_target: !duplicated,
+    }
+}
+
+/// Split a command line into words, redirections and control operators
+pub fn tokenize(command: &str) -> Result<Vec<Token>, TokenizeError> {
+    let mut tokens = Vec::new();
+    let mut word: Option<PartialWord> = None;
+    let mut chars = command.chars().peekable();
+
+    fn finish(tokens: &mut Vec<Token>, word: &mut Option<PartialWord>) {
+        if let Some(word) = word.take() {
+            // A lone `[` is the test command, not a bracket expression
+            let unquoted_glob = word.unquoted_glob && word.text != "[";
+            tokens.push(Token::Word {
+                text: word.text,
+                unquoted_glob,
+                substitutions: word.substitutions,
+            });
+        }
+    }
+
+    while let Some(c) = chars.next() {
+        match c {
+            ' ' | '\t' => finish(&mut tokens, &mut word),
+            '\n' => {
+                finish(&mut tokens, &mut word);
+                // A line ending in an operator continues on the next one
+                if matches!(
+                    tokens.last(),
+                    Some(Token::Word { .. } | Token::Redirect { .. })
+                ) {
+                    tokens.push(Token::Operator(";"));
+                }
+            }
+            '#' if word.is_none() => {
+                while chars.peek().is_some_and(|&c| c != '\n') {
+                    chars.next();
+                }
+            }
+            '&' if chars.peek() == Some(&'>') => {
+                finish(&mut tokens, &mut word);
+                chars.next();
+                let mut text = "&>".to_string();
+                if chars.peek() == Some(&'>') {
+                    text.push('>');
+                    chars.next();
+                }
+                tokens.push(Token::Redirect {
+                    text,
+                    takes_target: true,
+                });
+            }
+            '|' | '&' | ';' | '(' | ')' => {
+                finish(&mut tokens, &mut word);
+                let op = match (c, chars.peek()) {
+                    ('|', Some('|')) => "||",
+                    ('|', Some('&')) => "|&",
+                    ('&', Some('&')) => "&&",
+                    ('|', _) => "|",
+                    ('&', _) => "&",
+                    ('(', _) => "(",
+                    (')', _) => ")",
+                    _ => ";",
+                };
+                if op.len() == 2 {
+                    chars.next();
+                }
+                tokens.push(Token::Operator(op));
+            }
+            '<' | '>' if chars.peek() == Some(&'(') => {
+                let word = word.get_or_insert_with(Default::default);
+                substitution(c, &mut chars, word, true)?;
+            }
+            '<' | '>' => {
+                // Digits right before the operator name the descriptor: `2>`
+                let descriptor = word.as_ref().is_some_and(|w| {
+                    !w.text.is_empty()
+                        && w.substitutions.is_empty()
+                        && w.text.chars().all(|d| d.is_ascii_digit())
+                });
+                let text = if descriptor {
+                    word.take().map(|w| w.text).unwrap_or_default()
+                } else {
+                    finish(&mut tokens, &mut word);
+                    String::new()
+                };
+                tokens.push(redirect(c, &mut chars, text));
+            }
+            '\'' | '"' => {
+                let word = word.get_or_insert_with(Default::default);
+                loop {
+                    match chars.next() {
+                        Some(q) if q == c => break,
+                        Some('\\') if c == '"' => match chars.next() {
+                            Some(escaped) => word.text.push(escaped),
+                            None => return Err(TokenizeError::TrailingBackslash),
+                        },
+                        // Substitutions still run inside double quotes
+                        Some(s @ ('$' | '`')) if c == '"' => {
+                            if !substitution(s, &mut chars, word, false)? {
+                                word.text.push(s);
+                            }
+                        }
+                        Some(other) => word.text.push(other),
+                        None => return Err(TokenizeError::UnterminatedQuote(c)),
+                    }
+                }
+            }
+            '\\' => {
+                let escaped = chars.next().ok_or(TokenizeError::TrailingBackslash)?;
+                word.get_or_insert_with(Default::default).text.push(escaped);
+            }
+            _ => {
+                let word = word.get_or_insert_with(Default::default);
+                if substitution(c, &mut chars, word, true)? {
+                    continue;
+                }
+                // `$?` and `$*` are parameters, not globs
+                word.unquoted_glob |= matches!(c, '*' | '?' | '[') && !word.text.ends_with('$');
+                word.text.push(c);
+            }
+        }
+    }
+    finish(&mut tokens, &mut word);
+
+    Ok(tokens)
+}
+
+/// One problem found in a hook command
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    pub lint: HookLint,
+    pub message: String,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} [{}]", self.message, self.lint)
+    }
+}
+
+/// A word of a simple command
+struct Arg<'a> {
+    text: &'a str,
+    unquoted_glob: bool,
+    substitutions: &'a [Substitution],
+}
+
+/// A simple command: its words, the files it redirects to and the
+/// operator that preceded it
+struct SimpleCommand<'a> {
+    after: Option<&'static str>,
+    words: Vec<Arg<'a>>,
+    targets: Vec<Arg<'a>>,
+}
+
+/// `sudo` options that take an argument
+const SUDO_OPTIONS_WITH_ARGUMENT: &[&str] = &[
+    "-u",
+    "-g",
+    "-C",
+    "-h",
+    "-p",
+    "--user",
+    "--group",
+    "--close-from",
+    "--host",
+    "--prompt",
+];
+
+impl SimpleCommand<'_> {
+    /// Words with leading keywords, `VAR=value` prefixes and a leading
+    /// `sudo`/`env` removed, plus whether the command runs under sudo
+    fn program(&self) -> (bool, &[Arg<'_>]) {
+        let mut words = &self.words[..];
+        let mut sudo = false;
+        while let Some((first, rest)) = words.split_first() {
+            if HEADER_KEYWORDS.contains(&first.text) {
+                return (sudo, &[]);
+            } else if KEYWORDS.contains(&first.text) {
+                words = rest;
+            } else if first.text == "sudo" {
+                sudo = true;
+                words = rest;
+                while let Some((option, rest)) = words.split_first() {
+                    if option.text == "--" {
+                        words = rest;
+                        break;
+                    } else if SUDO_OPTIONS_WITH_ARGUMENT.contains(&option.text) {
+                        words = rest.get(1..).unwrap_or_default();
+                    } else if option.text.starts_with('-') {
+                        words = rest;
+                    } else {
+                        break;
+                    }
+                }
+            } else if first.text == "env" || is_assignment(first.text) {
+                words = rest;
+            } else {
+                break;
+            }
+        }
+        (sudo, words)
+    }
+}
+
+fn is_assignment(word: &str) -> bool {
+    word.split_once('=').is_some_and(|(name, _)| {
+        !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    })
+}
+
+/// `/usr/bin/curl` → `curl`
+fn basename(program: &str) -> &str {
+    program.rsplit('/').next().unwrap_or(program)
+}
+
+fn is_rm_rf(args: &[Arg]) -> bool {
+    let (mut recursive, mut force) = (false, false);
+    for arg in args {
+        match arg.text {
+            "--recursive" => recursive = true,
+            "--force" => force = true,
+            flag if flag.starts_with('-') && !flag.starts_with("--") => {
+                recursive |= flag.contains(['r', 'R']);
+                force |= flag.contains('f');
+            }
+            _ => {}
+        }
+    }
+    recursive && force
+}
+
+/// Whether a shell invocation runs a script produced by another command:
+/// `sh -c "$(curl …)"`, `bash <(curl …)` or `source <(…)`
+fn runs_substituted_script(program: &str, args: &[Arg]) -> bool {
+    let shell = SHELLS.contains(&program);
+    if !shell && program != "source" && program != "." {
+        return false;
+    }
+    args.iter().enumerate().any(|(i, arg)| {
+        let after_c = i > 0 && {
+            let flag = args[i - 1].text;
+            flag.starts_with('-') && !flag.starts_with("--") && flag.contains('c')
+        };
+        arg.substitutions
+            .iter()
+            .any(|s| s.process || (shell && after_c))
+    })
+}
+
+/// The script a shell runs with `-c` (`sh -c 'cmd'`, `bash -ec 'cmd'`)
+fn shell_script<'a>(args: &'a [Arg<'a>]) -> Option<&'a Arg<'a>> {
+    let flag = args.iter().position(|arg| {
+        arg.text.starts_with('-') && !arg.text.starts_with("--") && arg.text.contains('c')
+    })?;
+    args.get(flag + 1)
+}
+
+/// Lint a command line against the binaries its profile installs
+///
+/// Substituted commands (`$(…)`, backticks, `<(…)`) and the script of
+/// `sh -c` are linted as commands of their own. Reports every finding,
+/// ignoring any `allow` annotation.
+pub fn lint_command(
+    command: &str,
+    binaries: &HashSet<&str>,
+) -> Result<Vec<Finding>, TokenizeError> {
+    lint(command, binaries, false)
+}
+
+/// [`lint_command`], with `under_sudo` set for the script of `sudo sh -c`
+fn lint(
+    command: &str,
+    binaries: &HashSet<&str>,
+    under_sudo: bool,
+) -> Result<Vec<Finding>, TokenizeError> {
+    let tokens = tokenize(command)?;
+
+    let mut commands = vec![SimpleCommand {
+        after: None,
+        words: Vec::new(),
+        targets: Vec::new(),
+    }];
+    let mut target_next = false;
+    for token in &tokens {
+        match token {
+            Token::Word {
+                text,
+                unquoted_glob,
+                substitutions,
+            } => {
+                let command = commands.last_mut().unwrap();
+                let arg = Arg {
+                    text,
+                    unquoted_glob: *unquoted_glob,
+                    substitutions,
+                };
+                if std::mem::take(&mut target_next) {
+                    command.targets.push(arg);
+                } else {
+                    command.words.push(arg);
+                }
+            }
+            Token::Redirect { takes_target, .. } => target_next = *takes_target,
+            Token::Operator(op) => {
+                target_next = false;
+                commands.push(SimpleCommand {
+                    after: Some(op),
+                    words: Vec::new(),
+                    targets: Vec::new(),
+                });
+            }
+        }
+    }
+
+    let mut findings = Vec::new();
+    for command in &commands {
+        for word in command.words.iter().chain(&command.targets) {
+            if word.unquoted_glob {
+                findings.push(Finding {
+                    lint: HookLint::UnquotedGlob,
+                    message: format!("unquoted glob '{}'", word.text),
+                });
+            }
+            for substitution in word.substitutions {
+                findings.extend(lint(&substitution.body, binaries, false)?);
+            }
+        }
+
+        let (sudo, words) = command.program();
+        let sudo = sudo || under_sudo;
+        let Some((program, args)) = words.split_first() else {
+            continue;
+        };
+        let program = basename(program.text);
+
+        if matches!(command.after, Some("|" | "|&")) && SHELLS.contains(&program) {
+            findings.push(Finding {
+                lint: HookLint::PipeToShell,
+                message: format!("pipes into {}", program),
+            });
+        }
+        if runs_substituted_script(program, args) {
+            findings.push(Finding {
+                lint: HookLint::PipeToShell,
+                message: format!("runs a substituted script with {}", program),
+            });
+        } else if let Some(script) = shell_script(args).filter(|_| SHELLS.contains(&program)) {
+            findings.extend(lint(script.text, binaries, sudo)?);
+        }
+        if sudo && program == "rm" && is_rm_rf(args) {
+            findings.push(Finding {
+                lint: HookLint::SudoRmRf,
+                message: "runs `sudo rm -rf`".to_string(),
+            });
+        }
+        if NETWORK_COMMANDS.contains(&program) {
+            findings.push(Finding {
+                lint: HookLint::NetworkFetch,
+                message: format!("fetches from the network with {}", program),
+            });
+        }
+        if !SYSTEM_COMMANDS.contains(&program) && !binaries.contains(program) {
+            findings.push(Finding {
+                lint: HookLint::UnknownBinary,
+                message: format!(
+                    "runs '{}', which no package in the profile provides",
+                    program
+                ),
+            });
+        }
+    }
+
+    Ok(findings)
+}
+
+/// Lint a hook, dropping findings its `allow` list accepts
+pub fn lint_hook(hook: &Hook, binaries: &HashSet<&str>) -> Result<Vec<Finding>, TokenizeError> {
+    let mut findings = lint_command(&hook.command, binaries)?;
+    findings.retain(|f| !hook.allow.contains(&f.lint));
+    Ok(findings)
+}
+
+/// `allow` entries that suppress nothing, so the exception can be dropped
+pub fn unused_allows(hook: &Hook, binaries: &HashSet<&str>) -> Vec<HookLint> {
+    let Ok(findings) = lint_command(&hook.command, binaries) else {
+        return Vec::new();
+    };
+    hook.allow
+        .iter()
+        .filter(|lint| !findings.iter().any(|f| f.lint == **lint))
+        .copied()
+        .collect()
+}
@@ -0,0 +1,241 @@
+//! Installed packages, read from local package-manager state
+//!
+//! Each manager keeps its own record of what is installed: dpkg's `status`
+//! file, pacman's local database directory, the rpm database (read through
+//! `rpm -qa`), and Homebrew's cellar (read through `brew list --versions`).
+//! [`parse`] and [`read`] turn that state, as captured text or a path, into
+//! native names; [`Installed::resolve`] maps those back to canonical packages
+//! through the alias index, so `docker.io` and `fd-find` count as docker and
+//! fd.
+
+use crate::alias::{AliasIndex, NameKind};
+use crate::model::Manager;
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::cmp::Ordering;
+use std::fs;
+use std::path::Path;
+
+/// A package as the manager names it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct NativePackage {
+    pub name: String,
+    pub version: Option<String>,
+}
+
+impl NativePackage {
+    fn new(name: &str, version: Option<&str>) -> Self {
+        NativePackage {
+            name: name.to_string(),
+            version: version.map(String::from),
+        }
+    }
+}
+
+/// Parse captured state for `manager`:
+///
+/// - apt: the dpkg `status` file (only `install ok installed` entries)
+/// - pacman: `pacman -Q` output (`name version` per line)
+/// - dnf: `rpm -qa` output, either full `name-version-release.arch` names or
+///   `--queryformat '%{NAME} %{VERSION}-%{RELEASE}\n'` lines
+/// - brew: `brew list --versions` output (`name version...` per line)
+pub fn parse(manager: Manager, text: &str) -> Vec<NativePackage> {
+    match manager {
+        Manager::Apt => parse_dpkg_status(text),
+        Manager::Pacman => parse_name_version(text),
+        Manager::Dnf => parse_rpm(text),
+        Manager::Brew => parse_name_version(text),
+    }
+}
+
+/// Read state for `manager` from a file of the form [`parse`] takes, or, for
+/// pacman, from the local database directory (`/var/lib/pacman/local`)
+pub fn read(manager: Manager, path: impl AsRef<Path>) -> Result<Vec<NativePackage>> {
+    let path = path.as_ref();
+    if manager == Manager::Pacman && path.is_dir() {
+        return read_pacman_db(path);
+    }
+    let text =
+        fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(parse(manager, &text))
+}
+
+/// Entries of a dpkg `status` file whose status is `installed`
+pub fn parse_dpkg_status(text: &str) -> Vec<NativePackage> {
+    let mut packages: Vec<NativePackage> = Vec::new();
+    for stanza in text.split("\n\n") {
+        let field = |key: &str| {
+            stanza
+                .lines()
+                .find_map(|l| l.strip_prefix(key)?.strip_prefix(':'))
+                .map(str::trim)
+        };
+        let installed = field("Status").is_some_and(|s| s.ends_with(" installed"));
+        if let (true, Some(name)) = (installed, field("Package")) {
+            // Multi-arch packages appear once per architecture
+            if !packages.iter().any(|p| p.name == name) {
+                packages.push(NativePackage::new(name, field("Version")));
+            }
+        }
+    }
+    packages
+}
+
+/// Packages in a pacman local database: one `<name>-<version>/desc` per
+/// package
+pub fn read_pacman_db(dir: impl AsRef<Path>) -> Result<Vec<NativePackage>> {
+    let dir = dir.as_ref();
+    let mut entries: Vec<_> = fs::read_dir(dir)
+        .with_context(|| format!("Failed to read {}", dir.display()))?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path().join("desc"))
+        .filter(|desc| desc.is_file())
+        .collect();
+    entries.sort();
+
+    let mut packages = Vec::new();
+    for desc in entries {
+        let text = fs::read_to_string(&desc)
+            .with_context(|| format!("Failed to read {}", desc.display()))?;
+        if let Some(package) = parse_pacman_desc(&text) {
+            packages.push(package);
+        }
+    }
+    Ok(packages)
+}
+
+/// The `%NAME%` and `%VERSION%` of a pacman `desc` file
+pub fn parse_pacman_desc(text: &str) -> Option<NativePackage> {
+    let value = |section: &str| {
+        let mut lines = text.lines().skip_while(|l| l.trim() != section);
+        lines.next()?;
+        lines.next().map(str::trim).filter(|v| !v.is_empty())
+    };
+    Some(NativePackage::new(value("%NAME%")?, value("%VERSION%")))
+}
+
+/// `name version` lines (`pacman -Q`, `brew list --versions`); with several
+/// versions the highest is kept
+fn parse_name_version(text: &str) -> Vec<NativePackage> {
+    text.lines()
+        .filter_map(|line| {
+            let mut words = line.split_whitespace();
+            let name = words.next()?;
+            Some(NativePackage::new(
+                name,
+                words.max_by(|a, b| compare_versions(a, b)),
+            ))
+        })
+        .collect()
+}
+
+/// Compare version strings part by part (split on `.`, `-`, `_` and `+`),
+/// numerically where both parts are numbers
+fn compare_versions(a: &str, b: &str) -> Ordering {
+    const SEPARATORS: [char; 4] = ['.', '-', '_', '+'];
+    let mut left = a.split(SEPARATORS);
+    let mut right = b.split(SEPARATORS);
+    loop {
+        let ordering = match (left.next(), right.next()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) => match (x.parse::<u64>(), y.parse::<u64>()) {
+                (Ok(x), Ok(y)) => x.cmp(&y),
+                _ => x.cmp(y),
+            },
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+}
+
+/// `rpm -qa` lines: `name version` pairs or `name-version-release[.arch]`
+pub fn parse_rpm(text: &str) -> Vec<NativePackage> {
+    const ARCHES: [&str; 6] = [
+        ".x86_64", ".aarch64", ".noarch", ".i686", ".ppc64le", ".s390x",
+    ];
+
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            if let Some((name, version)) = line.split_once(char::is_whitespace) {
+                return NativePackage::new(name, Some(version.trim()));
+            }
+            let nvr = ARCHES
+                .iter()
+                .find_map(|arch| line.strip_suffix(arch))
+                .unwrap_or(line);
+            match nvr.rsplitn(3, '-').collect::<Vec<_>>()[..] {
+                [release, version, name] => {
+                    NativePackage::new(name, Some(&format!("{}-{}", version, release)))
+                }
+                _ => NativePackage::new(nvr, None),
+            }
+        })
+        .collect()
+}
+
+/// A native package matched to a canonical one
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct InstalledPackage {
+    /// Canonical package name
+    pub package: String,
+    /// Name the manager reports
+    pub native: String,
+    pub version: Option<String>,
+    /// How `native` relates to `package`
+    pub kind: NameKind,
+}
+
+/// What one manager has installed, in canonical terms
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Installed {
+    pub manager: Manager,
+    /// One entry per canonical package, in the order the manager listed them
+    pub packages: Vec<InstalledPackage>,
+    /// Native packages the database does not know
+    pub unknown: Vec<NativePackage>,
+}
+
+impl Installed {
+    /// Map native packages to canonical ones through `index`, preferring
+    /// `manager`'s own platform names
+    pub fn resolve(index: &AliasIndex, manager: Manager, natives: Vec<NativePackage>) -> Self {
+        let mut packages: Vec<InstalledPackage> = Vec::new();
+        let mut unknown = Vec::new();
+
+        for native in natives {
+            match index.resolve_on(manager, &native.name) {
+                Some(entry) => {
+                    if !packages.iter().any(|p| p.package == entry.canonical) {
+                        packages.push(InstalledPackage {
+                            package: entry.canonical,
+                            native: native.name,
+                            version: native.version,
+                            kind: entry.kind,
+                        });
+                    }
+                }
+                None => unknown.push(native),
+            }
+        }
+
+        Installed {
+            manager,
+            packages,
+            unknown,
+        }
+    }
+
+    pub fn package(&self, name: &str) -> Option<&InstalledPackage> {
+        self.packages.iter().find(|p| p.package == name)
+    }
+
+    /// Canonical names of the installed packages
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.packages.iter().map(|p| p.package.as_str())
+    }
+}
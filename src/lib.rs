@@ -0,0 +1,32 @@
+//! Shared library for the Heimdal Packages Database tooling
+//!
+//! The binaries in `scripts/` are thin front-ends over these modules, and the
+//! same API is available to Heimdal and other consumers of the database.
+
+pub mod alias;
+pub mod ansible;
+pub mod choice;
+pub mod conflicts;
+pub mod container;
+pub mod coverage;
+pub mod database;
+pub mod delta;
+pub mod deprecation;
+pub mod diff;
+pub mod dotfiles;
+pub mod drift;
+pub mod export;
+pub mod graph;
+pub mod hooks;
+pub mod installed;
+pub mod loader;
+pub mod maintenance;
+pub mod model;
+pub mod popularity;
+pub mod rdeps;
+pub mod recommend;
+pub mod reconcile;
+pub mod relations;
+pub mod resolve;
+pub mod signature;
+pub mod template;
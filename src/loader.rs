@@ -0,0 +1,284 @@
+//! Load the YAML source tree from disk or from a git revision
+
+use crate::model::{
+    Dependencies, Mapping, Package, PackageGroup, Profile, SuggestionPattern, Template,
+};
+use anyhow::{Context, Result};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use walkdir::WalkDir;
+
+/// Every entity loaded from a checkout of this repository
+#[derive(Debug, Default)]
+pub struct SourceTree {
+    pub packages: Vec<Package>,
+    pub groups: Vec<PackageGroup>,
+    pub profiles: Vec<Profile>,
+    pub templates: Vec<Template>,
+    pub mappings: Vec<Mapping>,
+    /// `dependencies/` entries, keyed by dependent package
+    pub dependencies: BTreeMap<String, Dependencies>,
+    pub suggestions: Vec<SuggestionPattern>,
+}
+
+impl SourceTree {
+    /// Load all entities below `root` (the repository root)
+    pub fn load(root: impl AsRef<Path>) -> Result<Self> {
+        let root = root.as_ref();
+        Ok(SourceTree {
+            packages: load_packages(root.join("packages"))?,
+            groups: load_groups(root.join("groups"))?,
+            profiles: load_profiles(root.join("profiles"))?,
+            templates: load_templates(root.join("templates"))?,
+            mappings: load_mappings(root.join("mappings"))?,
+            dependencies: load_dependencies(root.join("dependencies"))?,
+            suggestions: load_suggestions(root.join("suggestions"))?,
+        })
+    }
+
+    /// Load all entities as they were at git revision `rev` of the
+    /// repository at `repo`, without touching the working tree
+    pub fn from_git(repo: impl AsRef<Path>, rev: &str) -> Result<Self> {
+        let repo = repo.as_ref();
+        Ok(SourceTree {
+            packages: parse_packages(git_files(repo, rev, "packages")?)?,
+            groups: parse_all(git_files(repo, rev, "groups")?)?,
+            profiles: parse_all(git_files(repo, rev, "profiles")?)?,
+            templates: parse_all(git_files(repo, rev, "templates")?)?,
+            mappings: parse_mappings(git_files(repo, rev, "mappings")?)?,
+            dependencies: parse_dependencies(git_files(repo, rev, "dependencies")?)?,
+            suggestions: parse_suggestions(git_files(repo, rev, "suggestions")?)?,
+        })
+    }
+
+    /// The package called `name`, or the one renamed from it
+    pub fn package(&self, name: &str) -> Option<&Package> {
+        self.packages.iter().find(|p| p.name == name).or_else(|| {
+            self.packages
+                .iter()
+                .find(|p| p.renamed_from.iter().any(|old| old == name))
+        })
+    }
+
+    pub fn group(&self, id: &str) -> Option<&PackageGroup> {
+        self.groups.iter().find(|g| g.id == id)
+    }
+
+    pub fn profile(&self, id: &str) -> Option<&Profile> {
+        self.profiles.iter().find(|p| p.id == id)
+    }
+
+    pub fn template(&self, id: &str) -> Option<&Template> {
+        self.templates.iter().find(|t| t.id == id)
+    }
+
+    pub fn mapping(&self, canonical: &str) -> Option<&Mapping> {
+        self.mappings.iter().find(|m| m.canonical == canonical)
+    }
+}
+
+/// List the `.yaml` files below `dir`, sorted by path
+///
+/// A missing directory yields an empty list.
+pub fn yaml_files(dir: impl AsRef<Path>) -> Vec<PathBuf> {
+    let dir = dir.as_ref();
+    if !dir.exists() {
+        return Vec::new();
+    }
+
+    WalkDir::new(dir)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().extension().is_some_and(|ext| ext == "yaml"))
+        .map(|e| e.into_path())
+        .collect()
+}
+
+/// Rewrite the files of `packages` under `dir` (found by file name) with
+/// `edit(package, content)`, returning the number of files changed
+pub fn rewrite_package_files<'a>(
+    dir: impl AsRef<Path>,
+    packages: impl IntoIterator<Item = &'a str>,
+    mut edit: impl FnMut(&str, &str) -> String,
+) -> Result<usize> {
+    let files = yaml_files(dir);
+    let mut changed = 0;
+    for package in packages {
+        let path = files
+            .iter()
+            .find(|p| p.file_stem().is_some_and(|s| s == package))
+            .with_context(|| format!("No package file for '{}'", package))?;
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let edited = edit(package, &content);
+        if edited != content {
+            fs::write(path, edited)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            changed += 1;
+        }
+    }
+    Ok(changed)
+}
+
+/// Read every `.yaml` file below `dir` as `(path, content)` pairs
+fn read_files(dir: impl AsRef<Path>) -> Result<Vec<(PathBuf, String)>> {
+    yaml_files(dir)
+        .into_iter()
+        .map(|path| {
+            let content = fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            Ok((path, content))
+        })
+        .collect()
+}
+
+/// Read every `.yaml` file below `dir` at git revision `rev`
+fn git_files(repo: &Path, rev: &str, dir: &str) -> Result<Vec<(PathBuf, String)>> {
+    let listing = git(repo, &["ls-tree", "-r", "--name-only", rev, "--", dir])?;
+
+    let mut paths: Vec<&str> = listing
+        .lines()
+        .filter(|line| line.ends_with(".yaml"))
+        .collect();
+    paths.sort();
+
+    paths
+        .into_iter()
+        .map(|path| {
+            let content = git(repo, &["show", &format!("{}:{}", rev, path)])?;
+            Ok((PathBuf::from(path), content))
+        })
+        .collect()
+}
+
+fn git(repo: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(args)
+        .output()
+        .context("Failed to run git")?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    String::from_utf8(output.stdout).context("git produced non-UTF-8 output")
+}
+
+fn parse<T: DeserializeOwned>(path: &Path, content: &str) -> Result<T> {
+    serde_yaml::from_str(content).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+fn parse_all<T: DeserializeOwned>(files: Vec<(PathBuf, String)>) -> Result<Vec<T>> {
+    files
+        .iter()
+        .map(|(path, content)| parse(path, content))
+        .collect()
+}
+
+fn parse_packages(files: Vec<(PathBuf, String)>) -> Result<Vec<Package>> {
+    let mut packages = Vec::new();
+
+    for (path, content) in files {
+        let package: Package = parse(&path, &content)?;
+
+        // Validate filename matches package name
+        let expected_name = format!("{}.yaml", package.name);
+        let actual_name = path.file_name().unwrap().to_str().unwrap();
+        if actual_name != expected_name {
+            anyhow::bail!(
+                "Filename mismatch: {} should be {}",
+                path.display(),
+                expected_name
+            );
+        }
+
+        packages.push(package);
+    }
+
+    Ok(packages)
+}
+
+fn parse_mappings(files: Vec<(PathBuf, String)>) -> Result<Vec<Mapping>> {
+    let mut mappings = Vec::new();
+
+    for (path, content) in files {
+        let file: BTreeMap<String, Mapping> = parse(&path, &content)?;
+        mappings.extend(file.into_values());
+    }
+
+    Ok(mappings)
+}
+
+fn parse_dependencies(files: Vec<(PathBuf, String)>) -> Result<BTreeMap<String, Dependencies>> {
+    let mut dependencies: BTreeMap<String, Dependencies> = BTreeMap::new();
+
+    for (path, content) in files {
+        let file: BTreeMap<String, Dependencies> = parse(&path, &content)?;
+        for (package, deps) in file {
+            let entry = dependencies.entry(package).or_default();
+            entry.required.extend(deps.required);
+            entry.optional.extend(deps.optional);
+        }
+    }
+
+    Ok(dependencies)
+}
+
+/// A `suggestions/*.yaml` file
+#[derive(Deserialize)]
+struct SuggestionFile {
+    patterns: Vec<SuggestionPattern>,
+}
+
+fn parse_suggestions(files: Vec<(PathBuf, String)>) -> Result<Vec<SuggestionPattern>> {
+    let mut patterns = Vec::new();
+
+    for (path, content) in files {
+        let file: SuggestionFile = parse(&path, &content)?;
+        patterns.extend(file.patterns);
+    }
+
+    Ok(patterns)
+}
+
+pub fn load_packages(dir: impl AsRef<Path>) -> Result<Vec<Package>> {
+    parse_packages(read_files(dir)?)
+}
+
+pub fn load_groups(dir: impl AsRef<Path>) -> Result<Vec<PackageGroup>> {
+    parse_all(read_files(dir)?)
+}
+
+pub fn load_profiles(dir: impl AsRef<Path>) -> Result<Vec<Profile>> {
+    parse_all(read_files(dir)?)
+}
+
+pub fn load_templates(dir: impl AsRef<Path>) -> Result<Vec<Template>> {
+    parse_all(read_files(dir)?)
+}
+
+/// Load every mapping file, flattening the per-file maps into one list
+pub fn load_mappings(dir: impl AsRef<Path>) -> Result<Vec<Mapping>> {
+    parse_mappings(read_files(dir)?)
+}
+
+/// Load every dependency file, combining entries for the same package
+pub fn load_dependencies(dir: impl AsRef<Path>) -> Result<BTreeMap<String, Dependencies>> {
+    parse_dependencies(read_files(dir)?)
+}
+
+/// Load every suggestion file's patterns into one list
+pub fn load_suggestions(dir: impl AsRef<Path>) -> Result<Vec<SuggestionPattern>> {
+    parse_suggestions(read_files(dir)?)
+}
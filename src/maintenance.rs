@@ -0,0 +1,159 @@
+//! Package ownership and freshness
+//!
+//! A package may name its `maintainers` (GitHub handles), the database
+//! version it was `added_in`, and the date it was `last_verified` against
+//! upstream. `added_in` is informational only: the format version does not
+//! advance with data releases, so it is not checked against a current
+//! version. [`check`] rejects malformed values, [`stale`] finds entries not
+//! verified within a threshold, and [`MaintenanceReport`] summarises who
+//! looks after what.
+
+use crate::model::Package;
+use chrono::NaiveDate;
+use serde::Serialize;
+use std::fmt;
+
+/// Entries older than this many days are reported as stale by default
+pub const DEFAULT_STALE_DAYS: i64 = 365;
+
+/// A malformed `maintainers` or `last_verified` value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MaintenanceError {
+    /// Not a valid GitHub handle
+    InvalidHandle { package: String, handle: String },
+    /// Listed twice, ignoring case
+    DuplicateMaintainer { package: String, handle: String },
+    /// `last_verified` is after `today`
+    FutureVerification { package: String, date: NaiveDate },
+}
+
+impl fmt::Display for MaintenanceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MaintenanceError::InvalidHandle { package, handle } => {
+                write!(f, "'{}' lists invalid GitHub handle '{}'", package, handle)
+            }
+            MaintenanceError::DuplicateMaintainer { package, handle } => {
+                write!(f, "'{}' lists maintainer '{}' twice", package, handle)
+            }
+            MaintenanceError::FutureVerification { package, date } => {
+                write!(
+                    f,
+                    "'{}' was last verified in the future ({})",
+                    package, date
+                )
+            }
+        }
+    }
+}
+
+/// GitHub's rules: 1-39 letters, digits or single hyphens, not starting or
+/// ending with a hyphen
+pub fn is_github_handle(handle: &str) -> bool {
+    (1..=39).contains(&handle.len())
+        && handle
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-')
+        && !handle.starts_with('-')
+        && !handle.ends_with('-')
+        && !handle.contains("--")
+}
+
+/// Check every package's maintenance fields
+pub fn check(packages: &[Package], today: NaiveDate) -> Vec<MaintenanceError> {
+    let mut errors = Vec::new();
+
+    for package in packages {
+        let mut seen: Vec<String> = Vec::new();
+        for handle in &package.maintainers {
+            if !is_github_handle(handle) {
+                errors.push(MaintenanceError::InvalidHandle {
+                    package: package.name.clone(),
+                    handle: handle.clone(),
+                });
+            } else if seen.contains(&handle.to_lowercase()) {
+                errors.push(MaintenanceError::DuplicateMaintainer {
+                    package: package.name.clone(),
+                    handle: handle.clone(),
+                });
+            }
+            seen.push(handle.to_lowercase());
+        }
+        if let Some(date) = package.last_verified.filter(|&d| d > today) {
+            errors.push(MaintenanceError::FutureVerification {
+                package: package.name.clone(),
+                date,
+            });
+        }
+    }
+
+    errors
+}
+
+/// A package last verified more than the threshold ago
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StaleEntry {
+    pub package: String,
+    pub last_verified: NaiveDate,
+    pub days: i64,
+}
+
+impl fmt::Display for StaleEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "'{}' was last verified {} days ago ({})",
+            self.package, self.days, self.last_verified
+        )
+    }
+}
+
+/// Packages whose `last_verified` is more than `max_days` before `today`,
+/// oldest first. Packages without a date are not included.
+pub fn stale(packages: &[Package], today: NaiveDate, max_days: i64) -> Vec<StaleEntry> {
+    let mut entries: Vec<StaleEntry> = packages
+        .iter()
+        .filter_map(|p| {
+            let last_verified = p.last_verified?;
+            let days = (today - last_verified).num_days();
+            (days > max_days).then(|| StaleEntry {
+                package: p.name.clone(),
+                last_verified,
+                days,
+            })
+        })
+        .collect();
+    entries.sort_by(|a, b| b.days.cmp(&a.days).then_with(|| a.package.cmp(&b.package)));
+    entries
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MaintenanceReport {
+    pub packages: usize,
+    /// Packages without maintainers
+    pub unmaintained: Vec<String>,
+    /// Packages never verified
+    pub unverified: Vec<String>,
+    pub stale: Vec<StaleEntry>,
+}
+
+impl MaintenanceReport {
+    pub fn compute(packages: &[Package], today: NaiveDate, max_days: i64) -> Self {
+        let names = |keep: fn(&Package) -> bool| -> Vec<String> {
+            let mut names: Vec<String> = packages
+                .iter()
+                .filter(|p| keep(p))
+                .map(|p| p.name.clone())
+                .collect();
+            names.sort();
+            names
+        };
+
+        MaintenanceReport {
+            packages: packages.len(),
+            unmaintained: names(|p| p.maintainers.is_empty()),
+            unverified: names(|p| p.last_verified.is_none()),
+            stale: stale(packages, today, max_days),
+        }
+    }
+}
@@ -0,0 +1,575 @@
+//! Data model for the YAML source tree
+//!
+//! These types mirror the JSON schemas in `schemas/` and are shared by the
+//! compiler, the validator and the reporting tools.
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Package managers that have a name entry in `platforms`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Manager {
+    Apt,
+    Brew,
+    Dnf,
+    Pacman,
+}
+
+impl Manager {
+    pub const ALL: [Manager; 4] = [Manager::Apt, Manager::Brew, Manager::Dnf, Manager::Pacman];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Manager::Apt => "apt",
+            Manager::Brew => "brew",
+            Manager::Dnf => "dnf",
+            Manager::Pacman => "pacman",
+        }
+    }
+
+    /// The `platform_overrides` key that applies to this manager
+    pub fn override_key(&self) -> &'static str {
+        match self {
+            Manager::Brew => "macos",
+            Manager::Apt | Manager::Dnf | Manager::Pacman => "linux",
+        }
+    }
+}
+
+impl fmt::Display for Manager {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for Manager {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Manager::ALL
+            .into_iter()
+            .find(|m| m.as_str() == s)
+            .ok_or_else(|| anyhow::anyhow!("Unknown package manager: '{}'", s))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Package {
+    pub name: String,
+    pub description: String,
+    pub category: String,
+    pub popularity: u8,
+    pub platforms: Platforms,
+    #[serde(default)]
+    pub dependencies: Dependencies,
+    #[serde(default)]
+    pub alternatives: Vec<String>,
+    #[serde(default)]
+    pub related: Vec<String>,
+    /// Packages that cannot be installed alongside this one
+    #[serde(default)]
+    pub conflicts: Vec<String>,
+    pub tags: Vec<String>,
+    /// Executables installed, when they differ from `name`
+    #[serde(default)]
+    pub binaries: Vec<String>,
+    /// Still installable, but profiles should move off it
+    #[serde(default)]
+    pub deprecated: bool,
+    /// Package to install instead, for a deprecated package
+    pub replaced_by: Option<String>,
+    /// Former names, which keep resolving to this package
+    #[serde(default)]
+    pub renamed_from: Vec<String>,
+    /// GitHub handles of the people to ask about this entry
+    #[serde(default)]
+    pub maintainers: Vec<String>,
+    /// Database version that first shipped the package
+    pub added_in: Option<u32>,
+    /// When the entry was last checked against upstream
+    pub last_verified: Option<NaiveDate>,
+    pub website: Option<String>,
+    pub license: Option<String>,
+    pub source: Option<String>,
+}
+
+impl Package {
+    /// Executables this package provides (`binaries`, or else its name)
+    pub fn provided_binaries(&self) -> Vec<&str> {
+        if self.binaries.is_empty() {
+            vec![self.name.as_str()]
+        } else {
+            self.binaries.iter().map(String::as_str).collect()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Platforms {
+    pub apt: Option<String>,
+    pub brew: Option<String>,
+    pub dnf: Option<String>,
+    pub pacman: Option<String>,
+    /// Alpine package name, used for container images
+    pub apk: Option<String>,
+    pub mas: Option<i64>,
+}
+
+impl Platforms {
+    pub fn get(&self, manager: Manager) -> Option<&str> {
+        match manager {
+            Manager::Apt => self.apt.as_deref(),
+            Manager::Brew => self.brew.as_deref(),
+            Manager::Dnf => self.dnf.as_deref(),
+            Manager::Pacman => self.pacman.as_deref(),
+        }
+    }
+
+    /// Package names by platform: every manager, then `apk`
+    pub fn names(&self) -> [(&'static str, Option<&str>); 5] {
+        [
+            ("apt", self.apt.as_deref()),
+            ("brew", self.brew.as_deref()),
+            ("dnf", self.dnf.as_deref()),
+            ("pacman", self.pacman.as_deref()),
+            ("apk", self.apk.as_deref()),
+        ]
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Dependencies {
+    #[serde(default)]
+    pub required: Vec<Dependency>,
+    #[serde(default)]
+    pub optional: Vec<Dependency>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Dependency {
+    pub package: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PackageGroup {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub category: String,
+    pub packages: GroupPackages,
+    /// Slots installing one of several alternatives
+    #[serde(default)]
+    pub choices: Vec<Choice>,
+    #[serde(default)]
+    pub platform_overrides: HashMap<String, PlatformOverride>,
+}
+
+impl PackageGroup {
+    /// Required packages followed by optional ones
+    pub fn all_packages(&self) -> impl Iterator<Item = &String> {
+        self.packages
+            .required
+            .iter()
+            .chain(self.packages.optional.iter())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GroupPackages {
+    pub required: Vec<String>,
+    #[serde(default)]
+    pub optional: Vec<String>,
+}
+
+/// A slot installing exactly one of `options`: the user's choice for `id`,
+/// or else `default`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Choice {
+    /// Slot name users choose by, e.g. `editor`
+    pub id: String,
+    pub description: Option<String>,
+    pub options: Vec<String>,
+    pub default: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PlatformOverride {
+    #[serde(default)]
+    pub packages: Vec<String>,
+    #[serde(default)]
+    pub casks: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Profile {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    #[serde(rename = "type")]
+    pub profile_type: String,
+    /// Parent profiles, resolved in order before this profile's own packages
+    #[serde(default)]
+    pub extends: Vec<String>,
+    /// Groups whose required packages are pulled in
+    #[serde(default)]
+    pub include_groups: Vec<String>,
+    /// Inherited packages to drop
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    #[serde(default)]
+    pub packages: ProfilePackages,
+    /// Slots installing one of several alternatives
+    #[serde(default)]
+    pub choices: Vec<Choice>,
+    #[serde(default)]
+    pub dotfiles: Vec<Dotfile>,
+    #[serde(default)]
+    pub hooks: Hooks,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProfilePackages {
+    #[serde(default)]
+    pub essential: Vec<String>,
+    #[serde(default)]
+    pub terminal: Vec<String>,
+    #[serde(default)]
+    pub editor: Vec<String>,
+    #[serde(default)]
+    pub languages: Vec<String>,
+    #[serde(default)]
+    pub containers: Vec<String>,
+    #[serde(default)]
+    pub infrastructure: Vec<String>,
+    #[serde(default)]
+    pub databases: Vec<String>,
+    #[serde(default)]
+    pub other: Vec<String>,
+}
+
+impl ProfilePackages {
+    /// Package sections in schema order, as `(section, packages)` pairs
+    pub fn sections(&self) -> [(&'static str, &[String]); 8] {
+        [
+            ("essential", &self.essential),
+            ("terminal", &self.terminal),
+            ("editor", &self.editor),
+            ("languages", &self.languages),
+            ("containers", &self.containers),
+            ("infrastructure", &self.infrastructure),
+            ("databases", &self.databases),
+            ("other", &self.other),
+        ]
+    }
+
+    /// Every package in the profile, in section order
+    pub fn all(&self) -> impl Iterator<Item = &String> {
+        self.sections()
+            .into_iter()
+            .flat_map(|(_, packages)| packages.iter())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Dotfile {
+    pub source: String,
+    pub target: String,
+    /// The package this dotfile configures
+    pub package: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Hooks {
+    #[serde(default)]
+    pub pre_install: Vec<Hook>,
+    #[serde(default)]
+    pub post_install: Vec<Hook>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Hook {
+    pub command: String,
+    pub description: Option<String>,
+    /// Lints reviewed and accepted for this command
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allow: Vec<HookLint>,
+}
+
+/// Safety checks applied to hook commands (see `hooks`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum HookLint {
+    PipeToShell,
+    SudoRmRf,
+    UnquotedGlob,
+    NetworkFetch,
+    UnknownBinary,
+}
+
+impl HookLint {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HookLint::PipeToShell => "pipe-to-shell",
+            HookLint::SudoRmRf => "sudo-rm-rf",
+            HookLint::UnquotedGlob => "unquoted-glob",
+            HookLint::NetworkFetch => "network-fetch",
+            HookLint::UnknownBinary => "unknown-binary",
+        }
+    }
+
+    /// Whether a finding fails validation rather than warning
+    pub fn is_error(&self) -> bool {
+        matches!(
+            self,
+            HookLint::PipeToShell | HookLint::SudoRmRf | HookLint::UnknownBinary
+        )
+    }
+}
+
+impl fmt::Display for HookLint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Mapping {
+    pub canonical: String,
+    pub platforms: Platforms,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+}
+
+/// Packages suggested when a project contains any of `files`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SuggestionPattern {
+    /// File names or glob patterns
+    pub files: Vec<String>,
+    pub suggests: Vec<Suggestion>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Suggestion {
+    pub package: String,
+    pub priority: SuggestionPriority,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SuggestionPriority {
+    Required,
+    Recommended,
+    Optional,
+}
+
+impl SuggestionPriority {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SuggestionPriority::Required => "required",
+            SuggestionPriority::Recommended => "recommended",
+            SuggestionPriority::Optional => "optional",
+        }
+    }
+}
+
+/// Target platform of a template
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TemplatePlatform {
+    Macos,
+    Linux,
+    CrossPlatform,
+}
+
+impl TemplatePlatform {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TemplatePlatform::Macos => "macos",
+            TemplatePlatform::Linux => "linux",
+            TemplatePlatform::CrossPlatform => "cross-platform",
+        }
+    }
+
+    /// Package managers a template for this platform installs with
+    pub fn managers(&self) -> Vec<Manager> {
+        Manager::ALL
+            .into_iter()
+            .filter(|m| self.covers(m.override_key()))
+            .collect()
+    }
+
+    /// Whether a `platform_overrides` key (`macos`, `linux`) applies
+    pub fn covers(&self, override_key: &str) -> bool {
+        match self {
+            TemplatePlatform::CrossPlatform => true,
+            platform => platform.as_str() == override_key,
+        }
+    }
+}
+
+impl fmt::Display for TemplatePlatform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Template {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub platform: TemplatePlatform,
+    pub recommended_for: Vec<String>,
+    /// Base profile, resolved with its own inheritance chain
+    pub profile: Option<String>,
+    #[serde(default)]
+    pub groups: Vec<String>,
+    #[serde(default)]
+    pub custom_packages: Vec<String>,
+    #[serde(default)]
+    pub settings: Settings,
+}
+
+/// Typed template `settings`; unknown keys are rejected
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Settings {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub security: Option<SecuritySettings>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updates: Option<UpdateSettings>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub monitoring: Option<MonitoringSettings>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub homebrew: Option<HomebrewSettings>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shell: Option<ShellSettings>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub editor: Option<EditorSettings>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git: Option<GitSettings>,
+}
+
+impl Settings {
+    /// Packages named by the settings, as `(setting, package)` pairs
+    pub fn packages(&self) -> Vec<(&'static str, &str)> {
+        let mut packages = Vec::new();
+        if let Some(shell) = &self.shell {
+            if let Some(default) = shell.default {
+                packages.push(("shell.default", default.as_str()));
+            }
+            if let Some(theme) = &shell.theme {
+                packages.push(("shell.theme", theme.as_str()));
+            }
+        }
+        if let Some(default) = self.editor.as_ref().and_then(|e| e.default.as_ref()) {
+            packages.push(("editor.default", default.as_str()));
+        }
+        packages
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Toggle {
+    Enabled,
+    Disabled,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl Shell {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Shell::Bash => "bash",
+            Shell::Zsh => "zsh",
+            Shell::Fish => "fish",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SecuritySettings {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub firewall: Option<Toggle>,
+    /// Disable SSH password authentication
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ssh_key_only: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub disable_root_login: Option<bool>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UpdateSettings {
+    /// Install security updates unattended
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auto_security: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub frequency: Option<Frequency>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MonitoringSettings {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HomebrewSettings {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auto_update: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cleanup_frequency: Option<Frequency>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ShellSettings {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<Shell>,
+    /// Prompt theme package, e.g. `starship`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub theme: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EditorSettings {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<String>,
+}
+
+/// Git identity written to the global config
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GitSettings {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_branch: Option<String>,
+}
@@ -0,0 +1,317 @@
+//! Popularity scores recomputed from usage data
+//!
+//! Hand-set `popularity` values drift and have no common scale. This module
+//! derives them from counts supplied as local files:
+//!
+//! - Debian popcon `by_inst` dumps (installations per apt package)
+//! - Homebrew analytics JSON exports (`formulae.brew.sh/api/analytics/...`)
+//! - GitHub star counts, as `name,stars` CSV lines where `name` is a package
+//!   name or the `owner/repo` of its `source`
+//!
+//! Counts are heavy-tailed (git has orders of magnitude more installs than
+//! pandoc), so each source is scored on a log scale against the most used
+//! package of the database found in it:
+//!
+//! ```text
+//! score_s(p)    = 100 * ln(1 + count_s(p)) / ln(1 + max_s)
+//! popularity(p) = round(mean of score_s(p) over the sources that list p)
+//! ```
+//!
+//! Packages no source lists keep their hand-set score.
+
+use crate::alias::AliasIndex;
+use crate::loader::rewrite_package_files;
+use crate::model::{Manager, Package};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+/// Where a set of counts comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Source {
+    Popcon,
+    Homebrew,
+    GithubStars,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Source::Popcon => "popcon",
+            Source::Homebrew => "homebrew",
+            Source::GithubStars => "github-stars",
+        })
+    }
+}
+
+/// `rank name inst vote old recent no-files (maintainer)` rows of a popcon
+/// `by_inst` dump, as (apt package, installations)
+pub fn parse_popcon(text: &str) -> Vec<(String, u64)> {
+    text.lines()
+        .filter(|line| !line.starts_with('#') && !line.starts_with('-'))
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            fields.next()?.parse::<u64>().ok()?;
+            let name = fields.next()?;
+            let installs = fields.next()?.parse().ok()?;
+            Some((name.to_string(), installs))
+        })
+        .filter(|(name, _)| name != "Total")
+        .collect()
+}
+
+#[derive(Deserialize)]
+struct BrewAnalytics {
+    items: Vec<BrewItem>,
+}
+
+#[derive(Deserialize)]
+struct BrewItem {
+    #[serde(alias = "cask")]
+    formula: String,
+    count: BrewCount,
+}
+
+/// Exports write counts as `"12,345"`; accept plain numbers too
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum BrewCount {
+    Text(String),
+    Number(u64),
+}
+
+/// The `items` of a Homebrew analytics export, as (formula or cask, installs)
+pub fn parse_brew_analytics(text: &str) -> Result<Vec<(String, u64)>> {
+    let analytics: BrewAnalytics =
+        serde_json::from_str(text).context("Invalid Homebrew analytics JSON")?;
+    analytics
+        .items
+        .into_iter()
+        .map(|item| {
+            let count = match item.count {
+                BrewCount::Number(n) => n,
+                BrewCount::Text(s) => s
+                    .replace(',', "")
+                    .parse()
+                    .with_context(|| format!("Invalid count '{}' for {}", s, item.formula))?,
+            };
+            Ok((item.formula, count))
+        })
+        .collect()
+}
+
+/// `name,stars` lines; a header row and lines without a numeric count are
+/// skipped
+pub fn parse_stars_csv(text: &str) -> Vec<(String, u64)> {
+    text.lines()
+        .filter_map(|line| {
+            let mut fields = line.split(',').map(|f| f.trim().trim_matches('"'));
+            let name = fields.next().filter(|n| !n.is_empty())?;
+            let stars = fields.next()?.parse().ok()?;
+            Some((name.to_string(), stars))
+        })
+        .collect()
+}
+
+/// One source's counts, by canonical package
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Counts {
+    pub source: Source,
+    pub counts: BTreeMap<String, u64>,
+}
+
+impl Counts {
+    /// Map raw names to canonical packages: popcon names through apt names,
+    /// Homebrew names through brew names, and star counts through package
+    /// names or the GitHub repository in `source`. Names the database does
+    /// not know are dropped; when several names map to one package the
+    /// highest count wins.
+    pub fn resolve(
+        source: Source,
+        index: &AliasIndex,
+        packages: &[Package],
+        raw: Vec<(String, u64)>,
+    ) -> Self {
+        let mut counts: BTreeMap<String, u64> = BTreeMap::new();
+        for (name, count) in raw {
+            let canonical = match source {
+                Source::Popcon => index.resolve_on(Manager::Apt, &name).map(|e| e.canonical),
+                Source::Homebrew => index.resolve_on(Manager::Brew, &name).map(|e| e.canonical),
+                Source::GithubStars if name.contains('/') => repository(packages, &name),
+                Source::GithubStars => index.resolve(&name).map(|e| e.canonical),
+            };
+            if let Some(canonical) = canonical {
+                let entry = counts.entry(canonical).or_default();
+                *entry = (*entry).max(count);
+            }
+        }
+        Counts { source, counts }
+    }
+}
+
+/// The package whose `source` is `github.com/<repo>`
+fn repository(packages: &[Package], repo: &str) -> Option<String> {
+    let suffix = format!("github.com/{}", repo.trim_matches('/').to_lowercase());
+    packages
+        .iter()
+        .find(|p| {
+            p.source
+                .as_deref()
+                .is_some_and(|s| s.trim_end_matches('/').to_lowercase().ends_with(&suffix))
+        })
+        .map(|p| p.name.clone())
+}
+
+/// `count` on a log scale where `max` scores 100
+pub fn normalize(count: u64, max: u64) -> f64 {
+    if max == 0 {
+        return 0.0;
+    }
+    (100.0 * (count as f64).ln_1p() / (max as f64).ln_1p()).min(100.0)
+}
+
+/// A package's recomputed score
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Score {
+    pub package: String,
+    /// Hand-set `popularity`
+    pub current: u8,
+    pub computed: u8,
+    /// Score from each source that lists the package
+    pub sources: BTreeMap<Source, f64>,
+}
+
+impl Score {
+    pub fn divergence(&self) -> u8 {
+        self.current.abs_diff(self.computed)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PopularityReport {
+    pub sources: Vec<Source>,
+    /// In package order
+    pub scores: Vec<Score>,
+    /// Packages no source lists
+    pub unscored: Vec<String>,
+}
+
+impl PopularityReport {
+    pub fn compute(packages: &[Package], counts: &[Counts]) -> Self {
+        let maxima: Vec<u64> = counts
+            .iter()
+            .map(|c| c.counts.values().copied().max().unwrap_or(0))
+            .collect();
+
+        let mut scores = Vec::new();
+        let mut unscored = Vec::new();
+        for package in packages {
+            let sources: BTreeMap<Source, f64> = counts
+                .iter()
+                .zip(&maxima)
+                .filter_map(|(c, &max)| {
+                    let count = *c.counts.get(&package.name)?;
+                    Some((c.source, normalize(count, max)))
+                })
+                .collect();
+            if sources.is_empty() {
+                unscored.push(package.name.clone());
+                continue;
+            }
+            let mean = sources.values().sum::<f64>() / sources.len() as f64;
+            scores.push(Score {
+                package: package.name.clone(),
+                current: package.popularity,
+                computed: mean.round() as u8,
+                sources,
+            });
+        }
+
+        PopularityReport {
+            sources: counts.iter().map(|c| c.source).collect(),
+            scores,
+            unscored,
+        }
+    }
+
+    /// Scores at least `threshold` away from the hand-set value, largest
+    /// difference first
+    pub fn divergent(&self, threshold: u8) -> Vec<&Score> {
+        let mut divergent: Vec<&Score> = self
+            .scores
+            .iter()
+            .filter(|s| s.divergence() >= threshold)
+            .collect();
+        divergent.sort_by_key(|s| Reverse(s.divergence()));
+        divergent
+    }
+
+    pub fn to_text(&self, threshold: u8) -> String {
+        let sources: Vec<String> = self.sources.iter().map(Source::to_string).collect();
+        let mut out = format!(
+            "Popularity from {}: {} packages scored, {} without data\n",
+            sources.join(", "),
+            self.scores.len(),
+            self.unscored.len()
+        );
+
+        let divergent = self.divergent(threshold);
+        if divergent.is_empty() {
+            out.push_str(&format!("\nNo score differs by {} or more\n", threshold));
+            return out;
+        }
+        out.push_str(&format!(
+            "\nDiffering by {} or more ({}):\n",
+            threshold,
+            divergent.len()
+        ));
+        for score in divergent {
+            let detail: Vec<String> = score
+                .sources
+                .iter()
+                .map(|(source, value)| format!("{} {:.0}", source, value))
+                .collect();
+            out.push_str(&format!(
+                "  {}: {} -> {} ({})\n",
+                score.package,
+                score.current,
+                score.computed,
+                detail.join(", ")
+            ));
+        }
+        out
+    }
+}
+
+/// Replace the top-level `popularity:` value of a YAML document, keeping the
+/// rest of the text as it is
+pub fn set_popularity(yaml: &str, popularity: u8) -> String {
+    let mut out: Vec<String> = yaml
+        .lines()
+        .map(|line| match line.strip_prefix("popularity:") {
+            Some(_) => format!("popularity: {}", popularity),
+            None => line.to_string(),
+        })
+        .collect();
+    if yaml.ends_with('\n') {
+        out.push(String::new());
+    }
+    out.join("\n")
+}
+
+/// Write the computed scores that differ from the hand-set ones into the
+/// package files under `dir`, returning the number of files changed
+pub fn write_scores(dir: impl AsRef<Path>, scores: &[Score]) -> Result<usize> {
+    let computed: BTreeMap<&str, u8> = scores
+        .iter()
+        .filter(|s| s.computed != s.current)
+        .map(|s| (s.package.as_str(), s.computed))
+        .collect();
+    rewrite_package_files(dir, computed.keys().copied(), |package, content| {
+        set_popularity(content, computed[package])
+    })
+}
@@ -0,0 +1,235 @@
+//! Reverse dependencies: everything in the database that refers to a package
+//!
+//! Answers "what breaks if we drop or rename this?" from a compiled
+//! `packages.db`: dependents in the dependency graph (direct and transitive),
+//! the groups, profiles, templates and suggestion patterns that include the
+//! package, and packages naming it in `alternatives` or `related`.
+
+use crate::database::CompiledDatabase;
+use crate::graph::DependencyKind;
+use crate::model::SuggestionPriority;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum RdepsError {
+    #[error("unknown package '{0}'")]
+    UnknownPackage(String),
+}
+
+/// A package that depends on the queried one
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Dependent {
+    pub package: String,
+    /// Required if every edge of some path to the queried package is
+    pub kind: DependencyKind,
+    /// Shortest path of that kind, from `package` to the queried package
+    pub path: Vec<String>,
+}
+
+impl Dependent {
+    pub fn is_direct(&self) -> bool {
+        self.path.len() == 2
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GroupMember {
+    pub group: String,
+    pub required: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SuggestedBy {
+    pub files: Vec<String>,
+    pub priority: SuggestionPriority,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ReverseDependencies {
+    /// Canonical name of the queried package
+    pub package: String,
+    /// Required dependents first, then optional; each by path length
+    pub dependents: Vec<Dependent>,
+    pub groups: Vec<GroupMember>,
+    pub profiles: Vec<String>,
+    pub templates: Vec<String>,
+    pub suggestions: Vec<SuggestedBy>,
+    /// Packages listing it in `alternatives`
+    pub alternative_of: Vec<String>,
+    /// Packages listing it in `related`
+    pub related_to: Vec<String>,
+}
+
+impl ReverseDependencies {
+    /// Query by canonical name, alias or platform name
+    pub fn query(db: &CompiledDatabase, name: &str) -> Result<Self, RdepsError> {
+        let package = db
+            .resolve(name)
+            .ok_or_else(|| RdepsError::UnknownPackage(name.to_string()))?
+            .name
+            .clone();
+
+        let members = |lists: &BTreeMap<String, Vec<String>>| {
+            lists
+                .iter()
+                .filter(|(_, packages)| packages.contains(&package))
+                .map(|(id, _)| id.clone())
+                .collect()
+        };
+
+        Ok(ReverseDependencies {
+            dependents: dependents(db, &package),
+            groups: db
+                .groups
+                .iter()
+                .filter_map(|g| {
+                    let required = g.packages.required.contains(&package);
+                    (required || g.packages.optional.contains(&package)).then(|| GroupMember {
+                        group: g.id.clone(),
+                        required,
+                    })
+                })
+                .collect(),
+            profiles: members(&db.profile_packages),
+            templates: members(&db.template_packages),
+            suggestions: db
+                .suggestions
+                .iter()
+                .flat_map(|pattern| {
+                    pattern
+                        .suggests
+                        .iter()
+                        .filter(|s| s.package == package)
+                        .map(|s| SuggestedBy {
+                            files: pattern.files.clone(),
+                            priority: s.priority,
+                        })
+                })
+                .collect(),
+            alternative_of: db
+                .packages
+                .iter()
+                .filter(|p| p.alternatives.contains(&package))
+                .map(|p| p.name.clone())
+                .collect(),
+            related_to: db
+                .packages
+                .iter()
+                .filter(|p| p.related.contains(&package))
+                .map(|p| p.name.clone())
+                .collect(),
+            package,
+        })
+    }
+
+    /// Nothing refers to the package; it can be dropped or renamed freely
+    pub fn is_empty(&self) -> bool {
+        self.dependents.is_empty()
+            && self.groups.is_empty()
+            && self.profiles.is_empty()
+            && self.templates.is_empty()
+            && self.suggestions.is_empty()
+            && self.alternative_of.is_empty()
+            && self.related_to.is_empty()
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut out = format!("Why {}:\n", self.package);
+        if self.is_empty() {
+            out.push_str("  nothing refers to it\n");
+            return out;
+        }
+
+        let mut section = |title: &str, lines: Vec<String>| {
+            if !lines.is_empty() {
+                out.push_str(&format!("\n{}:\n", title));
+                for line in lines {
+                    out.push_str(&format!("  {}\n", line));
+                }
+            }
+        };
+        section(
+            "Dependents",
+            self.dependents
+                .iter()
+                .map(|d| {
+                    if d.is_direct() {
+                        format!("{} ({}, direct)", d.package, d.kind.as_str())
+                    } else {
+                        format!(
+                            "{} ({}, via {})",
+                            d.package,
+                            d.kind.as_str(),
+                            d.path[1..d.path.len() - 1].join(" → ")
+                        )
+                    }
+                })
+                .collect(),
+        );
+        section(
+            "Groups",
+            self.groups
+                .iter()
+                .map(|g| {
+                    let section = if g.required { "required" } else { "optional" };
+                    format!("{} ({})", g.group, section)
+                })
+                .collect(),
+        );
+        section("Profiles", self.profiles.clone());
+        section("Templates", self.templates.clone());
+        section(
+            "Suggested for",
+            self.suggestions
+                .iter()
+                .map(|s| format!("{} ({})", s.files.join(", "), s.priority.as_str()))
+                .collect(),
+        );
+        section("Alternative of", self.alternative_of.clone());
+        section("Related to", self.related_to.clone());
+        out
+    }
+}
+
+/// Walk the dependency graph backwards from `package`: required edges first,
+/// so a dependent reachable both ways is reported as required
+fn dependents(db: &CompiledDatabase, package: &str) -> Vec<Dependent> {
+    let mut reverse: BTreeMap<&str, Vec<(&str, DependencyKind)>> = BTreeMap::new();
+    for (dependent, edges) in &db.dependency_graph {
+        for edge in edges {
+            reverse
+                .entry(edge.package.as_str())
+                .or_default()
+                .push((dependent.as_str(), edge.kind));
+        }
+    }
+
+    let mut found: Vec<Dependent> = Vec::new();
+    for kind in [DependencyKind::Required, DependencyKind::Optional] {
+        let mut visited = BTreeSet::from([package]);
+        let mut queue = VecDeque::from([vec![package.to_string()]]);
+        while let Some(path) = queue.pop_front() {
+            for &(dependent, edge_kind) in reverse.get(path[0].as_str()).into_iter().flatten() {
+                if (kind == DependencyKind::Required && edge_kind != kind)
+                    || !visited.insert(dependent)
+                {
+                    continue;
+                }
+                let mut path = path.clone();
+                path.insert(0, dependent.to_string());
+                if !found.iter().any(|d| d.package == dependent) {
+                    found.push(Dependent {
+                        package: dependent.to_string(),
+                        kind,
+                        path: path.clone(),
+                    });
+                }
+                queue.push_back(path);
+            }
+        }
+    }
+
+    found
+}
@@ -0,0 +1,299 @@
+//! "You might also want": package recommendations for an installed set
+//!
+//! Every package not yet installed is scored against the installed set from a
+//! compiled `packages.db`:
+//!
+//! - `related`: 3 points per installed package it is related to, in either
+//!   direction
+//! - groups and profiles: 2 points times the share of the group's (or
+//!   resolved profile's) packages already installed
+//! - tags: 0.5 points per tag shared with an installed package
+//! - popularity: `popularity / 100`, added only to packages with another
+//!   signal, so it breaks ties without recommending unrelated tools
+//!
+//! Deprecated packages, alternatives of installed packages and packages
+//! conflicting with them are never recommended. Each recommendation keeps the
+//! reasons behind its score, so clients can explain it.
+
+use crate::database::CompiledDatabase;
+use crate::model::Package;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+const RELATED: f64 = 3.0;
+const MEMBERSHIP: f64 = 2.0;
+const TAG: f64 = 0.5;
+/// Reasons spelled out in an explanation; the rest only add to the score
+const EXPLAINED: usize = 3;
+
+/// Why a package was recommended
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum Reason {
+    /// Linked to an installed package through `related`
+    Related { installed: String },
+    /// Shares a group with installed packages
+    Group {
+        group: String,
+        installed: Vec<String>,
+    },
+    /// Shares a resolved profile with installed packages
+    Profile {
+        profile: String,
+        installed: Vec<String>,
+    },
+    /// Shares a tag with installed packages
+    Tag { tag: String, installed: Vec<String> },
+}
+
+impl Reason {
+    /// `previous` is the reason explained just before this one
+    fn phrase(&self, package: &str, previous: Option<&Reason>) -> String {
+        // "ripgrep is related to fzf and both are in ..."
+        let within = |owner: String, installed: &[String]| match (previous, installed) {
+            (Some(Reason::Related { installed: related }), [only]) if related == only => {
+                format!("both are in {}", owner)
+            }
+            _ => format!("it is in {} with {}", owner, list(installed)),
+        };
+        match self {
+            Reason::Related { installed } => {
+                format!("{} is related to {}", package, installed)
+            }
+            Reason::Group { group, installed } => within(group.clone(), installed),
+            Reason::Profile { profile, installed } => {
+                within(format!("the {} profile", profile), installed)
+            }
+            Reason::Tag { tag, installed } => {
+                format!("it shares the '{}' tag with {}", tag, list(installed))
+            }
+        }
+    }
+}
+
+fn list(names: &[String]) -> String {
+    match names {
+        [] => String::new(),
+        [one] => one.clone(),
+        [rest @ .., last] => format!("{} and {}", rest.join(", "), last),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Recommendation {
+    pub package: String,
+    pub description: String,
+    pub score: f64,
+    /// Strongest first
+    pub reasons: Vec<Reason>,
+}
+
+impl Recommendation {
+    /// The strongest reasons in words, e.g. "suggested because ripgrep is
+    /// related to fzf and both are in terminal-power-user"
+    pub fn explanation(&self) -> String {
+        let phrases: Vec<String> = self
+            .reasons
+            .iter()
+            .take(EXPLAINED)
+            .enumerate()
+            .map(|(i, r)| r.phrase(&self.package, i.checked_sub(1).map(|p| &self.reasons[p])))
+            .collect();
+        format!("suggested because {}", list(&phrases))
+    }
+}
+
+impl fmt::Display for Recommendation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} ({:.1}): {}",
+            self.package,
+            self.score,
+            self.explanation()
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Recommendations {
+    /// Canonical names of the installed packages
+    pub installed: Vec<String>,
+    /// Installed names the database does not know
+    pub unknown: Vec<String>,
+    /// Highest score first
+    pub recommendations: Vec<Recommendation>,
+}
+
+impl Recommendations {
+    /// Score every package against `installed` (canonical names, aliases or
+    /// platform names) and keep the best `limit`
+    pub fn query<'a>(
+        db: &CompiledDatabase,
+        installed: impl IntoIterator<Item = &'a str>,
+        limit: usize,
+    ) -> Self {
+        let mut names = BTreeSet::new();
+        let mut unknown = Vec::new();
+        for name in installed {
+            match db.resolve(name) {
+                Some(package) => {
+                    names.insert(package.name.clone());
+                }
+                None => unknown.push(name.to_string()),
+            }
+        }
+        let installed: Vec<&Package> = names.iter().filter_map(|n| db.package(n)).collect();
+
+        let memberships = memberships(db);
+        let mut recommendations: Vec<Recommendation> = db
+            .packages
+            .iter()
+            .filter(|p| !names.contains(&p.name) && !excluded(p, &installed))
+            .filter_map(|p| score(p, &installed, &memberships))
+            .collect();
+        recommendations.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then_with(|| a.package.cmp(&b.package))
+        });
+        recommendations.truncate(limit);
+
+        Recommendations {
+            installed: names.into_iter().collect(),
+            unknown,
+            recommendations,
+        }
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        if !self.unknown.is_empty() {
+            out.push_str(&format!(
+                "Unknown packages: {}\n\n",
+                self.unknown.join(", ")
+            ));
+        }
+        if self.recommendations.is_empty() {
+            out.push_str("No recommendations\n");
+            return out;
+        }
+        out.push_str("You might also want:\n");
+        for r in &self.recommendations {
+            out.push_str(&format!(
+                "  {} ({:.1}) - {}\n    {}\n",
+                r.package,
+                r.score,
+                r.description,
+                r.explanation()
+            ));
+        }
+        out
+    }
+}
+
+/// A group or resolved profile, as a candidate's shared membership
+struct Membership<'a> {
+    reason: fn(String, Vec<String>) -> Reason,
+    id: &'a str,
+    packages: Vec<&'a str>,
+}
+
+fn memberships(db: &CompiledDatabase) -> Vec<Membership<'_>> {
+    let groups = db.groups.iter().map(|g| Membership {
+        reason: |group, installed| Reason::Group { group, installed },
+        id: &g.id,
+        packages: g
+            .packages
+            .required
+            .iter()
+            .chain(&g.packages.optional)
+            .map(String::as_str)
+            .collect(),
+    });
+    let profiles = db.profile_packages.iter().map(|(id, packages)| Membership {
+        reason: |profile, installed| Reason::Profile { profile, installed },
+        id,
+        packages: packages.iter().map(String::as_str).collect(),
+    });
+    groups.chain(profiles).collect()
+}
+
+/// Deprecated, or an alternative of (or conflicting with) an installed package
+fn excluded(candidate: &Package, installed: &[&Package]) -> bool {
+    candidate.deprecated
+        || installed.iter().any(|p| {
+            p.alternatives.contains(&candidate.name)
+                || candidate.alternatives.contains(&p.name)
+                || p.conflicts.contains(&candidate.name)
+                || candidate.conflicts.contains(&p.name)
+        })
+}
+
+fn score(
+    candidate: &Package,
+    installed: &[&Package],
+    memberships: &[Membership],
+) -> Option<Recommendation> {
+    let mut weighted: Vec<(f64, Reason)> = Vec::new();
+
+    for package in installed {
+        if candidate.related.contains(&package.name) || package.related.contains(&candidate.name) {
+            weighted.push((
+                RELATED,
+                Reason::Related {
+                    installed: package.name.clone(),
+                },
+            ));
+        }
+    }
+
+    for membership in memberships {
+        if !membership.packages.contains(&candidate.name.as_str()) {
+            continue;
+        }
+        let shared: Vec<String> = installed
+            .iter()
+            .filter(|p| membership.packages.contains(&p.name.as_str()))
+            .map(|p| p.name.clone())
+            .collect();
+        if !shared.is_empty() {
+            let share = shared.len() as f64 / membership.packages.len() as f64;
+            weighted.push((
+                MEMBERSHIP * share,
+                (membership.reason)(membership.id.to_string(), shared),
+            ));
+        }
+    }
+
+    let mut tags: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+    for package in installed {
+        for tag in package.tags.iter().filter(|t| candidate.tags.contains(t)) {
+            tags.entry(tag).or_default().push(package.name.clone());
+        }
+    }
+    for (tag, installed) in tags {
+        weighted.push((
+            TAG,
+            Reason::Tag {
+                tag: tag.to_string(),
+                installed,
+            },
+        ));
+    }
+
+    if weighted.is_empty() {
+        return None;
+    }
+    // Stable, so equally weighted reasons keep their signal order
+    weighted.sort_by(|a, b| b.0.total_cmp(&a.0));
+    let score = weighted.iter().map(|(w, _)| w).sum::<f64>() + candidate.popularity as f64 / 100.0;
+
+    Some(Recommendation {
+        package: candidate.name.clone(),
+        description: candidate.description.clone(),
+        score,
+        reasons: weighted.into_iter().map(|(_, r)| r).collect(),
+    })
+}
@@ -0,0 +1,123 @@
+//! Consistency between package `platforms` and `mappings/`
+//!
+//! Platform names are recorded twice: in each package file and in the
+//! `mappings/` tables. The merge policy is that the package file is
+//! authoritative and a mapping only fills in managers the package leaves
+//! empty (the same rule [`availability`] applies). `compile` writes the merged
+//! names into `packages.db`, so consumers see one name per package and
+//! manager; [`reconcile`] reports where the two sources disagree.
+
+use crate::coverage::availability;
+use crate::model::{Manager, Mapping, Package, Platforms};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Discrepancy {
+    /// Both sources name the package on `manager`, differently
+    Conflict {
+        package: String,
+        manager: Manager,
+        package_name: String,
+        mapping_name: String,
+    },
+    /// Only the mapping names the package on `manager`
+    MappingOnly {
+        package: String,
+        manager: Manager,
+        mapping_name: String,
+    },
+    /// A mapping whose `canonical` has no package file
+    OrphanMapping { canonical: String },
+}
+
+impl fmt::Display for Discrepancy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Discrepancy::Conflict {
+                package,
+                manager,
+                package_name,
+                mapping_name,
+            } => write!(
+                f,
+                "'{}' is '{}' on {} but its mapping says '{}' (package file wins)",
+                package, package_name, manager, mapping_name
+            ),
+            Discrepancy::MappingOnly {
+                package,
+                manager,
+                mapping_name,
+            } => write!(
+                f,
+                "'{}' has no {} name; its mapping supplies '{}'",
+                package, manager, mapping_name
+            ),
+            Discrepancy::OrphanMapping { canonical } => {
+                write!(f, "mapping '{}' has no package file", canonical)
+            }
+        }
+    }
+}
+
+/// Compare every mapping with its package
+pub fn reconcile(packages: &[Package], mappings: &[Mapping]) -> Vec<Discrepancy> {
+    let mut discrepancies = Vec::new();
+
+    for mapping in mappings {
+        let Some(package) = packages.iter().find(|p| p.name == mapping.canonical) else {
+            discrepancies.push(Discrepancy::OrphanMapping {
+                canonical: mapping.canonical.clone(),
+            });
+            continue;
+        };
+
+        for manager in Manager::ALL {
+            match (
+                package.platforms.get(manager),
+                mapping.platforms.get(manager),
+            ) {
+                (Some(ours), Some(theirs)) if ours != theirs => {
+                    discrepancies.push(Discrepancy::Conflict {
+                        package: package.name.clone(),
+                        manager,
+                        package_name: ours.to_string(),
+                        mapping_name: theirs.to_string(),
+                    });
+                }
+                (None, Some(theirs)) => discrepancies.push(Discrepancy::MappingOnly {
+                    package: package.name.clone(),
+                    manager,
+                    mapping_name: theirs.to_string(),
+                }),
+                _ => {}
+            }
+        }
+    }
+
+    discrepancies
+}
+
+/// The authoritative platform names of `package` under the merge policy
+pub fn merged_platforms(package: &Package, mapping: Option<&Mapping>) -> Platforms {
+    let name = |manager| availability(package, mapping, manager).map(|a| a.name);
+    Platforms {
+        apt: name(Manager::Apt),
+        brew: name(Manager::Brew),
+        dnf: name(Manager::Dnf),
+        pacman: name(Manager::Pacman),
+        apk: package
+            .platforms
+            .apk
+            .clone()
+            .or_else(|| mapping.and_then(|m| m.platforms.apk.clone())),
+        mas: package.platforms.mas,
+    }
+}
+
+/// Apply the merge policy to every package
+pub fn merge(packages: &mut [Package], mappings: &[Mapping]) {
+    for package in packages {
+        let mapping = mappings.iter().find(|m| m.canonical == package.name);
+        package.platforms = merged_platforms(package, mapping);
+    }
+}
@@ -0,0 +1,226 @@
+//! Checks on `alternatives` and `related`
+//!
+//! Alternatives are symmetric: if `vim` lists `neovim`, `neovim` lists `vim`,
+//! and the two share a category. Neither list may name the package itself.
+//! [`check`] reports the errors, [`category_mismatches`] the (warning-level)
+//! cross-category alternatives, and [`add_back_links`] writes missing
+//! back-links into the package files without disturbing their layout.
+
+use crate::loader::rewrite_package_files;
+use crate::model::Package;
+use anyhow::Result;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::Path;
+
+/// A malformed `alternatives` or `related` list
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RelationError {
+    /// `package` names itself in `list`
+    SelfReference { package: String, list: &'static str },
+    /// `alternative` does not list `package` back
+    Asymmetric {
+        package: String,
+        alternative: String,
+    },
+}
+
+impl fmt::Display for RelationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RelationError::SelfReference { package, list } => {
+                write!(f, "'{}' lists itself in its {}", package, list)
+            }
+            RelationError::Asymmetric {
+                package,
+                alternative,
+            } => write!(
+                f,
+                "'{}' lists '{}' as an alternative, but '{}' does not list '{}' back",
+                package, alternative, alternative, package
+            ),
+        }
+    }
+}
+
+/// Alternatives in different categories
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CategoryMismatch {
+    pub package: String,
+    pub category: String,
+    pub alternative: String,
+    pub alternative_category: String,
+}
+
+impl fmt::Display for CategoryMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "'{}' ({}) and its alternative '{}' ({}) are in different categories",
+            self.package, self.category, self.alternative, self.alternative_category
+        )
+    }
+}
+
+/// A missing back-link: `package` should list `alternative`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackLink {
+    pub package: String,
+    pub alternative: String,
+}
+
+/// Check for self-references and one-sided alternatives. Unknown names are
+/// left to the cross-reference check.
+pub fn check(packages: &[Package]) -> Vec<RelationError> {
+    let mut errors = Vec::new();
+
+    for package in packages {
+        for (list, names) in [
+            ("alternatives", &package.alternatives),
+            ("related", &package.related),
+        ] {
+            if names.contains(&package.name) {
+                errors.push(RelationError::SelfReference {
+                    package: package.name.clone(),
+                    list,
+                });
+            }
+        }
+    }
+    for link in missing_back_links(packages) {
+        errors.push(RelationError::Asymmetric {
+            package: link.alternative,
+            alternative: link.package,
+        });
+    }
+
+    errors
+}
+
+/// Alternatives whose categories differ, each pair reported once
+pub fn category_mismatches(packages: &[Package]) -> Vec<CategoryMismatch> {
+    let by_name: HashMap<&str, &Package> = packages.iter().map(|p| (p.name.as_str(), p)).collect();
+    let mut mismatches = Vec::new();
+
+    for package in packages {
+        for alternative in &package.alternatives {
+            let Some(other) = by_name.get(alternative.as_str()) else {
+                continue;
+            };
+            let reported_from_other =
+                other.name < package.name && other.alternatives.contains(&package.name);
+            if other.category != package.category && !reported_from_other {
+                mismatches.push(CategoryMismatch {
+                    package: package.name.clone(),
+                    category: package.category.clone(),
+                    alternative: other.name.clone(),
+                    alternative_category: other.category.clone(),
+                });
+            }
+        }
+    }
+
+    mismatches
+}
+
+/// Back-links that would make every known alternative symmetric
+pub fn missing_back_links(packages: &[Package]) -> Vec<BackLink> {
+    let by_name: HashMap<&str, &Package> = packages.iter().map(|p| (p.name.as_str(), p)).collect();
+    let mut links = Vec::new();
+
+    for package in packages {
+        for alternative in &package.alternatives {
+            match by_name.get(alternative.as_str()) {
+                Some(other)
+                    if other.name != package.name
+                        && !other.alternatives.contains(&package.name) =>
+                {
+                    links.push(BackLink {
+                        package: other.name.clone(),
+                        alternative: package.name.clone(),
+                    })
+                }
+                _ => {}
+            }
+        }
+    }
+
+    links
+}
+
+/// Write `links` into the package files under `dir`, returning the number of
+/// files changed
+pub fn add_back_links(dir: impl AsRef<Path>, links: &[BackLink]) -> Result<usize> {
+    let mut by_package: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for link in links {
+        by_package
+            .entry(&link.package)
+            .or_default()
+            .push(&link.alternative);
+    }
+
+    rewrite_package_files(dir, by_package.keys().copied(), |package, content| {
+        by_package[package]
+            .iter()
+            .fold(content.to_string(), |content, alternative| {
+                append_to_list(&content, "alternatives", alternative)
+            })
+    })
+}
+
+/// Append `item` to the top-level list `key` of a YAML document, keeping the
+/// rest of the text as it is. Handles block lists, flow lists (`[a, b]`) and
+/// a missing key.
+pub fn append_to_list(yaml: &str, key: &str, item: &str) -> String {
+    let lines: Vec<&str> = yaml.lines().collect();
+    let prefix = format!("{}:", key);
+    let Some(start) = lines.iter().position(|l| l.starts_with(&prefix)) else {
+        let separator = if yaml.is_empty() || yaml.ends_with('\n') {
+            ""
+        } else {
+            "\n"
+        };
+        return format!("{}{}{}:\n  - {}\n", yaml, separator, key, item);
+    };
+
+    let mut out: Vec<String> = lines[..start].iter().map(|l| l.to_string()).collect();
+    let line = &lines[start][prefix.len()..];
+    // A trailing comment starts at a `#` preceded by whitespace
+    let end = line
+        .match_indices('#')
+        .find(|(i, _)| line[..*i].ends_with(char::is_whitespace))
+        .map_or(line.len(), |(i, _)| line[..i].trim_end().len());
+    let (value, comment) = (line[..end].trim(), &line[end..]);
+    let mut rest = start + 1;
+    if let Some(flow) = value.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+        out.push(match flow.trim() {
+            "" => format!("{}:{}\n  - {}", key, comment, item),
+            items => format!("{}: [{}, {}]{}", key, items, item, comment),
+        });
+    } else {
+        out.push(lines[start].to_string());
+        let mut indent = "  ";
+        let mut last_item = start;
+        while rest < lines.len() {
+            let line = lines[rest];
+            let trimmed = line.trim_start();
+            if trimmed.starts_with("- ") {
+                indent = &line[..line.len() - trimmed.len()];
+                last_item = rest;
+            } else if !(trimmed.is_empty() || trimmed.starts_with('#') || line.starts_with(' ')) {
+                break;
+            }
+            rest += 1;
+        }
+        out.extend(lines[start + 1..=last_item].iter().map(|l| l.to_string()));
+        out.push(format!("{}- {}", indent, item));
+        rest = last_item + 1;
+    }
+    out.extend(lines[rest..].iter().map(|l| l.to_string()));
+
+    let mut text = out.join("\n");
+    if yaml.ends_with('\n') {
+        text.push('\n');
+    }
+    text
+}
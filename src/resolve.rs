@@ -0,0 +1,419 @@
+//! Profile inheritance and template resolution
+//!
+//! A profile may `extends` other profiles, `include_groups` and `exclude`
+//! inherited packages. Resolution is depth-first: parents in order, then the
+//! included groups' required packages, then `exclude`, then the profile's own
+//! sections. Every resulting package remembers which profiles and groups
+//! contributed it.
+//!
+//! A template resolves the same way on top of its base `profile`: then its
+//! `groups` (required packages) and finally its `custom_packages`.
+//!
+//! Choice slots resolve where their group or profile does, to the user's pick
+//! (see [`Resolver::with_choices`]) or the slot's default. The first slot
+//! with a given id wins.
+//!
+//! When the resolver knows the packages, the result also lists pairs of
+//! resolved packages that declare a conflict.
+
+use crate::choice::{self, Choices};
+use crate::conflicts::{self, PackageConflict};
+use crate::dotfiles::{self, DotfileConflict, ResolvedDotfile};
+use crate::loader::SourceTree;
+use crate::model::{Choice, Hooks, Package, PackageGroup, Profile, Template};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ResolveError {
+    #[error("unknown profile '{0}'")]
+    UnknownProfile(String),
+
+    #[error("unknown template '{0}'")]
+    UnknownTemplate(String),
+
+    #[error("'{owner}' extends unknown profile '{parent}'")]
+    UnknownParent { owner: String, parent: String },
+
+    #[error("'{owner}' includes unknown group '{group}'")]
+    UnknownGroup { owner: String, group: String },
+
+    #[error("profile inheritance cycle: {}", .0.join(" -> "))]
+    Cycle(Vec<String>),
+
+    #[error("'{package}' is not an option of choice '{slot}' (options: {})", .options.join(", "))]
+    InvalidChoice {
+        slot: String,
+        package: String,
+        options: Vec<String>,
+    },
+
+    #[error("no choice slot named '{0}'")]
+    UnknownSlot(String),
+}
+
+static NO_CHOICES: Choices = BTreeMap::new();
+
+/// What contributed a package to a resolved profile
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum Origin {
+    Profile { id: String, section: &'static str },
+    Group { id: String },
+    Template { id: String },
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Origin::Profile { id, section } => write!(f, "profile {} ({})", id, section),
+            Origin::Group { id } => write!(f, "group {}", id),
+            Origin::Template { id } => write!(f, "template {}", id),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ResolvedPackage {
+    pub name: String,
+    /// Every contributor, in resolution order
+    pub sources: Vec<Origin>,
+}
+
+impl ResolvedPackage {
+    /// The contributor that first brought the package in
+    pub fn origin(&self) -> &Origin {
+        &self.sources[0]
+    }
+}
+
+/// A choice slot and the option it resolved to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ResolvedChoice {
+    pub slot: String,
+    pub options: Vec<String>,
+    pub chosen: String,
+    /// The group or profile declaring the slot
+    pub origin: Origin,
+}
+
+/// A profile (or template) with its inheritance chain flattened
+#[derive(Debug, Clone, Serialize)]
+pub struct ResolvedProfile {
+    pub id: String,
+    /// Profiles merged into this one, ancestors first; a profile's own `id`
+    /// comes last
+    pub chain: Vec<String>,
+    pub packages: Vec<ResolvedPackage>,
+    /// Choice slots, in resolution order
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub choices: Vec<ResolvedChoice>,
+    pub dotfiles: Vec<ResolvedDotfile>,
+    /// Dotfiles from different profiles claiming the same target
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub dotfile_conflicts: Vec<DotfileConflict>,
+    /// Resolved packages that cannot be installed together
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub package_conflicts: Vec<PackageConflict>,
+    pub hooks: Hooks,
+}
+
+impl ResolvedProfile {
+    pub fn package(&self, name: &str) -> Option<&ResolvedPackage> {
+        self.packages.iter().find(|p| p.name == name)
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.packages.iter().map(|p| p.name.as_str())
+    }
+
+    /// Groups included by the profiles of the chain, ancestors first
+    pub fn groups<'a>(&self, tree: &'a SourceTree) -> Vec<&'a PackageGroup> {
+        self.chain
+            .iter()
+            .filter_map(|p| tree.profile(p))
+            .flat_map(|p| &p.include_groups)
+            .filter_map(|g| tree.group(g))
+            .collect()
+    }
+
+    fn empty(id: &str) -> Self {
+        ResolvedProfile {
+            id: id.to_string(),
+            chain: Vec::new(),
+            packages: Vec::new(),
+            choices: Vec::new(),
+            dotfiles: Vec::new(),
+            dotfile_conflicts: Vec::new(),
+            package_conflicts: Vec::new(),
+            hooks: Hooks::default(),
+        }
+    }
+
+    fn add(&mut self, name: &str, origin: Origin) {
+        match self.packages.iter_mut().find(|p| p.name == name) {
+            Some(existing) => {
+                if !existing.sources.contains(&origin) {
+                    existing.sources.push(origin);
+                }
+            }
+            None => self.packages.push(ResolvedPackage {
+                name: name.to_string(),
+                sources: vec![origin],
+            }),
+        }
+    }
+
+    /// Fold a resolved parent into this profile
+    fn inherit(&mut self, parent: ResolvedProfile) {
+        for id in parent.chain {
+            if !self.chain.contains(&id) {
+                self.chain.push(id);
+            }
+        }
+        for package in parent.packages {
+            for origin in package.sources {
+                self.add(&package.name, origin);
+            }
+        }
+        for choice in parent.choices {
+            if !self.choices.iter().any(|c| c.slot == choice.slot) {
+                self.choices.push(choice);
+            }
+        }
+        for conflict in parent.dotfile_conflicts {
+            if !self.dotfile_conflicts.contains(&conflict) {
+                self.dotfile_conflicts.push(conflict);
+            }
+        }
+        dotfiles::merge(
+            &mut self.dotfiles,
+            &mut self.dotfile_conflicts,
+            parent.dotfiles,
+            false,
+        );
+        self.add_hooks(&parent.hooks);
+    }
+
+    /// Hooks run parents first; identical commands run once
+    fn add_hooks(&mut self, hooks: &Hooks) {
+        for (merged, new) in [
+            (&mut self.hooks.pre_install, &hooks.pre_install),
+            (&mut self.hooks.post_install, &hooks.post_install),
+        ] {
+            for hook in new {
+                if !merged.iter().any(|h| h.command == hook.command) {
+                    merged.push(hook.clone());
+                }
+            }
+        }
+    }
+}
+
+/// Resolves profiles and templates against one source tree
+pub struct Resolver<'a> {
+    profiles: HashMap<&'a str, &'a Profile>,
+    groups: HashMap<&'a str, &'a PackageGroup>,
+    templates: HashMap<&'a str, &'a Template>,
+    packages: &'a [Package],
+    choices: &'a Choices,
+}
+
+impl<'a> Resolver<'a> {
+    pub fn new(profiles: &'a [Profile], groups: &'a [PackageGroup]) -> Self {
+        Resolver {
+            profiles: profiles.iter().map(|p| (p.id.as_str(), p)).collect(),
+            groups: groups.iter().map(|g| (g.id.as_str(), g)).collect(),
+            templates: HashMap::new(),
+            packages: &[],
+            choices: &NO_CHOICES,
+        }
+    }
+
+    pub fn with_templates(mut self, templates: &'a [Template]) -> Self {
+        self.templates = templates.iter().map(|t| (t.id.as_str(), t)).collect();
+        self
+    }
+
+    /// Packages whose `conflicts` are checked in every resolution
+    pub fn with_packages(mut self, packages: &'a [Package]) -> Self {
+        self.packages = packages;
+        self
+    }
+
+    /// User picks for choice slots; a pick naming no slot in the resolution
+    /// is an error
+    pub fn with_choices(mut self, choices: &'a Choices) -> Self {
+        self.choices = choices;
+        self
+    }
+
+    pub fn from_tree(tree: &'a SourceTree) -> Self {
+        Self::new(&tree.profiles, &tree.groups)
+            .with_templates(&tree.templates)
+            .with_packages(&tree.packages)
+    }
+
+    fn check_conflicts(&self, resolved: &mut ResolvedProfile) {
+        resolved.package_conflicts = conflicts::find(self.packages, resolved.names());
+    }
+
+    fn check_choices(&self, resolved: &ResolvedProfile) -> Result<(), ResolveError> {
+        match self
+            .choices
+            .keys()
+            .find(|slot| !resolved.choices.iter().any(|c| &c.slot == *slot))
+        {
+            Some(slot) => Err(ResolveError::UnknownSlot(slot.clone())),
+            None => Ok(()),
+        }
+    }
+
+    /// Resolve `choices` on behalf of `origin`, adding each chosen package
+    fn add_choices(
+        &self,
+        resolved: &mut ResolvedProfile,
+        choices: &[Choice],
+        origin: Origin,
+    ) -> Result<(), ResolveError> {
+        for choice in choices {
+            if resolved.choices.iter().any(|c| c.slot == choice.id) {
+                continue;
+            }
+            let chosen = choice::pick(choice, self.choices)?;
+            resolved.add(chosen, origin.clone());
+            resolved.choices.push(ResolvedChoice {
+                slot: choice.id.clone(),
+                options: choice.options.clone(),
+                chosen: chosen.clone(),
+                origin: origin.clone(),
+            });
+        }
+        Ok(())
+    }
+
+    pub fn resolve_template(&self, id: &str) -> Result<ResolvedProfile, ResolveError> {
+        let template = self
+            .templates
+            .get(id)
+            .ok_or_else(|| ResolveError::UnknownTemplate(id.to_string()))?;
+
+        let mut resolved = ResolvedProfile::empty(id);
+        if let Some(profile) = &template.profile {
+            if !self.profiles.contains_key(profile.as_str()) {
+                return Err(ResolveError::UnknownParent {
+                    owner: id.to_string(),
+                    parent: profile.clone(),
+                });
+            }
+            resolved.inherit(self.flatten(profile, &mut Vec::new())?);
+        }
+
+        self.include_groups(&mut resolved, id, &template.groups)?;
+        for name in &template.custom_packages {
+            resolved.add(name, Origin::Template { id: id.to_string() });
+        }
+
+        self.check_choices(&resolved)?;
+        self.check_conflicts(&mut resolved);
+        Ok(resolved)
+    }
+
+    /// Add the required packages and choices of `groups` on behalf of `owner`
+    fn include_groups(
+        &self,
+        resolved: &mut ResolvedProfile,
+        owner: &str,
+        groups: &[String],
+    ) -> Result<(), ResolveError> {
+        for group_id in groups {
+            let group =
+                self.groups
+                    .get(group_id.as_str())
+                    .ok_or_else(|| ResolveError::UnknownGroup {
+                        owner: owner.to_string(),
+                        group: group_id.clone(),
+                    })?;
+            let origin = Origin::Group {
+                id: group_id.clone(),
+            };
+            for name in &group.packages.required {
+                resolved.add(name, origin.clone());
+            }
+            self.add_choices(resolved, &group.choices, origin)?;
+        }
+        Ok(())
+    }
+
+    pub fn resolve_profile(&self, id: &str) -> Result<ResolvedProfile, ResolveError> {
+        if !self.profiles.contains_key(id) {
+            return Err(ResolveError::UnknownProfile(id.to_string()));
+        }
+        let mut resolved = self.flatten(id, &mut Vec::new())?;
+        self.check_choices(&resolved)?;
+        self.check_conflicts(&mut resolved);
+        Ok(resolved)
+    }
+
+    fn flatten(&self, id: &str, stack: &mut Vec<String>) -> Result<ResolvedProfile, ResolveError> {
+        if let Some(start) = stack.iter().position(|p| p == id) {
+            let mut cycle = stack[start..].to_vec();
+            cycle.push(id.to_string());
+            return Err(ResolveError::Cycle(cycle));
+        }
+        let profile = self.profiles[id];
+        stack.push(id.to_string());
+
+        let mut resolved = ResolvedProfile::empty(id);
+        for parent in &profile.extends {
+            if !self.profiles.contains_key(parent.as_str()) {
+                return Err(ResolveError::UnknownParent {
+                    owner: id.to_string(),
+                    parent: parent.clone(),
+                });
+            }
+            resolved.inherit(self.flatten(parent, stack)?);
+        }
+
+        self.include_groups(&mut resolved, id, &profile.include_groups)?;
+
+        resolved
+            .packages
+            .retain(|p| !profile.exclude.contains(&p.name));
+
+        for (section, names) in profile.packages.sections() {
+            for name in names {
+                resolved.add(
+                    name,
+                    Origin::Profile {
+                        id: id.to_string(),
+                        section,
+                    },
+                );
+            }
+        }
+        self.add_choices(
+            &mut resolved,
+            &profile.choices,
+            Origin::Profile {
+                id: id.to_string(),
+                section: "choices",
+            },
+        )?;
+
+        // A profile's own dotfiles override those it inherits
+        dotfiles::merge(
+            &mut resolved.dotfiles,
+            &mut resolved.dotfile_conflicts,
+            profile.dotfiles.iter().map(|d| ResolvedDotfile::new(d, id)),
+            true,
+        );
+        resolved.add_hooks(&profile.hooks);
+        resolved.chain.push(id.to_string());
+
+        stack.pop();
+        Ok(resolved)
+    }
+}
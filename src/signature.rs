@@ -0,0 +1,329 @@
+//! Minisign-compatible ed25519 signatures for `packages.db`
+//!
+//! Signatures use minisign's prehashed mode: the file is hashed with
+//! BLAKE2b-512 and the digest is signed with ed25519. A second "global"
+//! signature covers the first one plus the trusted comment, exactly as
+//! `minisign -Vm packages.db -p minisign.pub` expects.
+//!
+//! Only unencrypted secret keys (`minisign -G -W`) can be loaded here; keep
+//! them in CI secrets rather than on disk.
+
+use crate::database::CompiledDatabase;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use blake2::digest::consts::U32;
+use blake2::{Blake2b, Blake2b512, Digest};
+use ed25519_dalek::{Signer, SigningKey, Verifier, VerifyingKey};
+use thiserror::Error;
+
+const SIG_ALG: [u8; 2] = *b"Ed";
+const SIG_ALG_PREHASHED: [u8; 2] = *b"ED";
+const KDF_NONE: [u8; 2] = [0, 0];
+const KDF_SCRYPT: [u8; 2] = *b"Sc";
+const CHK_ALG: [u8; 2] = *b"B2";
+
+const SECRET_KEY_LEN: usize = 158;
+const PUBLIC_KEY_LEN: usize = 42;
+const SIGNATURE_LEN: usize = 74;
+
+#[derive(Debug, Error)]
+pub enum SignatureError {
+    #[error("malformed {what}: {reason}")]
+    Malformed { what: &'static str, reason: String },
+
+    #[error("encrypted secret keys are not supported; create the key with `minisign -G -W`")]
+    EncryptedKey,
+
+    #[error("unsupported signature algorithm (only prehashed ed25519 is accepted)")]
+    UnsupportedAlgorithm,
+
+    #[error("signature was made with key {signature}, expected key {public_key}")]
+    KeyMismatch {
+        signature: String,
+        public_key: String,
+    },
+
+    #[error("signature verification failed")]
+    Invalid,
+
+    #[error("signature is valid but the database could not be read: {0}")]
+    Database(String),
+}
+
+fn malformed(what: &'static str, reason: impl Into<String>) -> SignatureError {
+    SignatureError::Malformed {
+        what,
+        reason: reason.into(),
+    }
+}
+
+/// Key IDs are displayed as the uppercase hex of a little-endian u64
+fn key_id_hex(key_id: &[u8; 8]) -> String {
+    format!("{:016X}", u64::from_le_bytes(*key_id))
+}
+
+/// Split a minisign file into `(untrusted comment, decoded base64 payload)`
+/// plus any remaining lines
+fn parse_box<'a>(
+    what: &'static str,
+    text: &'a str,
+) -> Result<(Vec<u8>, std::str::Lines<'a>), SignatureError> {
+    let mut lines = text.lines();
+    let comment = lines
+        .next()
+        .ok_or_else(|| malformed(what, "file is empty"))?;
+    if !comment.starts_with("untrusted comment: ") {
+        return Err(malformed(what, "missing untrusted comment line"));
+    }
+    let payload = lines
+        .next()
+        .ok_or_else(|| malformed(what, "missing base64 payload"))?;
+    let bytes = BASE64
+        .decode(payload.trim())
+        .map_err(|e| malformed(what, e.to_string()))?;
+    Ok((bytes, lines))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PublicKey {
+    pub key_id: [u8; 8],
+    key: VerifyingKey,
+}
+
+impl PublicKey {
+    pub fn from_minisign(text: &str) -> Result<Self, SignatureError> {
+        // Accept either the whole `.pub` file or just its base64 line
+        let bytes = if text.trim_start().starts_with("untrusted comment:") {
+            parse_box("public key", text)?.0
+        } else {
+            BASE64
+                .decode(text.trim())
+                .map_err(|e| malformed("public key", e.to_string()))?
+        };
+
+        if bytes.len() != PUBLIC_KEY_LEN {
+            return Err(malformed("public key", format!("{} bytes", bytes.len())));
+        }
+        if bytes[..2] != SIG_ALG {
+            return Err(SignatureError::UnsupportedAlgorithm);
+        }
+
+        let key_id: [u8; 8] = bytes[2..10].try_into().unwrap();
+        let key: [u8; 32] = bytes[10..].try_into().unwrap();
+        let key =
+            VerifyingKey::from_bytes(&key).map_err(|e| malformed("public key", e.to_string()))?;
+
+        Ok(PublicKey { key_id, key })
+    }
+
+    pub fn to_minisign(&self) -> String {
+        let mut bytes = Vec::with_capacity(PUBLIC_KEY_LEN);
+        bytes.extend_from_slice(&SIG_ALG);
+        bytes.extend_from_slice(&self.key_id);
+        bytes.extend_from_slice(self.key.as_bytes());
+        format!(
+            "untrusted comment: minisign public key {}\n{}\n",
+            key_id_hex(&self.key_id),
+            BASE64.encode(bytes)
+        )
+    }
+
+    pub fn key_id(&self) -> String {
+        key_id_hex(&self.key_id)
+    }
+}
+
+pub struct SecretKey {
+    pub key_id: [u8; 8],
+    key: SigningKey,
+}
+
+impl SecretKey {
+    pub fn from_seed(seed: [u8; 32], key_id: [u8; 8]) -> Self {
+        SecretKey {
+            key_id,
+            key: SigningKey::from_bytes(&seed),
+        }
+    }
+
+    pub fn public_key(&self) -> PublicKey {
+        PublicKey {
+            key_id: self.key_id,
+            key: self.key.verifying_key(),
+        }
+    }
+
+    pub fn from_minisign(text: &str) -> Result<Self, SignatureError> {
+        let (bytes, _) = parse_box("secret key", text)?;
+        if bytes.len() != SECRET_KEY_LEN {
+            return Err(malformed("secret key", format!("{} bytes", bytes.len())));
+        }
+        if bytes[..2] != SIG_ALG || bytes[4..6] != CHK_ALG {
+            return Err(SignatureError::UnsupportedAlgorithm);
+        }
+        match [bytes[2], bytes[3]] {
+            KDF_NONE => {}
+            KDF_SCRYPT => return Err(SignatureError::EncryptedKey),
+            _ => return Err(SignatureError::UnsupportedAlgorithm),
+        }
+
+        // sig_alg(2) kdf_alg(2) chk_alg(2) salt(32) opslimit(8) memlimit(8)
+        let keynum = &bytes[54..];
+        let key_id: [u8; 8] = keynum[..8].try_into().unwrap();
+        let sk = &keynum[8..72];
+        let checksum = &keynum[72..];
+        if checksum != secret_key_checksum(&key_id, sk) {
+            return Err(malformed("secret key", "checksum mismatch"));
+        }
+
+        let seed: [u8; 32] = sk[..32].try_into().unwrap();
+        let key = SigningKey::from_bytes(&seed);
+        if key.verifying_key().as_bytes()[..] != sk[32..] {
+            return Err(malformed("secret key", "public half does not match"));
+        }
+
+        Ok(SecretKey { key_id, key })
+    }
+
+    /// Serialize as an unencrypted minisign secret key file
+    pub fn to_minisign(&self) -> String {
+        let mut sk = Vec::with_capacity(64);
+        sk.extend_from_slice(self.key.as_bytes());
+        sk.extend_from_slice(self.key.verifying_key().as_bytes());
+
+        let mut bytes = Vec::with_capacity(SECRET_KEY_LEN);
+        bytes.extend_from_slice(&SIG_ALG);
+        bytes.extend_from_slice(&KDF_NONE);
+        bytes.extend_from_slice(&CHK_ALG);
+        bytes.extend_from_slice(&[0; 32 + 8 + 8]);
+        bytes.extend_from_slice(&self.key_id);
+        bytes.extend_from_slice(&sk);
+        bytes.extend_from_slice(&secret_key_checksum(&self.key_id, &sk));
+
+        format!(
+            "untrusted comment: minisign encrypted secret key\n{}\n",
+            BASE64.encode(bytes)
+        )
+    }
+
+    /// Sign `data`, embedding `trusted_comment` in the signature
+    pub fn sign(&self, data: &[u8], trusted_comment: &str) -> Signature {
+        let signature = self.key.sign(&Blake2b512::digest(data)).to_bytes();
+
+        let mut global = signature.to_vec();
+        global.extend_from_slice(trusted_comment.as_bytes());
+        let global_signature = self.key.sign(&global).to_bytes();
+
+        Signature {
+            key_id: self.key_id,
+            signature,
+            trusted_comment: trusted_comment.to_string(),
+            global_signature,
+        }
+    }
+}
+
+fn secret_key_checksum(key_id: &[u8; 8], sk: &[u8]) -> [u8; 32] {
+    let mut hasher = Blake2b::<U32>::new();
+    hasher.update(SIG_ALG);
+    hasher.update(key_id);
+    hasher.update(sk);
+    hasher.finalize().into()
+}
+
+/// A detached `.minisig` signature
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signature {
+    pub key_id: [u8; 8],
+    signature: [u8; 64],
+    pub trusted_comment: String,
+    global_signature: [u8; 64],
+}
+
+impl Signature {
+    pub fn from_minisign(text: &str) -> Result<Self, SignatureError> {
+        let (bytes, mut lines) = parse_box("signature", text)?;
+        if bytes.len() != SIGNATURE_LEN {
+            return Err(malformed("signature", format!("{} bytes", bytes.len())));
+        }
+        if bytes[..2] != SIG_ALG_PREHASHED {
+            return Err(SignatureError::UnsupportedAlgorithm);
+        }
+
+        let trusted_comment = lines
+            .next()
+            .and_then(|line| line.strip_prefix("trusted comment: "))
+            .ok_or_else(|| malformed("signature", "missing trusted comment"))?;
+        let global = lines
+            .next()
+            .ok_or_else(|| malformed("signature", "missing global signature"))?;
+        let global = BASE64
+            .decode(global.trim())
+            .map_err(|e| malformed("signature", e.to_string()))?;
+
+        Ok(Signature {
+            key_id: bytes[2..10].try_into().unwrap(),
+            signature: bytes[10..].try_into().unwrap(),
+            trusted_comment: trusted_comment.to_string(),
+            global_signature: global
+                .try_into()
+                .map_err(|_| malformed("signature", "global signature must be 64 bytes"))?,
+        })
+    }
+
+    pub fn to_minisign(&self) -> String {
+        let mut bytes = Vec::with_capacity(SIGNATURE_LEN);
+        bytes.extend_from_slice(&SIG_ALG_PREHASHED);
+        bytes.extend_from_slice(&self.key_id);
+        bytes.extend_from_slice(&self.signature);
+        format!(
+            "untrusted comment: signature from heimdal-packages secret key\n{}\ntrusted comment: {}\n{}\n",
+            BASE64.encode(bytes),
+            self.trusted_comment,
+            BASE64.encode(self.global_signature)
+        )
+    }
+}
+
+/// Check `signature` over `data` against the pinned `public_key`
+pub fn verify(
+    data: &[u8],
+    signature: &Signature,
+    public_key: &PublicKey,
+) -> Result<(), SignatureError> {
+    if signature.key_id != public_key.key_id {
+        return Err(SignatureError::KeyMismatch {
+            signature: key_id_hex(&signature.key_id),
+            public_key: public_key.key_id(),
+        });
+    }
+
+    let sig = ed25519_dalek::Signature::from_bytes(&signature.signature);
+    public_key
+        .key
+        .verify(&Blake2b512::digest(data), &sig)
+        .map_err(|_| SignatureError::Invalid)?;
+
+    let mut global = signature.signature.to_vec();
+    global.extend_from_slice(signature.trusted_comment.as_bytes());
+    let global_sig = ed25519_dalek::Signature::from_bytes(&signature.global_signature);
+    public_key
+        .key
+        .verify(&global, &global_sig)
+        .map_err(|_| SignatureError::Invalid)?;
+
+    Ok(())
+}
+
+/// Verify a downloaded database and only then deserialize it
+///
+/// `data` may be raw or zstd-compressed; the signature covers the exact
+/// bytes that were downloaded.
+pub fn verify_database(
+    data: &[u8],
+    signature: &Signature,
+    public_key: &PublicKey,
+) -> Result<CompiledDatabase, SignatureError> {
+    verify(data, signature, public_key)?;
+    CompiledDatabase::from_bytes(data).map_err(|e| SignatureError::Database(format!("{:#}", e)))
+}
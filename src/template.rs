@@ -0,0 +1,195 @@
+//! Template materialization
+//!
+//! Renders a template into one flattened environment spec: every package with
+//! its names on the template's platform, the dotfiles and hooks merged from
+//! the base profile chain, the group `platform_overrides` that apply, and the
+//! template's settings. Heimdal consumes the same document.
+
+use crate::choice::Choices;
+use crate::conflicts::PackageConflict;
+use crate::coverage::availability;
+use crate::dotfiles::{DotfileConflict, ResolvedDotfile};
+use crate::loader::SourceTree;
+use crate::model::{Hooks, Manager, PackageGroup, PlatformOverride, Settings, TemplatePlatform};
+use crate::resolve::{Origin, ResolveError, ResolvedChoice, Resolver};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum RenderError {
+    #[error(transparent)]
+    Resolve(#[from] ResolveError),
+
+    #[error("unknown package '{package}' (from {origin})")]
+    UnknownPackage { package: String, origin: Origin },
+
+    #[error("settings.{setting} is '{package}', which the template does not install")]
+    SettingNotInstalled {
+        setting: &'static str,
+        package: String,
+    },
+
+    #[error("{0}")]
+    DotfileConflict(Box<DotfileConflict>),
+
+    #[error("conflicting packages: {0}")]
+    PackageConflict(PackageConflict),
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RenderedPackage {
+    pub name: String,
+    pub description: String,
+    pub sources: Vec<Origin>,
+    /// Native name on each of the template's package managers
+    pub platforms: BTreeMap<Manager, String>,
+    /// Template managers the package has no name on
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub unavailable: Vec<Manager>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RenderedTemplate {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub platform: TemplatePlatform,
+    pub profile: Option<String>,
+    /// Profiles merged through the base profile's inheritance chain
+    pub profiles: Vec<String>,
+    pub packages: Vec<RenderedPackage>,
+    /// Choice slots and the option installed for each
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub choices: Vec<ResolvedChoice>,
+    /// Merged overrides of the template's groups, keyed `macos` / `linux`
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub platform_overrides: BTreeMap<String, PlatformOverride>,
+    pub dotfiles: Vec<ResolvedDotfile>,
+    pub hooks: Hooks,
+    pub settings: Settings,
+}
+
+impl RenderedTemplate {
+    pub fn render(tree: &SourceTree, id: &str) -> Result<Self, RenderError> {
+        Self::render_with_choices(tree, id, &Choices::new())
+    }
+
+    /// Render with the user's picks for the template's choice slots
+    pub fn render_with_choices(
+        tree: &SourceTree,
+        id: &str,
+        choices: &Choices,
+    ) -> Result<Self, RenderError> {
+        let resolved = Resolver::from_tree(tree)
+            .with_choices(choices)
+            .resolve_template(id)?;
+        let template = tree.template(id).expect("resolved template exists");
+        if let Some(conflict) = resolved.dotfile_conflicts.first() {
+            return Err(RenderError::DotfileConflict(Box::new(conflict.clone())));
+        }
+        if let Some(conflict) = resolved.package_conflicts.first() {
+            return Err(RenderError::PackageConflict(conflict.clone()));
+        }
+        let managers = template.platform.managers();
+        // Groups included anywhere in the base profile's chain, then the
+        // template's own
+        let groups: Vec<&PackageGroup> = resolved
+            .groups(tree)
+            .into_iter()
+            .chain(template.groups.iter().filter_map(|g| tree.group(g)))
+            .collect();
+
+        let packages = resolved
+            .packages
+            .into_iter()
+            .map(|resolved| {
+                let package =
+                    tree.package(&resolved.name)
+                        .ok_or_else(|| RenderError::UnknownPackage {
+                            package: resolved.name.clone(),
+                            origin: resolved.origin().clone(),
+                        })?;
+                let mapping = tree.mapping(&package.name);
+
+                let mut platforms = BTreeMap::new();
+                let mut unavailable = Vec::new();
+                for &manager in &managers {
+                    match availability(package, mapping, manager) {
+                        Some(a) => {
+                            platforms.insert(manager, a.name);
+                        }
+                        None => unavailable.push(manager),
+                    }
+                }
+
+                Ok(RenderedPackage {
+                    name: resolved.name,
+                    description: package.description.clone(),
+                    sources: resolved.sources,
+                    platforms,
+                    unavailable,
+                })
+            })
+            .collect::<Result<Vec<_>, RenderError>>()?;
+
+        for (setting, package) in template.settings.packages() {
+            if !packages.iter().any(|p| p.name == package) {
+                return Err(RenderError::SettingNotInstalled {
+                    setting,
+                    package: package.to_string(),
+                });
+            }
+        }
+
+        let platform_overrides = merge_overrides(groups, |key| template.platform.covers(key));
+
+        Ok(RenderedTemplate {
+            id: template.id.clone(),
+            name: template.name.clone(),
+            description: template.description.clone(),
+            platform: template.platform,
+            profile: template.profile.clone(),
+            profiles: resolved.chain,
+            packages,
+            choices: resolved.choices,
+            platform_overrides,
+            dotfiles: resolved.dotfiles,
+            hooks: resolved.hooks,
+            settings: template.settings.clone(),
+        })
+    }
+
+    pub fn package(&self, name: &str) -> Option<&RenderedPackage> {
+        self.packages.iter().find(|p| p.name == name)
+    }
+}
+
+/// Merge the `platform_overrides` of `groups` whose key `covers` accepts,
+/// dropping empty entries
+pub(crate) fn merge_overrides<'a>(
+    groups: impl IntoIterator<Item = &'a PackageGroup>,
+    covers: impl Fn(&str) -> bool,
+) -> BTreeMap<String, PlatformOverride> {
+    let mut platform_overrides: BTreeMap<String, PlatformOverride> = BTreeMap::new();
+    for group in groups {
+        for (key, overrides) in &group.platform_overrides {
+            if !covers(key) {
+                continue;
+            }
+            let merged = platform_overrides.entry(key.clone()).or_default();
+            for (into, from) in [
+                (&mut merged.packages, &overrides.packages),
+                (&mut merged.casks, &overrides.casks),
+            ] {
+                for name in from {
+                    if !into.contains(name) {
+                        into.push(name.clone());
+                    }
+                }
+            }
+        }
+    }
+    platform_overrides.retain(|_, o| !o.packages.is_empty() || !o.casks.is_empty());
+    platform_overrides
+}
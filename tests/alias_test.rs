@@ -0,0 +1,104 @@
+//! Alias resolution tests
+
+use heimdal_packages::alias::{AliasCollision, AliasIndex, NameKind};
+use heimdal_packages::database::CompiledDatabase;
+use heimdal_packages::loader::SourceTree;
+use heimdal_packages::model::{Manager, Mapping};
+
+fn mapping(yaml: &str) -> Mapping {
+    serde_yaml::from_str(yaml).expect("Failed to parse test mapping")
+}
+
+#[test]
+fn test_resolves_aliases_and_platform_names() {
+    let tree = SourceTree::load(".").expect("Failed to load source tree");
+    let index = AliasIndex::from_tree(&tree);
+    assert!(index.collisions.is_empty(), "{:?}", index.collisions);
+
+    let resolve = |name: &str| index.resolve(name).map(|e| (e.canonical, e.kind));
+    assert_eq!(
+        resolve("git"),
+        Some(("git".to_string(), NameKind::Canonical))
+    );
+    assert_eq!(
+        resolve("git-core"),
+        Some(("git".to_string(), NameKind::Alias))
+    );
+    assert_eq!(
+        resolve("gmake"),
+        Some(("make".to_string(), NameKind::Alias))
+    );
+    assert_eq!(
+        resolve("libcurl"),
+        Some(("curl".to_string(), NameKind::Alias))
+    );
+    assert_eq!(
+        resolve("docker.io"),
+        Some(("docker".to_string(), NameKind::Platform(vec![Manager::Apt])))
+    );
+    assert_eq!(resolve("kubernetes-client").unwrap().0, "kubectl");
+    assert_eq!(resolve("no-such-tool"), None);
+
+    // A manager-specific lookup only considers that manager's names
+    assert_eq!(
+        index
+            .resolve_on(Manager::Dnf, "kubernetes-client")
+            .unwrap()
+            .kind,
+        NameKind::Platform(vec![Manager::Dnf])
+    );
+    assert_eq!(
+        index.resolve_on(Manager::Brew, "git").unwrap().kind,
+        NameKind::Canonical
+    );
+}
+
+#[test]
+fn test_reports_alias_collisions() {
+    let tree = SourceTree::load(".").expect("Failed to load source tree");
+    let mappings = vec![
+        mapping("{canonical: vim, platforms: {apt: vim, brew: vim}, aliases: [vi, neovim]}"),
+        mapping("{canonical: helix, platforms: {apt: helix, brew: helix}, aliases: [vi]}"),
+        mapping("{canonical: bat, platforms: {apt: bat, brew: bat}, aliases: [docker.io]}"),
+    ];
+    let index = AliasIndex::build(&tree.packages, &mappings);
+
+    assert_eq!(
+        index.collisions,
+        vec![
+            AliasCollision::PackageName {
+                alias: "neovim".to_string(),
+                canonical: "vim".to_string(),
+            },
+            AliasCollision::Duplicate {
+                alias: "vi".to_string(),
+                first: "vim".to_string(),
+                second: "helix".to_string(),
+            },
+            AliasCollision::PlatformName {
+                alias: "docker.io".to_string(),
+                canonical: "bat".to_string(),
+                owner: "docker".to_string(),
+                manager: Manager::Apt,
+            },
+        ]
+    );
+    assert_eq!(
+        index.collisions[2].to_string(),
+        "alias 'docker.io' of 'bat' is the apt name of 'docker'"
+    );
+}
+
+#[test]
+fn test_alias_index_is_compiled_into_database() {
+    let tree = SourceTree::load(".").expect("Failed to load source tree");
+    let aliases = AliasIndex::from_tree(&tree);
+    let db = CompiledDatabase::build(tree.packages, tree.groups).with_aliases(&aliases);
+
+    let db = CompiledDatabase::from_bytes(&db.to_bytes().unwrap()).unwrap();
+    assert_eq!(db.index_by_alias["gmake"].canonical, "make");
+    assert_eq!(db.resolve("git-core").unwrap().name, "git");
+    assert_eq!(db.resolve("docker.io").unwrap().name, "docker");
+    assert_eq!(db.resolve("ripgrep").unwrap().name, "ripgrep");
+    assert!(db.resolve("no-such-tool").is_none());
+}
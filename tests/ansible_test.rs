@@ -0,0 +1,113 @@
+//! Ansible role export tests
+
+use heimdal_packages::ansible::AnsibleRole;
+use heimdal_packages::export::{ExportError, PackageSet, Selection};
+use heimdal_packages::loader::SourceTree;
+use heimdal_packages::model::Manager;
+use serde_yaml::Value;
+
+fn role(selection: Selection) -> AnsibleRole {
+    let tree = SourceTree::load(".").expect("Failed to load source tree");
+    let set = PackageSet::load(&tree, &selection).expect("Selection should export");
+    AnsibleRole::build(&set)
+}
+
+fn document(role: &AnsibleRole, path: &str) -> Value {
+    serde_yaml::from_str(&role.files[path]).expect("Role files should be valid YAML")
+}
+
+#[test]
+fn test_exports_group_with_optional_packages_and_casks() {
+    let role = role(Selection::Group("devops".to_string()));
+    assert_eq!(role.name, "devops");
+
+    let vars = document(&role, "vars/main.yml");
+    let packages = &vars["devops_packages"];
+    assert_eq!(packages["apt"]["required"][1], "docker.io");
+    assert!(packages["apt"]["optional"]
+        .as_sequence()
+        .unwrap()
+        .contains(&Value::from("terraform")));
+    assert_eq!(packages["casks"][0], "docker");
+
+    let defaults = document(&role, "defaults/main.yml");
+    assert_eq!(defaults["devops_install_optional"], false);
+
+    let tasks = document(&role, "tasks/main.yml");
+    let apt = &tasks[0];
+    assert_eq!(apt["when"], "ansible_os_family == \"Debian\"");
+    assert_eq!(apt["become"], true);
+    assert!(apt["ansible.builtin.apt"]["name"]
+        .as_str()
+        .unwrap()
+        .contains("devops_install_optional | bool"));
+    assert!(tasks
+        .as_sequence()
+        .unwrap()
+        .iter()
+        .any(|t| !t["community.general.homebrew_cask"].is_null()));
+    assert!(!role.files.contains_key("handlers/main.yml"));
+}
+
+#[test]
+fn test_exports_profile_hooks_and_dotfiles() {
+    let role = role(Selection::Profile("devops".to_string()));
+
+    let handlers = document(&role, "handlers/main.yml");
+    assert_eq!(
+        handlers[0]["ansible.builtin.shell"],
+        "kubectl version --client"
+    );
+    assert_eq!(handlers[0]["listen"], "devops packages installed");
+
+    let tasks = document(&role, "tasks/main.yml");
+    let tasks = tasks.as_sequence().unwrap();
+    let brew = tasks
+        .iter()
+        .find(|t| !t["community.general.homebrew"].is_null())
+        .expect("profile installs on Homebrew");
+    assert!(brew["become"].is_null());
+    assert_eq!(brew["notify"], "devops packages installed");
+    assert!(tasks
+        .iter()
+        .any(|t| !t["ansible.builtin.copy"].is_null() && t["loop"] == "{{ devops_dotfiles }}"));
+
+    let vars = document(&role, "vars/main.yml");
+    assert_eq!(vars["devops_dotfiles"][2]["dest"], ".config/k9s");
+    assert_eq!(
+        role.dotfile_sources,
+        vec![".gitconfig", ".bashrc", ".config/k9s"]
+    );
+
+    let dir = tempfile::tempdir().unwrap();
+    role.write(dir.path()).unwrap();
+    assert!(dir.path().join("tasks/main.yml").is_file());
+    assert!(dir.path().join("files").is_dir());
+}
+
+#[test]
+fn test_exports_template_for_its_platform_only() {
+    let tree = SourceTree::load(".").expect("Failed to load source tree");
+    let set = PackageSet::load(&tree, &Selection::Template("macos-developer".to_string()))
+        .expect("Template should export");
+    assert_eq!(set.managers, vec![Manager::Brew]);
+
+    let role = AnsibleRole::build(&set);
+    assert_eq!(role.name, "macos_developer");
+    let vars = document(&role, "vars/main.yml");
+    assert!(vars["macos_developer_packages"]["apt"].is_null());
+    assert!(vars["macos_developer_packages"]["package"].is_null());
+}
+
+#[test]
+fn test_reports_unknown_selections() {
+    let tree = SourceTree::load(".").expect("Failed to load source tree");
+    assert!(matches!(
+        PackageSet::load(&tree, &Selection::Group("nope".to_string())),
+        Err(ExportError::UnknownGroup(_))
+    ));
+    assert!(matches!(
+        PackageSet::load(&tree, &Selection::Profile("nope".to_string())),
+        Err(ExportError::Resolve(_))
+    ));
+}
@@ -0,0 +1,168 @@
+//! Choice slot tests
+
+use heimdal_packages::choice::{self, ChoiceError, Choices};
+use heimdal_packages::export::{PackageSet, Selection};
+use heimdal_packages::loader::SourceTree;
+use heimdal_packages::model::{Choice, Package};
+use heimdal_packages::resolve::{Origin, ResolveError, Resolver};
+use heimdal_packages::template::RenderedTemplate;
+
+fn package(name: &str, alternatives: &[&str]) -> Package {
+    serde_yaml::from_str(&format!(
+        r#"
+name: {}
+description: Test package
+category: utilities
+popularity: 50
+platforms:
+  apt: {}
+alternatives: [{}]
+tags: [test]
+"#,
+        name,
+        name,
+        alternatives.join(", ")
+    ))
+    .expect("Invalid package YAML")
+}
+
+fn editor_choice(default: &str) -> Choice {
+    serde_yaml::from_str(&format!(
+        "id: editor\noptions: [helix, emacs]\ndefault: {}",
+        default
+    ))
+    .expect("Invalid choice YAML")
+}
+
+fn choices(picks: &[(&str, &str)]) -> Choices {
+    picks
+        .iter()
+        .map(|(slot, package)| (slot.to_string(), package.to_string()))
+        .collect()
+}
+
+#[test]
+fn test_checks_choice_slots() {
+    let tree = SourceTree::load(".").expect("Failed to load source tree");
+    for group in &tree.groups {
+        assert!(choice::check(&group.choices, &group.packages.required, &tree.packages).is_empty());
+    }
+
+    let packages = [package("a", &["b"]), package("b", &[]), package("c", &[])];
+    let slots: Vec<Choice> = serde_yaml::from_str(
+        r#"
+- id: tool
+  options: [a, b]
+  default: a
+- id: tool
+  options: [a, c, nope]
+  default: d
+"#,
+    )
+    .unwrap();
+    let listed = ["b".to_string()];
+
+    assert_eq!(
+        choice::check(&slots, &listed, &packages),
+        vec![
+            ChoiceError::AlsoListed {
+                slot: "tool".to_string(),
+                option: "b".to_string(),
+            },
+            ChoiceError::DuplicateSlot {
+                slot: "tool".to_string(),
+            },
+            ChoiceError::DefaultNotAnOption {
+                slot: "tool".to_string(),
+                default: "d".to_string(),
+            },
+            ChoiceError::UnknownOption {
+                slot: "tool".to_string(),
+                option: "nope".to_string(),
+            },
+            ChoiceError::NotAlternatives {
+                slot: "tool".to_string(),
+                first: "a".to_string(),
+                second: "c".to_string(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_resolves_defaults_and_user_picks() {
+    let mut tree = SourceTree::load(".").expect("Failed to load source tree");
+    let devops = tree.profiles.iter_mut().find(|p| p.id == "devops").unwrap();
+    devops.choices.push(editor_choice("helix"));
+
+    let resolved = Resolver::from_tree(&tree)
+        .resolve_profile("devops")
+        .unwrap();
+    assert_eq!(resolved.choices[0].chosen, "helix");
+    assert_eq!(
+        resolved.package("helix").unwrap().origin(),
+        &Origin::Profile {
+            id: "devops".to_string(),
+            section: "choices",
+        }
+    );
+    assert!(resolved.package("emacs").is_none());
+
+    let picks = choices(&[("editor", "emacs")]);
+    let resolved = Resolver::from_tree(&tree)
+        .with_choices(&picks)
+        .resolve_profile("devops")
+        .unwrap();
+    assert_eq!(resolved.choices[0].chosen, "emacs");
+    assert!(resolved.package("emacs").is_some());
+    assert!(resolved.package("helix").is_none());
+}
+
+#[test]
+fn test_rejects_invalid_and_unknown_picks() {
+    let mut tree = SourceTree::load(".").expect("Failed to load source tree");
+    let group = tree.groups.iter_mut().find(|g| g.id == "devops").unwrap();
+    group.choices.push(editor_choice("emacs"));
+
+    let picks = choices(&[("editor", "vim")]);
+    let error = RenderedTemplate::render_with_choices(&tree, "linux-server", &picks).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "'vim' is not an option of choice 'editor' (options: helix, emacs)"
+    );
+
+    let picks = choices(&[("shell", "zsh")]);
+    assert_eq!(
+        Resolver::from_tree(&tree)
+            .with_choices(&picks)
+            .resolve_template("linux-server")
+            .unwrap_err(),
+        ResolveError::UnknownSlot("shell".to_string())
+    );
+
+    assert_eq!(
+        choice::parse("editor=helix"),
+        Ok(("editor".to_string(), "helix".to_string()))
+    );
+    assert!(choice::parse("editor").is_err());
+}
+
+#[test]
+fn test_export_installs_group_choice() {
+    let mut tree = SourceTree::load(".").expect("Failed to load source tree");
+    let group = tree.groups.iter_mut().find(|g| g.id == "devops").unwrap();
+    group.choices.push(editor_choice("emacs"));
+
+    let selection = Selection::Group("devops".to_string());
+    let set = PackageSet::load(&tree, &selection).unwrap();
+    assert!(set.packages.iter().any(|p| p.name == "emacs" && p.required));
+
+    let picks = choices(&[("editor", "helix")]);
+    let set = PackageSet::load_with_choices(&tree, &selection, &picks).unwrap();
+    assert!(set.packages.iter().any(|p| p.name == "helix" && p.required));
+    assert!(set.packages.iter().all(|p| p.name != "emacs"));
+
+    let rendered = RenderedTemplate::render_with_choices(&tree, "linux-server", &picks).unwrap();
+    assert_eq!(rendered.choices[0].chosen, "helix");
+    assert!(rendered.package("helix").is_some());
+}
@@ -0,0 +1,76 @@
+//! Compressed database tests
+
+use heimdal_packages::database::{
+    checksum, compress, decompress, is_compressed, CompiledDatabase, DATABASE_VERSION,
+};
+use heimdal_packages::loader::SourceTree;
+
+#[test]
+fn test_compressed_database_is_read_transparently() {
+    let tree = SourceTree::load(".").expect("Failed to load source tree");
+    let raw = CompiledDatabase::build(tree.packages.clone(), tree.groups.clone())
+        .to_bytes()
+        .expect("Failed to serialize database");
+
+    let compressed = compress(&raw).expect("Failed to compress database");
+    assert!(is_compressed(&compressed));
+    assert!(!is_compressed(&raw));
+    assert!(compressed.len() < raw.len());
+
+    // The raw checksum also verifies the decompressed download
+    let decompressed = decompress(&compressed).expect("Failed to decompress database");
+    assert_eq!(checksum(&decompressed), checksum(&raw));
+
+    let db = CompiledDatabase::from_bytes(&compressed).expect("Failed to read compressed database");
+    assert_eq!(db.packages, tree.packages);
+}
+
+#[test]
+fn test_corrupt_compressed_database_is_rejected() {
+    let tree = SourceTree::load(".").expect("Failed to load source tree");
+    let raw = CompiledDatabase::build(tree.packages, tree.groups)
+        .to_bytes()
+        .expect("Failed to serialize database");
+
+    let mut compressed = compress(&raw).expect("Failed to compress database");
+    compressed.truncate(compressed.len() / 2);
+
+    assert!(CompiledDatabase::from_bytes(&compressed).is_err());
+}
+
+#[test]
+fn test_other_database_versions_are_rejected() {
+    let tree = SourceTree::load(".").expect("Failed to load source tree");
+    let mut db = CompiledDatabase::build(tree.packages, tree.groups);
+    db.version = DATABASE_VERSION - 1;
+    let old = compress(&db.to_bytes().unwrap()).unwrap();
+
+    let error = CompiledDatabase::from_bytes(&old).unwrap_err().to_string();
+    assert_eq!(
+        error,
+        format!(
+            "Unsupported database version {} (this build reads version {})",
+            DATABASE_VERSION - 1,
+            DATABASE_VERSION
+        )
+    );
+    assert!(CompiledDatabase::from_bytes(&[1, 0]).is_err());
+}
+
+#[test]
+fn test_source_date_epoch_makes_builds_reproducible() {
+    std::env::set_var("SOURCE_DATE_EPOCH", "1767225600");
+    let tree = SourceTree::load(".").expect("Failed to load source tree");
+    let build = || {
+        CompiledDatabase::build(tree.packages.clone(), tree.groups.clone())
+            .to_bytes()
+            .expect("Failed to serialize database")
+    };
+
+    let first = build();
+    std::thread::sleep(std::time::Duration::from_millis(10));
+    assert_eq!(build(), first);
+
+    let db = CompiledDatabase::from_bytes(&first).expect("Failed to read database");
+    assert_eq!(db.last_updated, "2026-01-01T00:00:00+00:00");
+}
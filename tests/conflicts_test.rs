@@ -0,0 +1,127 @@
+//! Package conflict tests
+
+use heimdal_packages::conflicts::{self, ConflictError, PackageConflict};
+use heimdal_packages::export::{ExportError, PackageSet, Selection};
+use heimdal_packages::loader::SourceTree;
+use heimdal_packages::model::Package;
+use heimdal_packages::resolve::Resolver;
+use heimdal_packages::template::{RenderError, RenderedTemplate};
+
+fn package(name: &str, conflicts: &[&str]) -> Package {
+    serde_yaml::from_str(&format!(
+        r#"
+name: {}
+description: Test package
+category: utilities
+popularity: 50
+platforms:
+  apt: {}
+conflicts: [{}]
+tags: [test]
+"#,
+        name,
+        name,
+        conflicts.join(", ")
+    ))
+    .expect("Invalid package YAML")
+}
+
+/// The repository, with `first` and `second` declared conflicting
+fn tree_with_conflict(first: &str, second: &str) -> SourceTree {
+    let mut tree = SourceTree::load(".").expect("Failed to load source tree");
+    for (name, other) in [(first, second), (second, first)] {
+        let package = tree.packages.iter_mut().find(|p| p.name == name).unwrap();
+        package.conflicts.push(other.to_string());
+    }
+    tree
+}
+
+#[test]
+fn test_checks_conflict_declarations() {
+    let tree = SourceTree::load(".").expect("Failed to load source tree");
+    assert!(conflicts::check(&tree.packages).is_empty());
+
+    let errors = conflicts::check(&[
+        package("a", &["b"]),
+        package("b", &["a"]),
+        package("c", &["a", "c", "nope"]),
+    ]);
+    assert_eq!(
+        errors,
+        vec![
+            ConflictError::Asymmetric {
+                package: "c".to_string(),
+                conflict: "a".to_string(),
+            },
+            ConflictError::SelfConflict {
+                package: "c".to_string(),
+            },
+            ConflictError::Unknown {
+                package: "c".to_string(),
+                conflict: "nope".to_string(),
+            },
+        ]
+    );
+    assert_eq!(
+        errors[0].to_string(),
+        "'c' conflicts with 'a', but 'a' does not list 'c' in its conflicts"
+    );
+}
+
+#[test]
+fn test_finds_conflicts_declared_on_either_side() {
+    let packages = [package("a", &[]), package("b", &["a"]), package("c", &[])];
+
+    assert_eq!(
+        conflicts::find(&packages, ["c", "a", "b"]),
+        vec![PackageConflict {
+            package: "a".to_string(),
+            conflicts_with: "b".to_string(),
+        }]
+    );
+    assert!(conflicts::find(&packages, ["a", "c"]).is_empty());
+}
+
+#[test]
+fn test_resolver_reports_conflicts_in_profiles_and_templates() {
+    let tree = tree_with_conflict("docker", "kubectl");
+    let resolver = Resolver::from_tree(&tree);
+
+    let resolved = resolver.resolve_profile("devops").unwrap();
+    assert_eq!(
+        resolved.package_conflicts,
+        vec![PackageConflict {
+            package: "docker".to_string(),
+            conflicts_with: "kubectl".to_string(),
+        }]
+    );
+    assert!(resolver
+        .resolve_profile("minimal")
+        .unwrap()
+        .package_conflicts
+        .is_empty());
+
+    let error = RenderedTemplate::render(&tree, "linux-server").unwrap_err();
+    assert!(matches!(error, RenderError::PackageConflict(_)));
+    assert_eq!(
+        error.to_string(),
+        "conflicting packages: 'docker' conflicts with 'kubectl'"
+    );
+}
+
+#[test]
+fn test_export_rejects_conflicting_required_packages() {
+    let tree = tree_with_conflict("docker", "kubectl");
+    assert!(matches!(
+        PackageSet::load(&tree, &Selection::Group("devops".to_string())),
+        Err(ExportError::PackageConflict(_))
+    ));
+    assert!(matches!(
+        PackageSet::load(&tree, &Selection::Profile("devops".to_string())),
+        Err(ExportError::PackageConflict(_))
+    ));
+
+    // Optional packages are chosen one by one
+    let tree = tree_with_conflict("terraform", "ansible");
+    assert!(PackageSet::load(&tree, &Selection::Group("devops".to_string())).is_ok());
+}
@@ -0,0 +1,95 @@
+//! Dockerfile and devcontainer generation tests
+
+use heimdal_packages::container::{ContainerError, ContainerSpec, Family};
+use heimdal_packages::export::{PackageSet, Selection};
+use heimdal_packages::loader::SourceTree;
+
+fn spec(
+    selection: Selection,
+    family: Family,
+    optional: bool,
+) -> Result<ContainerSpec, ContainerError> {
+    let tree = SourceTree::load(".").expect("Failed to load source tree");
+    let set = PackageSet::load(&tree, &selection).expect("Selection should export");
+    ContainerSpec::build(&tree, &set, family, optional)
+}
+
+#[test]
+fn test_dockerfile_has_one_cached_install_layer() {
+    let spec = spec(
+        Selection::Group("devops".to_string()),
+        Family::Debian,
+        false,
+    )
+    .unwrap();
+    assert_eq!(
+        spec.packages,
+        vec!["docker-compose", "docker.io", "git", "kubectl"]
+    );
+
+    let dockerfile = spec.dockerfile();
+    assert!(dockerfile.starts_with("# syntax=docker/dockerfile:1\n"));
+    assert!(dockerfile.contains("FROM debian:bookworm-slim\n"));
+    assert_eq!(dockerfile.matches("RUN ").count(), 1);
+    assert!(dockerfile.contains("--mount=type=cache,target=/var/cache/apt"));
+    assert!(dockerfile.contains("apt-get install -y --no-install-recommends"));
+
+    let custom = spec.with_image("ubuntu:24.04");
+    assert!(custom.dockerfile().contains("FROM ubuntu:24.04\n"));
+}
+
+#[test]
+fn test_uses_family_names_and_skips_unmapped_optional_packages() {
+    let alpine = spec(Selection::Group("devops".to_string()), Family::Alpine, true).unwrap();
+    assert!(alpine.packages.contains(&"docker-cli-compose".to_string()));
+    assert!(alpine.dockerfile().contains("apk add --update-cache"));
+    assert_eq!(alpine.skipped, vec!["terraform"]);
+
+    let rust = spec(
+        Selection::Group("rust-dev".to_string()),
+        Family::Fedora,
+        false,
+    )
+    .unwrap();
+    assert!(rust.packages.contains(&"pkg-config".to_string()));
+}
+
+#[test]
+fn test_fails_when_required_package_is_unmapped() {
+    let err = spec(
+        Selection::Profile("devops".to_string()),
+        Family::Debian,
+        false,
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContainerError::Unmapped {
+            family: Family::Debian,
+            packages: vec!["helm".to_string(), "k9s".to_string()],
+        }
+    );
+    assert_eq!(
+        err.to_string(),
+        "required packages have no debian name: helm, k9s"
+    );
+}
+
+#[test]
+fn test_devcontainer_runs_post_install_hooks() {
+    let spec = spec(
+        Selection::Profile("devops".to_string()),
+        Family::Arch,
+        false,
+    )
+    .unwrap();
+    let devcontainer = spec.devcontainer();
+    assert_eq!(devcontainer["build"]["dockerfile"], "Dockerfile");
+    assert_eq!(devcontainer["name"], "profile devops");
+    assert_eq!(
+        devcontainer["postCreateCommand"],
+        "kubectl version --client && docker --version"
+    );
+    assert!("alpine".parse::<Family>().is_ok());
+    assert!("gentoo".parse::<Family>().is_err());
+}
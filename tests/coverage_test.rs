@@ -0,0 +1,97 @@
+//! Platform coverage report tests
+
+use heimdal_packages::coverage::{CoverageMatrix, CoverageReport, SetCoverage, Source};
+use heimdal_packages::loader::SourceTree;
+use heimdal_packages::model::Manager;
+use heimdal_packages::resolve::Resolver;
+
+#[test]
+fn test_matrix_lists_missing_packages_per_platform() {
+    let tree = SourceTree::load(".").expect("Failed to load source tree");
+    let matrix = CoverageMatrix::build(&tree);
+
+    assert_eq!(matrix.rows.len(), tree.packages.len());
+    assert!(matrix.missing(Manager::Dnf).contains(&"k9s"));
+    assert!(!matrix.missing(Manager::Pacman).contains(&"k9s"));
+
+    let git = matrix.row("git").expect("git should be in the matrix");
+    assert_eq!(git.platforms[&Manager::Apt].name, "git");
+    assert_eq!(git.platforms[&Manager::Apt].source, Source::Package);
+}
+
+#[test]
+fn test_group_coverage_reports_missing_packages() {
+    let tree = SourceTree::load(".").expect("Failed to load source tree");
+    let matrix = CoverageMatrix::build(&tree);
+    let devops = tree.group("devops").expect("devops group should exist");
+
+    let coverage = SetCoverage::for_group(devops, &matrix);
+    let dnf = coverage
+        .managers
+        .iter()
+        .find(|m| m.manager == Manager::Dnf)
+        .unwrap();
+
+    assert_eq!(dnf.missing, vec!["k9s".to_string()]);
+    assert!(coverage.summary().starts_with("devops group:"));
+    assert!(coverage.summary().contains("missing: helm, k9s"));
+}
+
+#[test]
+fn test_group_overrides_count_as_coverage() {
+    let mut tree = SourceTree::load(".").expect("Failed to load source tree");
+    let matrix = CoverageMatrix::build(&tree);
+    let group = tree.groups.iter_mut().find(|g| g.id == "devops").unwrap();
+    group
+        .platform_overrides
+        .entry("linux".to_string())
+        .or_default()
+        .packages
+        .push("k9s".to_string());
+
+    let coverage = SetCoverage::for_group(group, &matrix);
+    let dnf = coverage
+        .managers
+        .iter()
+        .find(|m| m.manager == Manager::Dnf)
+        .unwrap();
+    assert!(dnf.missing.is_empty());
+
+    // A group included by an ancestor also covers the profile
+    let minimal = tree
+        .profiles
+        .iter_mut()
+        .find(|p| p.id == "minimal")
+        .unwrap();
+    minimal.include_groups.push("devops".to_string());
+    let resolved = Resolver::from_tree(&tree)
+        .resolve_profile("devops")
+        .unwrap();
+    let groups = resolved.groups(&tree);
+    assert_eq!(groups.len(), 1);
+    let coverage = SetCoverage::for_profile(&resolved, &groups, &matrix);
+    let dnf = coverage
+        .managers
+        .iter()
+        .find(|m| m.manager == Manager::Dnf)
+        .unwrap();
+    assert!(!dnf.missing.contains(&"k9s".to_string()));
+}
+
+#[test]
+fn test_report_renders_all_formats() {
+    let tree = SourceTree::load(".").expect("Failed to load source tree");
+    let report = CoverageReport::build(&tree).expect("Profiles should resolve");
+
+    let markdown = report.to_markdown();
+    assert!(markdown.contains("## Missing Packages"));
+    assert!(markdown.contains("| devops |"));
+
+    let html = report.to_html();
+    assert!(html.contains("<table>"));
+    assert!(html.contains("class=\"missing\""));
+
+    let csv = report.matrix.to_csv();
+    assert!(csv.starts_with("package,apt,brew,dnf,pacman\n"));
+    assert!(csv.contains("\nk9s,,k9s,,k9s\n"));
+}
@@ -0,0 +1,87 @@
+//! Delta patch tests
+
+use heimdal_packages::database::{checksum, CompiledDatabase};
+use heimdal_packages::delta::{apply_patch, DeltaError, Op, Patch};
+use heimdal_packages::loader::SourceTree;
+
+fn compile(tree: &SourceTree) -> Vec<u8> {
+    CompiledDatabase::build(tree.packages.clone(), tree.groups.clone())
+        .to_bytes()
+        .expect("Failed to serialize database")
+}
+
+#[test]
+fn test_patch_reproduces_new_database() {
+    let mut tree = SourceTree::load(".").expect("Failed to load source tree");
+    let old = compile(&tree);
+
+    let neovim = tree
+        .packages
+        .iter_mut()
+        .find(|p| p.name == "neovim")
+        .unwrap();
+    neovim.popularity = 91;
+    neovim.tags.push("lua".to_string());
+    let new = compile(&tree);
+
+    let patch = Patch::diff(&old, &new);
+    assert_eq!(patch.old_sha256, checksum(&old));
+    assert_eq!(patch.new_sha256, checksum(&new));
+
+    let encoded = patch.to_bytes();
+    assert!(
+        encoded.len() < new.len() / 4,
+        "Patch ({} bytes) should be much smaller than the database ({} bytes)",
+        encoded.len(),
+        new.len()
+    );
+
+    let rebuilt = apply_patch(&old, &encoded).expect("Patch should apply");
+    assert_eq!(rebuilt, new);
+}
+
+#[test]
+fn test_patch_rejects_wrong_source() {
+    let old = b"the previous release of the package database".repeat(4);
+    let new = b"the current release of the package database".repeat(4);
+    let patch = Patch::diff(&old, &new);
+
+    let result = patch.apply(&new);
+    assert!(matches!(result, Err(DeltaError::SourceMismatch { .. })));
+}
+
+#[test]
+fn test_patch_detects_tampering() {
+    let old = b"the previous release of the package database".repeat(4);
+    let new = b"the current release of the package database".repeat(4);
+    let mut patch = Patch::diff(&old, &new);
+    patch.new_sha256 = checksum(b"something else");
+
+    let result = patch.apply(&old);
+    assert!(matches!(result, Err(DeltaError::ChecksumMismatch { .. })));
+    assert!(matches!(
+        apply_patch(&old, b"not a patch"),
+        Err(DeltaError::Corrupt(_))
+    ));
+}
+
+#[test]
+fn test_patch_rejects_oversized_targets() {
+    let old = b"the previous release of the package database".repeat(4);
+    let new = b"the current release of the package database".repeat(4);
+
+    let mut patch = Patch::diff(&old, &new);
+    patch.new_len = u64::MAX;
+    let error = patch.apply(&old).unwrap_err();
+    assert!(error.to_string().contains("exceeds the"));
+
+    // Repeated copies may not grow the output past the declared length
+    let mut patch = Patch::diff(&old, &new);
+    let copy = Op::Copy {
+        offset: 0,
+        len: old.len() as u64,
+    };
+    patch.ops = vec![copy; 1000];
+    let error = patch.apply(&old).unwrap_err();
+    assert!(error.to_string().contains("more than the"));
+}
@@ -0,0 +1,146 @@
+//! Dependency graph merge tests
+
+use heimdal_packages::database::CompiledDatabase;
+use heimdal_packages::graph::{Contradiction, DependencyGraph, DependencyKind, DependencySource};
+use heimdal_packages::loader::SourceTree;
+use heimdal_packages::model::{Dependencies, Package};
+use std::collections::BTreeMap;
+
+fn package(yaml: &str) -> Package {
+    serde_yaml::from_str(yaml).expect("Invalid package YAML")
+}
+
+fn dependencies(yaml: &str) -> BTreeMap<String, Dependencies> {
+    serde_yaml::from_str(yaml).expect("Invalid dependencies YAML")
+}
+
+const TOOL: &str = r#"
+name: tool
+description: A tool
+category: utilities
+popularity: 50
+platforms:
+  apt: tool
+dependencies:
+  required:
+    - package: git
+      reason: "Clones plugins"
+  optional:
+    - package: curl
+      reason: "Downloads updates"
+tags: [cli]
+"#;
+
+#[test]
+fn test_merges_sources_with_provenance() {
+    let files = dependencies(
+        r#"
+tool:
+  required:
+    - package: curl
+      reason: "Fetches its index"
+  optional:
+    - package: git
+      reason: "Nice to have"
+    - package: fzf
+      reason: "Fuzzy search"
+"#,
+    );
+    let graph = DependencyGraph::build(&[package(TOOL)], &files);
+
+    let edges = graph.dependencies("tool");
+    assert_eq!(
+        edges.iter().map(|e| e.package.as_str()).collect::<Vec<_>>(),
+        vec!["git", "curl", "fzf"]
+    );
+    assert_eq!(edges[0].kind, DependencyKind::Required);
+    assert_eq!(edges[1].kind, DependencyKind::Required);
+    assert_eq!(edges[2].kind, DependencyKind::Optional);
+    assert_eq!(
+        edges[1].declared_by(DependencySource::PackageFile),
+        Some(DependencyKind::Optional)
+    );
+    assert_eq!(edges[1].provenance[1].reason, "Fetches its index");
+
+    let merged = graph.merged("tool");
+    assert_eq!(merged.required[1].package, "curl");
+    assert_eq!(merged.required[1].reason, "Downloads updates");
+    assert_eq!(merged.optional[0].package, "fzf");
+}
+
+#[test]
+fn test_flags_required_against_optional_or_absent() {
+    let files = dependencies(
+        r#"
+tool:
+  optional:
+    - package: git
+      reason: "Nice to have"
+    - package: curl
+      reason: "Downloads updates"
+other:
+  required:
+    - package: tool
+      reason: "Wraps it"
+"#,
+    );
+    let graph = DependencyGraph::build(&[package(TOOL)], &files);
+
+    assert_eq!(
+        graph.contradictions(),
+        vec![
+            Contradiction {
+                package: "other".to_string(),
+                dependency: "tool".to_string(),
+                required_by: DependencySource::DependencyFile,
+                other: None,
+            },
+            Contradiction {
+                package: "tool".to_string(),
+                dependency: "git".to_string(),
+                required_by: DependencySource::PackageFile,
+                other: Some(DependencyKind::Optional),
+            },
+        ]
+    );
+    assert_eq!(
+        graph.contradictions()[1].to_string(),
+        "'tool' requires 'git' in its package file but dependencies/ lists it as optional"
+    );
+}
+
+#[test]
+fn test_repository_dependency_files_merge() {
+    let tree = SourceTree::load(".").expect("Failed to load source tree");
+    let graph = DependencyGraph::from_tree(&tree);
+
+    let npm = graph.dependencies("npm");
+    assert_eq!(npm[0].package, "node");
+    assert_eq!(npm[0].kind, DependencyKind::Required);
+    assert_eq!(
+        npm[0].declared_by(DependencySource::DependencyFile),
+        Some(DependencyKind::Required)
+    );
+    assert!(graph.contradictions().iter().any(|c| c.package == "neovim"
+        && c.dependency == "git"
+        && c.other == Some(DependencyKind::Optional)));
+}
+
+#[test]
+fn test_compiled_database_carries_graph() {
+    let tree = SourceTree::load(".").expect("Failed to load source tree");
+    let graph = DependencyGraph::from_tree(&tree);
+    let mut packages = tree.packages;
+    graph.apply(&mut packages);
+
+    let db = CompiledDatabase::build(packages, tree.groups).with_dependencies(&graph);
+    let db = CompiledDatabase::from_bytes(&db.to_bytes().unwrap()).unwrap();
+
+    let helm = db.package("helm").expect("helm is compiled");
+    assert_eq!(helm.dependencies.required[0].package, "kubectl");
+    assert_eq!(
+        db.dependencies("helm")[0].provenance[0].source,
+        DependencySource::DependencyFile
+    );
+    assert!(db.dependencies("nope").is_empty());
+}
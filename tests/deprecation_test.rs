@@ -0,0 +1,203 @@
+//! Package rename and deprecation tests
+
+use heimdal_packages::alias::{AliasCollision, AliasIndex, NameKind};
+use heimdal_packages::database::CompiledDatabase;
+use heimdal_packages::deprecation::{self, deprecated_uses, DeprecationError, Notice};
+use heimdal_packages::diff::{DatabaseDiff, Rename};
+use heimdal_packages::loader::SourceTree;
+use heimdal_packages::model::{Package, PackageGroup, Profile};
+
+fn package(name: &str, extra: &str) -> Package {
+    serde_yaml::from_str(&format!(
+        r#"
+name: {}
+description: Test package
+category: utilities
+popularity: 50
+platforms:
+  apt: {}
+tags: [test]
+{}
+"#,
+        name, name, extra
+    ))
+    .expect("Invalid package YAML")
+}
+
+fn database(packages: Vec<Package>) -> CompiledDatabase {
+    let aliases = AliasIndex::build(&packages, &[]);
+    assert!(aliases.collisions.is_empty(), "{:?}", aliases.collisions);
+    CompiledDatabase::build(packages, Vec::new()).with_aliases(&aliases)
+}
+
+#[test]
+fn test_lookup_follows_renames_and_replacements() {
+    let db = database(vec![
+        package("nodejs", "renamed_from: [node]"),
+        package("oldtool", "deprecated: true\nreplaced_by: newtool"),
+        package("newtool", "renamed_from: [newtool-legacy]"),
+        package("abandoned", "deprecated: true"),
+    ]);
+    let db = CompiledDatabase::from_bytes(&db.to_bytes().unwrap()).unwrap();
+
+    assert_eq!(db.index_by_alias["node"].kind, NameKind::Renamed);
+    let lookup = db.lookup("node").unwrap();
+    assert_eq!(lookup.package.name, "nodejs");
+    assert_eq!(
+        lookup.notices[0].to_string(),
+        "'node' was renamed to 'nodejs'"
+    );
+
+    let lookup = db.lookup("oldtool").unwrap();
+    assert_eq!(lookup.package.name, "newtool");
+    assert_eq!(
+        lookup.notices,
+        vec![Notice::Deprecated {
+            package: "oldtool".to_string(),
+            replaced_by: Some("newtool".to_string()),
+        }]
+    );
+
+    let lookup = db.lookup("abandoned").unwrap();
+    assert_eq!(lookup.package.name, "abandoned");
+    assert_eq!(lookup.notices[0].to_string(), "'abandoned' is deprecated");
+
+    assert!(db.lookup("nodejs").unwrap().notices.is_empty());
+    assert!(db.lookup("nope").is_none());
+}
+
+#[test]
+fn test_checks_replacements() {
+    let errors = deprecation::check(&[
+        package("a", "deprecated: true\nreplaced_by: b"),
+        package("b", "deprecated: true\nreplaced_by: a"),
+        package("c", "replaced_by: a"),
+        package("d", "deprecated: true\nreplaced_by: nope"),
+    ]);
+
+    assert_eq!(
+        errors,
+        vec![
+            DeprecationError::ReplacementCycle(vec![
+                "a".to_string(),
+                "b".to_string(),
+                "a".to_string()
+            ]),
+            DeprecationError::NotDeprecated {
+                package: "c".to_string(),
+                replaced_by: "a".to_string(),
+            },
+            DeprecationError::UnknownReplacement {
+                package: "d".to_string(),
+                replaced_by: "nope".to_string(),
+            },
+        ]
+    );
+
+    let index = AliasIndex::build(&[package("a", "renamed_from: [b]"), package("b", "")], &[]);
+    assert_eq!(
+        index.collisions,
+        vec![AliasCollision::PackageName {
+            alias: "b".to_string(),
+            canonical: "a".to_string(),
+        }]
+    );
+}
+
+#[test]
+fn test_reports_deprecated_packages_in_groups_and_profiles() {
+    let group: PackageGroup = serde_yaml::from_str(
+        r#"
+id: tools
+name: Tools
+description: Tools
+category: utilities
+packages:
+  required: [oldtool, newtool]
+"#,
+    )
+    .unwrap();
+    let profile: Profile = serde_yaml::from_str(
+        r#"
+id: dev
+name: Dev
+description: Dev
+type: developer
+packages:
+  other: [oldtool]
+"#,
+    )
+    .unwrap();
+    let tree = SourceTree {
+        packages: vec![
+            package("oldtool", "deprecated: true\nreplaced_by: newtool"),
+            package("newtool", ""),
+        ],
+        groups: vec![group],
+        profiles: vec![profile],
+        ..Default::default()
+    };
+
+    let uses: Vec<String> = deprecated_uses(&tree)
+        .iter()
+        .map(|u| u.to_string())
+        .collect();
+    assert_eq!(
+        uses,
+        vec![
+            "group tools uses deprecated package 'oldtool' (use 'newtool')",
+            "profile dev (other) uses deprecated package 'oldtool' (use 'newtool')",
+        ]
+    );
+}
+
+#[test]
+fn test_former_names_in_groups_resolve_and_are_reported() {
+    let group: PackageGroup = serde_yaml::from_str(
+        r#"
+id: web
+name: Web
+description: Web
+category: development
+packages:
+  required: [node]
+"#,
+    )
+    .unwrap();
+    let tree = SourceTree {
+        packages: vec![package("nodejs", "renamed_from: [node]")],
+        groups: vec![group],
+        ..Default::default()
+    };
+
+    assert_eq!(
+        tree.package("node").map(|p| p.name.as_str()),
+        Some("nodejs")
+    );
+    let uses = deprecated_uses(&tree);
+    assert_eq!(uses.len(), 1);
+    assert!(uses[0].renamed);
+    assert_eq!(uses[0].replaced_by.as_deref(), Some("nodejs"));
+    assert_eq!(
+        uses[0].to_string(),
+        "group web uses former package name 'node' (use 'nodejs')"
+    );
+}
+
+#[test]
+fn test_diff_reports_renamed_from_as_rename() {
+    let old = vec![package("node", "source: https://example.com/old")];
+    let new = vec![package(
+        "nodejs",
+        "renamed_from: [node]\nsource: https://example.com/new",
+    )];
+
+    let diff = DatabaseDiff::compute(&old, &[], &new, &[]);
+    assert_eq!(
+        diff.renamed,
+        vec![Rename {
+            from: "node".to_string(),
+            to: "nodejs".to_string(),
+        }]
+    );
+}
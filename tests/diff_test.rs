@@ -0,0 +1,143 @@
+//! Database diff and changelog generation tests
+
+use heimdal_packages::database::CompiledDatabase;
+use heimdal_packages::diff::{compiled_packages, Change, DatabaseDiff, Rename};
+use heimdal_packages::loader::SourceTree;
+use heimdal_packages::model::{Dependency, Package};
+
+#[test]
+fn test_identical_trees_have_no_changes() {
+    let tree = SourceTree::load(".").expect("Failed to load source tree");
+    let diff = DatabaseDiff::compute(&tree.packages, &tree.groups, &tree.packages, &tree.groups);
+
+    assert!(diff.is_empty());
+    assert!(diff
+        .to_markdown("1.0.0", "2026-01-01")
+        .contains("No package data changes."));
+}
+
+#[test]
+fn test_detects_package_and_group_changes() {
+    let old = SourceTree::load(".").expect("Failed to load source tree");
+    let mut new = SourceTree::load(".").expect("Failed to load source tree");
+
+    // Rename node -> nodejs, keeping its source URL
+    let node = new.packages.iter_mut().find(|p| p.name == "node").unwrap();
+    node.name = "nodejs".to_string();
+
+    // Change a platform name and add a dependency
+    let fd = new.packages.iter_mut().find(|p| p.name == "fd").unwrap();
+    fd.platforms.apt = Some("fdfind".to_string());
+    fd.dependencies.optional.push(Dependency {
+        package: "bat".to_string(),
+        reason: "Preview files".to_string(),
+    });
+
+    // Drop a package entirely and move a group member
+    new.packages.retain(|p| p.name != "pandoc");
+    let devops = new.groups.iter_mut().find(|g| g.id == "devops").unwrap();
+    devops.packages.optional.retain(|p| p != "k9s");
+
+    let diff = DatabaseDiff::compute(&old.packages, &old.groups, &new.packages, &new.groups);
+
+    assert_eq!(
+        diff.renamed,
+        vec![Rename {
+            from: "node".to_string(),
+            to: "nodejs".to_string()
+        }]
+    );
+    assert!(diff.added.is_empty());
+    assert_eq!(diff.removed, vec!["pandoc".to_string()]);
+
+    let change = &diff.platform_changes[0];
+    assert_eq!(change.package, "fd");
+    assert_eq!(change.platform, "apt");
+    assert_eq!(change.new.as_deref(), Some("fdfind"));
+
+    assert_eq!(diff.dependency_changes.len(), 1);
+    assert_eq!(diff.dependency_changes[0].dependency, "bat");
+    assert_eq!(diff.dependency_changes[0].change, Change::Added);
+
+    assert_eq!(diff.membership_changes.len(), 1);
+    assert_eq!(diff.membership_changes[0].package, "k9s");
+    assert_eq!(diff.membership_changes[0].change, Change::Removed);
+
+    let markdown = diff.to_markdown("1.1.0", "2026-03-01");
+    assert!(markdown.starts_with("## [1.1.0] - 2026-03-01\n"));
+    assert!(markdown.contains("### Changed\n- Renamed package `node` to `nodejs`"));
+    assert!(markdown.contains("### Removed\n- Package `pandoc`"));
+
+    let json = serde_json::to_value(&diff).expect("Diff should serialize to JSON");
+    assert_eq!(json["renamed"][0]["to"], "nodejs");
+    assert_eq!(json["membership_changes"][0]["change"], "removed");
+}
+
+#[test]
+fn test_detects_apk_name_changes() {
+    let old = SourceTree::load(".").expect("Failed to load source tree");
+    let mut new = SourceTree::load(".").expect("Failed to load source tree");
+    let jq = new.packages.iter_mut().find(|p| p.name == "jq").unwrap();
+    jq.platforms.apk = Some("jq-static".to_string());
+
+    let diff = DatabaseDiff::compute(&old.packages, &old.groups, &new.packages, &new.groups);
+    assert_eq!(diff.platform_changes.len(), 1);
+    assert_eq!(diff.platform_changes[0].platform, "apk");
+    assert!(diff
+        .to_markdown("1.1.0", "2026-03-01")
+        .contains("- `jq` apk name changed from `jq` to `jq-static`"));
+
+    // Without a source URL, a rename needs every platform name, apk included,
+    // to match
+    let package = |name: &str, apk: &str| -> Package {
+        serde_yaml::from_str(&format!(
+            "name: {}\ndescription: x\ncategory: x\npopularity: 1\nplatforms:\n  apt: tool\n  apk: {}\ntags: []",
+            name, apk
+        ))
+        .unwrap()
+    };
+    let renamed = DatabaseDiff::compute(&[package("a", "tool")], &[], &[package("b", "tool")], &[]);
+    assert_eq!(renamed.renamed.len(), 1);
+    let replaced =
+        DatabaseDiff::compute(&[package("a", "tool")], &[], &[package("b", "other")], &[]);
+    assert!(replaced.renamed.is_empty());
+    assert_eq!(replaced.added, vec!["b".to_string()]);
+}
+
+#[test]
+fn test_compiled_database_round_trip() {
+    let tree = SourceTree::load(".").expect("Failed to load source tree");
+    let db = CompiledDatabase::build(tree.packages.clone(), tree.groups.clone());
+
+    let bytes = db.to_bytes().expect("Failed to serialize database");
+    let decoded = CompiledDatabase::from_bytes(&bytes).expect("Failed to deserialize database");
+
+    assert_eq!(decoded.packages, tree.packages);
+    assert_eq!(decoded.package("git").map(|p| p.name.as_str()), Some("git"));
+}
+
+#[test]
+fn test_git_trees_are_compared_as_compiled() {
+    let mut tree = SourceTree::load(".").expect("Failed to load source tree");
+    // A package relying on its mapping for one platform name
+    let fd = tree.packages.iter_mut().find(|p| p.name == "fd").unwrap();
+    fd.platforms.apt = None;
+
+    let packages = compiled_packages(&tree);
+    let fd = packages.iter().find(|p| p.name == "fd").unwrap();
+    assert_eq!(fd.platforms.apt.as_deref(), Some("fd-find"));
+    let node = packages.iter().find(|p| p.name == "node").unwrap();
+    assert!(node
+        .dependencies
+        .optional
+        .iter()
+        .any(|d| d.package == "yarn"));
+
+    // Comparing the raw tree against its own compiled database would report
+    // the merged names and dependencies as changes
+    let db = CompiledDatabase::build(packages.clone(), tree.groups.clone());
+    let raw = DatabaseDiff::compute(&db.packages, &db.groups, &tree.packages, &tree.groups);
+    assert!(raw.platform_changes.iter().any(|c| c.package == "fd"));
+    let compiled = DatabaseDiff::compute(&db.packages, &db.groups, &packages, &tree.groups);
+    assert!(compiled.is_empty());
+}
@@ -0,0 +1,93 @@
+//! Dotfile validation tests
+
+use heimdal_packages::dotfiles::path_errors;
+use heimdal_packages::loader::SourceTree;
+use heimdal_packages::model::{Dotfile, Profile};
+use heimdal_packages::resolve::Resolver;
+
+fn dotfile(source: &str, target: &str) -> Dotfile {
+    Dotfile {
+        source: source.to_string(),
+        target: target.to_string(),
+        package: None,
+    }
+}
+
+fn profile(yaml: &str) -> Profile {
+    serde_yaml::from_str(yaml).expect("Failed to parse test profile")
+}
+
+#[test]
+fn test_dotfile_paths() {
+    assert!(path_errors(&dotfile(".config/nvim", "~/.config/nvim")).is_empty());
+
+    assert_eq!(path_errors(&dotfile("/etc/profile", "~/.profile")).len(), 1);
+    assert_eq!(path_errors(&dotfile("~/.bashrc", "~/.bashrc")).len(), 1);
+    assert_eq!(path_errors(&dotfile("../secrets", "~/.secrets")).len(), 1);
+    assert_eq!(
+        path_errors(&dotfile(".bashrc", "/etc/bash.bashrc")).len(),
+        1
+    );
+    assert_eq!(path_errors(&dotfile(".bashrc", "~")).len(), 1);
+    assert_eq!(
+        path_errors(&dotfile(".ssh/config", "~/../root/.ssh/config")),
+        vec!["target '~/../root/.ssh/config' contains '..' and could escape the home directory"]
+    );
+}
+
+#[test]
+fn test_repository_dotfiles_name_installed_packages() {
+    let tree = SourceTree::load(".").expect("Failed to load source tree");
+    let resolver = Resolver::from_tree(&tree);
+
+    for profile in &tree.profiles {
+        let resolved = resolver.resolve_profile(&profile.id).unwrap();
+        assert!(resolved.dotfile_conflicts.is_empty());
+        for dotfile in &profile.dotfiles {
+            assert!(path_errors(dotfile).is_empty());
+            let package = dotfile.package.as_deref().expect("dotfile names a package");
+            assert!(
+                resolved.package(package).is_some(),
+                "Profile '{}' does not install '{}'",
+                profile.id,
+                package
+            );
+        }
+    }
+}
+
+#[test]
+fn test_detects_target_collisions() {
+    let profiles = vec![
+        profile("{id: a, name: AAA, description: First profile, type: custom, dotfiles: [{source: .config/nvim, target: ~/.config/nvim}, {source: .gitconfig, target: ~/.gitconfig}]}"),
+        profile("{id: b, name: BBB, description: Second profile, type: custom, dotfiles: [{source: lazyvim, target: ~/.config/nvim}, {source: .gitconfig, target: ~/.gitconfig}]}"),
+        profile("{id: c, name: CCC, description: Third profile, type: custom, dotfiles: [{source: .config, target: ~/.config}]}"),
+        profile("{id: ab, name: ABAB, description: Both profiles, type: custom, extends: [a, b]}"),
+        profile("{id: ac, name: ACAC, description: Nested targets, type: custom, extends: [a, c]}"),
+        profile("{id: fixed, name: Fixed, description: Overrides the collision, type: custom, extends: [a, b], dotfiles: [{source: mine/nvim, target: ~/.config/nvim}]}"),
+    ];
+    let resolver = Resolver::new(&profiles, &[]);
+
+    let ab = resolver.resolve_profile("ab").unwrap();
+    assert_eq!(ab.dotfile_conflicts.len(), 1);
+    assert_eq!(
+        ab.dotfile_conflicts[0].to_string(),
+        "dotfile ~/.config/nvim (lazyvim, profile b) collides with ~/.config/nvim (.config/nvim, profile a)"
+    );
+
+    let ac = resolver.resolve_profile("ac").unwrap();
+    assert_eq!(ac.dotfile_conflicts.len(), 1);
+    assert!(ac.dotfile_conflicts[0].to_string().contains("overlaps"));
+
+    let fixed = resolver.resolve_profile("fixed").unwrap();
+    assert!(fixed.dotfile_conflicts.is_empty());
+    let nvim = fixed
+        .dotfiles
+        .iter()
+        .find(|d| d.target == "~/.config/nvim")
+        .unwrap();
+    assert_eq!(
+        (nvim.source.as_str(), nvim.profile.as_str()),
+        ("mine/nvim", "fixed")
+    );
+}
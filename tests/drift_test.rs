@@ -0,0 +1,134 @@
+//! Drift report tests
+
+use heimdal_packages::alias::AliasIndex;
+use heimdal_packages::choice::Choices;
+use heimdal_packages::drift::{AliasedInstall, DriftError, DriftReport};
+use heimdal_packages::export::Selection;
+use heimdal_packages::installed::{self, Installed};
+use heimdal_packages::loader::SourceTree;
+use heimdal_packages::model::Manager;
+use heimdal_packages::resolve::ResolveError;
+
+fn load() -> SourceTree {
+    SourceTree::load(".").expect("Failed to load source tree")
+}
+
+fn brew(tree: &SourceTree, names: &[&str]) -> Installed {
+    let natives = installed::parse(Manager::Brew, &names.join("\n"));
+    Installed::resolve(&AliasIndex::from_tree(tree), Manager::Brew, natives)
+}
+
+#[test]
+fn test_reports_drift_from_a_profile() {
+    let tree = load();
+    let natives = installed::read(Manager::Apt, "tests/fixtures/installed/dpkg-status")
+        .expect("Failed to read fixture");
+    let installed = Installed::resolve(&AliasIndex::from_tree(&tree), Manager::Apt, natives);
+
+    let report = DriftReport::compute(
+        &tree,
+        &Selection::Profile("developer".to_string()),
+        &Choices::new(),
+        &installed,
+    )
+    .unwrap();
+
+    assert_eq!(report.selection, "profile developer");
+    assert!(report.missing_required.contains(&"make".to_string()));
+    assert!(!report.missing_required.contains(&"git".to_string()));
+    assert!(!report.missing_required.contains(&"fd".to_string()));
+    assert_eq!(
+        report.aliased,
+        vec![AliasedInstall {
+            package: "docker".to_string(),
+            native: "docker-ce".to_string(),
+        }]
+    );
+    assert_eq!(report.unknown, vec!["libc6"]);
+    assert!(!report.is_clean());
+
+    let text = report.to_text();
+    assert!(text.starts_with("Drift from profile developer (apt):"));
+    assert!(text.contains("  docker (as docker-ce)\n"));
+    assert!(text.contains("1 installed package is not in the database"));
+}
+
+#[test]
+fn test_reports_group_optional_and_extra_packages() {
+    let mut tree = load();
+    let installed = brew(
+        &tree,
+        &["git", "neovim", "tmux", "fzf", "ripgrep", "bat", "jq"],
+    );
+    let selection = Selection::Group("terminal-power-user".to_string());
+
+    let report = DriftReport::compute(&tree, &selection, &Choices::new(), &installed).unwrap();
+    assert!(report.missing_required.is_empty());
+    assert_eq!(
+        report.missing_optional,
+        vec!["fd", "htop", "tree", "starship", "delta"]
+    );
+    assert_eq!(report.extra, vec!["git", "neovim"]);
+
+    // git is a dependency of neovim, so offering neovim covers both
+    let group = tree
+        .groups
+        .iter_mut()
+        .find(|g| g.id == "terminal-power-user")
+        .unwrap();
+    group.packages.optional.push("neovim".to_string());
+    let report = DriftReport::compute(&tree, &selection, &Choices::new(), &installed).unwrap();
+    assert!(report.extra.is_empty());
+    assert!(report.is_clean());
+}
+
+#[test]
+fn test_any_option_satisfies_a_choice_slot() {
+    let mut tree = load();
+    let group = tree.groups.iter_mut().find(|g| g.id == "minimal").unwrap();
+    group.choices =
+        vec![serde_yaml::from_str("id: editor\noptions: [helix, emacs]\ndefault: helix").unwrap()];
+    let selection = Selection::Group("minimal".to_string());
+
+    let installed = brew(&tree, &["git", "curl", "wget", "emacs"]);
+    let report = DriftReport::compute(&tree, &selection, &Choices::new(), &installed).unwrap();
+    assert!(report.missing_required.is_empty());
+    assert!(report.extra.is_empty());
+
+    let installed = brew(&tree, &["git", "curl", "wget"]);
+    let report = DriftReport::compute(&tree, &selection, &Choices::new(), &installed).unwrap();
+    assert_eq!(report.missing_required, vec!["helix"]);
+
+    let shell: Choices = [("shell".to_string(), "zsh".to_string())].into();
+    assert_eq!(
+        DriftReport::compute(&tree, &selection, &shell, &installed),
+        Err(DriftError::Resolve(ResolveError::UnknownSlot(
+            "shell".to_string()
+        )))
+    );
+}
+
+#[test]
+fn test_packages_unavailable_on_the_manager_are_not_missing() {
+    let mut tree = load();
+    for package in tree.packages.iter_mut().filter(|p| p.name == "htop") {
+        package.platforms.brew = None;
+    }
+    for mapping in tree.mappings.iter_mut().filter(|m| m.canonical == "htop") {
+        mapping.platforms.brew = None;
+    }
+    let installed = brew(&tree, &["tmux", "fzf", "ripgrep", "bat", "jq"]);
+
+    let report = DriftReport::compute(
+        &tree,
+        &Selection::Group("terminal-power-user".to_string()),
+        &Choices::new(),
+        &installed,
+    )
+    .unwrap();
+    assert_eq!(report.unavailable, vec!["htop"]);
+    assert!(!report.missing_optional.contains(&"htop".to_string()));
+    assert!(report
+        .to_text()
+        .contains("\nNot available on brew (1):\n  htop\n"));
+}
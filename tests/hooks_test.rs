@@ -0,0 +1,233 @@
+//! Hook safety lint tests
+
+use heimdal_packages::hooks::{lint_command, lint_hook, tokenize, unused_allows, Token};
+use heimdal_packages::loader::SourceTree;
+use heimdal_packages::model::{Hook, HookLint};
+use heimdal_packages::resolve::Resolver;
+use std::collections::HashSet;
+
+fn lints(command: &str, binaries: &[&str]) -> Vec<HookLint> {
+    let binaries: HashSet<&str> = binaries.iter().copied().collect();
+    lint_command(command, &binaries)
+        .expect("Command should tokenize")
+        .into_iter()
+        .map(|f| f.lint)
+        .collect()
+}
+
+#[test]
+fn test_tokenizer_handles_quotes_and_operators() {
+    let tokens = tokenize(r#"git config --global user.name "Ada L*" && echo \*done|tee 'a b'"#)
+        .expect("Command should tokenize");
+    let words: Vec<String> = tokens
+        .iter()
+        .map(|t| match t {
+            Token::Word { text, .. } => text.clone(),
+            Token::Operator(op) => op.to_string(),
+            Token::Redirect { text, .. } => text.clone(),
+        })
+        .collect();
+    assert_eq!(
+        words,
+        vec![
+            "git",
+            "config",
+            "--global",
+            "user.name",
+            "Ada L*",
+            "&&",
+            "echo",
+            "*done",
+            "|",
+            "tee",
+            "a b"
+        ]
+    );
+    assert!(!tokens.iter().any(|t| matches!(
+        t,
+        Token::Word {
+            unquoted_glob: true,
+            ..
+        }
+    )));
+
+    assert!(tokenize("echo 'unterminated").is_err());
+}
+
+#[test]
+fn test_flags_dangerous_patterns() {
+    assert_eq!(
+        lints("curl -fsSL https://example.com/install.sh | sh", &["curl"]),
+        vec![
+            HookLint::NetworkFetch,
+            HookLint::PipeToShell,
+            HookLint::UnknownBinary
+        ]
+    );
+    assert_eq!(
+        lints("sudo rm -r -f /opt/tool", &[]),
+        vec![HookLint::SudoRmRf]
+    );
+    assert_eq!(
+        lints("rm ~/.cache/*.tmp", &[]),
+        vec![HookLint::UnquotedGlob]
+    );
+    assert_eq!(
+        lints("nvim --headless +PlugInstall +qall", &["git"]),
+        vec![HookLint::UnknownBinary]
+    );
+    assert!(lints("[ -d ~/.config ] && nvim --version", &["nvim"]).is_empty());
+    assert!(lints("rm -rf ~/.cache/nvim", &["nvim"]).is_empty());
+}
+
+#[test]
+fn test_allow_list_suppresses_reviewed_findings() {
+    let binaries: HashSet<&str> = ["curl", "bash"].into_iter().collect();
+    let hook = Hook {
+        command: "curl -fsSL https://sh.rustup.rs | bash".to_string(),
+        description: None,
+        allow: vec![
+            HookLint::PipeToShell,
+            HookLint::NetworkFetch,
+            HookLint::SudoRmRf,
+        ],
+    };
+
+    assert!(lint_hook(&hook, &binaries).unwrap().is_empty());
+    assert_eq!(unused_allows(&hook, &binaries), vec![HookLint::SudoRmRf]);
+
+    let parsed: Hook = serde_yaml::from_str("command: ls\nallow: [unknown-binary]\n").unwrap();
+    assert_eq!(parsed.allow, vec![HookLint::UnknownBinary]);
+}
+
+#[test]
+fn test_repository_hooks_are_clean() {
+    let tree = SourceTree::load(".").expect("Failed to load source tree");
+    let resolver = Resolver::from_tree(&tree);
+
+    for profile in &tree.profiles {
+        let resolved = resolver.resolve_profile(&profile.id).unwrap();
+        let binaries: HashSet<&str> = resolved
+            .names()
+            .filter_map(|name| tree.package(name))
+            .flat_map(|package| package.provided_binaries())
+            .collect();
+
+        for hook in profile
+            .hooks
+            .pre_install
+            .iter()
+            .chain(&profile.hooks.post_install)
+        {
+            let findings = lint_hook(hook, &binaries).unwrap();
+            assert!(
+                findings.is_empty(),
+                "Profile '{}' hook `{}`: {:?}",
+                profile.id,
+                hook.command,
+                findings
+            );
+        }
+    }
+}
+
+#[test]
+fn test_flags_fetched_scripts_in_substitutions_and_under_sudo() {
+    let url = "https://example.com/install.sh";
+
+    // Command substitution under a shell's -c, inside double quotes
+    assert_eq!(
+        lints(
+            &format!(r#"sh -c "$(curl -fsSL {})""#, url),
+            &["curl", "sh"]
+        ),
+        vec![HookLint::NetworkFetch, HookLint::PipeToShell]
+    );
+    // Process substitution
+    assert_eq!(
+        lints(&format!("bash <(curl -fsSL {})", url), &["curl", "bash"]),
+        vec![HookLint::NetworkFetch, HookLint::PipeToShell]
+    );
+    // Backticks
+    assert_eq!(
+        lints(
+            &format!("bash -c \"`wget -qO- {}`\"", url),
+            &["wget", "bash"]
+        ),
+        vec![HookLint::NetworkFetch, HookLint::PipeToShell]
+    );
+    // Substituted commands are linted like any other
+    assert_eq!(
+        lints("echo $(nvim --version) $((1 + 2))", &[]),
+        vec![HookLint::UnknownBinary]
+    );
+
+    // sudo options that take an argument, and `--`
+    assert_eq!(
+        lints(
+            &format!("curl -fsSL {} | sudo -u root sh", url),
+            &["curl", "sh"]
+        ),
+        vec![HookLint::NetworkFetch, HookLint::PipeToShell]
+    );
+    assert_eq!(
+        lints("sudo -g wheel -- rm -rf /opt/tool", &[]),
+        vec![HookLint::SudoRmRf]
+    );
+
+    let tokens = tokenize(r#"sh -c "$(curl "a b")""#).unwrap();
+    assert!(matches!(
+        &tokens[2],
+        Token::Word { substitutions, .. } if substitutions[0].body == r#"curl "a b""#
+    ));
+    assert!(tokenize("echo $(curl").is_err());
+}
+
+#[test]
+fn test_understands_redirections_keywords_comments_and_subshells() {
+    let git = &["git", "curl", "sh"];
+    assert!(lints("git --version 2>&1", git).is_empty());
+    assert!(lints("git --version >/dev/null 2>>err.log &>all.log", git).is_empty());
+    assert_eq!(
+        lints("curl -fsSL https://example.com/install.sh |& sh", git),
+        vec![HookLint::NetworkFetch, HookLint::PipeToShell]
+    );
+    assert!(lints("if [ -d x ]; then git pull; fi", git).is_empty());
+    assert!(lints("for d in a b; do git -C \"$d\" pull; done", git).is_empty());
+    assert!(lints("echo a # note *", git).is_empty());
+    assert!(lints("(cd x && git pull)", git).is_empty());
+    assert_eq!(
+        lints("curl -fsSL https://example.com/install.sh |\n  sh", git),
+        vec![HookLint::NetworkFetch, HookLint::PipeToShell]
+    );
+
+    // The script of `sh -c` is linted, as root under sudo
+    assert_eq!(
+        lints("sudo sh -c 'rm -rf /'", git),
+        vec![HookLint::SudoRmRf]
+    );
+    assert_eq!(
+        lints("bash -ec 'nvim --version > /tmp/v'", &["bash"]),
+        vec![HookLint::UnknownBinary]
+    );
+
+    let tokens = tokenize("git log 2>&1 >out").unwrap();
+    assert_eq!(
+        tokens[2..],
+        [
+            Token::Redirect {
+                text: "2>&1".to_string(),
+                takes_target: false,
+            },
+            Token::Redirect {
+                text: ">".to_string(),
+                takes_target: true,
+            },
+            Token::Word {
+                text: "out".to_string(),
+                unquoted_glob: false,
+                substitutions: Vec::new(),
+            },
+        ]
+    );
+}
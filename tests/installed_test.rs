@@ -0,0 +1,123 @@
+//! Installed package detection tests
+
+use heimdal_packages::alias::{AliasIndex, NameKind};
+use heimdal_packages::installed::{self, Installed, NativePackage};
+use heimdal_packages::loader::SourceTree;
+use heimdal_packages::model::Manager;
+
+const FIXTURES: &str = "tests/fixtures/installed";
+
+fn detect(manager: Manager, fixture: &str) -> Installed {
+    let tree = SourceTree::load(".").expect("Failed to load source tree");
+    let natives = installed::read(manager, format!("{}/{}", FIXTURES, fixture))
+        .expect("Failed to read fixture");
+    Installed::resolve(&AliasIndex::from_tree(&tree), manager, natives)
+}
+
+fn summary(installed: &Installed) -> Vec<(&str, &str)> {
+    installed
+        .packages
+        .iter()
+        .map(|p| (p.package.as_str(), p.native.as_str()))
+        .collect()
+}
+
+#[test]
+fn test_detects_dpkg_status() {
+    let installed = detect(Manager::Apt, "dpkg-status");
+
+    assert_eq!(
+        summary(&installed),
+        vec![
+            ("git", "git"),
+            ("fd", "fd-find"),
+            ("docker", "docker-ce"),
+            ("pip", "python3-pip"),
+        ]
+    );
+    let git = installed.package("git").unwrap();
+    assert_eq!(git.version.as_deref(), Some("1:2.43.0-1ubuntu7"));
+    assert_eq!(git.kind, NameKind::Canonical);
+    assert_eq!(installed.package("docker").unwrap().kind, NameKind::Alias);
+    assert_eq!(
+        installed.package("fd").unwrap().kind,
+        NameKind::Platform(vec![Manager::Apt])
+    );
+
+    // Removed packages are skipped; multi-arch ones are listed once
+    assert!(installed.package("vim").is_none());
+    assert_eq!(
+        installed.unknown,
+        vec![NativePackage {
+            name: "libc6".to_string(),
+            version: Some("2.39-0ubuntu8".to_string()),
+        }]
+    );
+}
+
+#[test]
+fn test_detects_pacman_local_db() {
+    let installed = detect(Manager::Pacman, "pacman-local");
+
+    assert_eq!(
+        summary(&installed),
+        vec![("fd", "fd"), ("git", "git"), ("pip", "python-pip")]
+    );
+    assert_eq!(
+        installed.package("git").unwrap().version.as_deref(),
+        Some("2.43.0-1")
+    );
+    assert_eq!(installed.unknown[0].name, "glibc");
+
+    let natives = installed::parse(Manager::Pacman, "git 2.43.0-1\npython-pip 23.3.1-1\n");
+    assert_eq!(natives[1].name, "python-pip");
+}
+
+#[test]
+fn test_detects_rpm_query_output() {
+    let installed = detect(Manager::Dnf, "rpm-qa.txt");
+
+    assert_eq!(
+        summary(&installed),
+        vec![("git", "git"), ("go", "golang"), ("fd", "fd-find")]
+    );
+    assert_eq!(
+        installed.package("fd").unwrap().version.as_deref(),
+        Some("9.0.0-2.fc39")
+    );
+    let unknown: Vec<_> = installed.unknown.iter().map(|p| p.name.as_str()).collect();
+    assert_eq!(unknown, vec!["glibc", "gpg-pubkey"]);
+
+    // --queryformat '%{NAME} %{VERSION}-%{RELEASE}\n'
+    assert_eq!(
+        installed::parse(Manager::Dnf, "golang 1.21.6-1.fc39\n"),
+        vec![NativePackage {
+            name: "golang".to_string(),
+            version: Some("1.21.6-1.fc39".to_string()),
+        }]
+    );
+}
+
+#[test]
+fn test_detects_brew_list_versions() {
+    let installed = detect(Manager::Brew, "brew-list.txt");
+
+    assert_eq!(
+        summary(&installed),
+        vec![
+            ("git", "git"),
+            ("go", "go"),
+            ("neovim", "neovim"),
+            ("ripgrep", "ripgrep"),
+        ]
+    );
+    // With several versions installed, the highest is reported
+    assert_eq!(
+        installed.package("neovim").unwrap().version.as_deref(),
+        Some("0.9.5")
+    );
+    let go = installed::parse(Manager::Brew, "go 1.21.5 1.9.7\n");
+    assert_eq!(go[0].version.as_deref(), Some("1.21.5"));
+    assert_eq!(installed.unknown[0].name, "openssl@3");
+    assert_eq!(installed.names().count(), 4);
+}
@@ -100,11 +100,11 @@ fn test_all_packages_have_files() {
 
         // Verify file is readable
         let content = fs::read_to_string(entry.path())
-            .expect(&format!("Failed to read {}", entry.path().display()));
+            .unwrap_or_else(|_| panic!("Failed to read {}", entry.path().display()));
 
         // Verify it's valid YAML
         let _: serde_yaml::Value = serde_yaml::from_str(&content)
-            .expect(&format!("Invalid YAML in {}", entry.path().display()));
+            .unwrap_or_else(|_| panic!("Invalid YAML in {}", entry.path().display()));
     }
 
     assert!(
@@ -158,8 +158,9 @@ fn test_required_schemas_exist() {
         );
 
         // Verify it's valid JSON
-        let content = fs::read_to_string(path).expect(&format!("Failed to read {}", schema));
+        let content =
+            fs::read_to_string(path).unwrap_or_else(|_| panic!("Failed to read {}", schema));
         let _: serde_json::Value =
-            serde_json::from_str(&content).expect(&format!("Invalid JSON in {}", schema));
+            serde_json::from_str(&content).unwrap_or_else(|_| panic!("Invalid JSON in {}", schema));
     }
 }
@@ -0,0 +1,119 @@
+//! Maintainer and verification date tests
+
+use chrono::NaiveDate;
+use heimdal_packages::database::CompiledDatabase;
+use heimdal_packages::maintenance::{self, MaintenanceError, MaintenanceReport};
+use heimdal_packages::model::Package;
+
+fn package(name: &str, extra: &str) -> Package {
+    serde_yaml::from_str(&format!(
+        r#"
+name: {}
+description: Test package
+category: utilities
+popularity: 50
+platforms:
+  apt: {}
+tags: [test]
+{}
+"#,
+        name, name, extra
+    ))
+    .expect("Invalid package YAML")
+}
+
+fn date(s: &str) -> NaiveDate {
+    s.parse().unwrap()
+}
+
+#[test]
+fn test_checks_maintenance_fields() {
+    let today = date("2026-10-18");
+    let packages = vec![
+        package(
+            "a",
+            "maintainers: [octocat, bad--name, -dash, Octocat]\nadded_in: 1\nlast_verified: 2026-01-05",
+        ),
+        package("b", "added_in: 99\nlast_verified: 2027-01-01"),
+        package("c", ""),
+    ];
+
+    let errors = maintenance::check(&packages, today);
+    assert_eq!(
+        errors,
+        vec![
+            MaintenanceError::InvalidHandle {
+                package: "a".to_string(),
+                handle: "bad--name".to_string(),
+            },
+            MaintenanceError::InvalidHandle {
+                package: "a".to_string(),
+                handle: "-dash".to_string(),
+            },
+            MaintenanceError::DuplicateMaintainer {
+                package: "a".to_string(),
+                handle: "Octocat".to_string(),
+            },
+            MaintenanceError::FutureVerification {
+                package: "b".to_string(),
+                date: date("2027-01-01"),
+            },
+        ]
+    );
+    assert_eq!(
+        errors[3].to_string(),
+        "'b' was last verified in the future (2027-01-01)"
+    );
+    assert!(serde_yaml::from_str::<Package>(
+        "name: x\ndescription: x\ncategory: x\npopularity: 1\nplatforms: {}\ntags: []\nlast_verified: yesterday"
+    )
+    .is_err());
+}
+
+#[test]
+fn test_reports_stale_entries_against_a_threshold() {
+    let today = date("2026-10-18");
+    let packages = vec![
+        package("fresh", "last_verified: 2026-09-01"),
+        package("old", "last_verified: 2025-06-01"),
+        package("older", "last_verified: 2024-10-18"),
+        package("never", ""),
+    ];
+
+    let stale = maintenance::stale(&packages, today, 365);
+    let names: Vec<_> = stale.iter().map(|s| s.package.as_str()).collect();
+    assert_eq!(names, vec!["older", "old"]);
+    assert_eq!(stale[0].days, 730);
+    assert_eq!(
+        stale[1].to_string(),
+        "'old' was last verified 504 days ago (2025-06-01)"
+    );
+
+    assert_eq!(maintenance::stale(&packages, today, 30).len(), 3);
+    assert!(maintenance::stale(&packages, today, 1000).is_empty());
+}
+
+#[test]
+fn test_summarises_maintenance_and_keeps_fields_in_database() {
+    let packages = vec![
+        package(
+            "kept",
+            "maintainers: [octocat]\nadded_in: 2\nlast_verified: 2026-10-01",
+        ),
+        package("orphan", "last_verified: 2023-01-01"),
+        package("unknown", ""),
+    ];
+
+    let report = MaintenanceReport::compute(&packages, date("2026-10-18"), 365);
+    assert_eq!(report.packages, 3);
+    assert_eq!(report.unmaintained, vec!["orphan", "unknown"]);
+    assert_eq!(report.unverified, vec!["unknown"]);
+    assert_eq!(report.stale[0].package, "orphan");
+
+    let db = CompiledDatabase::build(packages, Vec::new());
+    let db = CompiledDatabase::from_bytes(&db.to_bytes().unwrap()).unwrap();
+    let kept = db.package("kept").unwrap();
+    assert_eq!(kept.maintainers, vec!["octocat"]);
+    assert_eq!(kept.added_in, Some(2));
+    assert_eq!(kept.last_verified, Some(date("2026-10-01")));
+}
@@ -0,0 +1,113 @@
+//! Popularity recomputation tests
+
+use heimdal_packages::alias::AliasIndex;
+use heimdal_packages::loader::SourceTree;
+use heimdal_packages::popularity::{self, Counts, PopularityReport, Score, Source};
+use std::collections::BTreeMap;
+use std::fs;
+
+const FIXTURES: &str = "tests/fixtures/popularity";
+
+fn fixture(name: &str) -> String {
+    fs::read_to_string(format!("{}/{}", FIXTURES, name)).expect("Failed to read fixture")
+}
+
+fn score(package: &str, current: u8, computed: u8) -> Score {
+    Score {
+        package: package.to_string(),
+        current,
+        computed,
+        sources: BTreeMap::new(),
+    }
+}
+
+#[test]
+fn test_parses_usage_exports() {
+    let popcon = popularity::parse_popcon(&fixture("popcon-by_inst.txt"));
+    assert_eq!(popcon.len(), 6);
+    assert_eq!(popcon[3], ("git".to_string(), 151205));
+
+    let brew = popularity::parse_brew_analytics(&fixture("brew-install-365d.json")).unwrap();
+    assert_eq!(brew[0], ("git".to_string(), 512304));
+    assert_eq!(brew[4], ("helix".to_string(), 9120));
+    assert!(popularity::parse_brew_analytics(
+        r#"{"items": [{"formula": "git", "count": "many"}]}"#
+    )
+    .is_err());
+
+    let stars = popularity::parse_stars_csv(&fixture("github-stars.csv"));
+    assert_eq!(stars[0], ("git/git".to_string(), 53120));
+    assert_eq!(stars.len(), 5);
+}
+
+#[test]
+fn test_normalizes_counts_on_a_log_scale() {
+    assert_eq!(popularity::normalize(500, 500), 100.0);
+    assert_eq!(popularity::normalize(0, 500), 0.0);
+    assert_eq!(popularity::normalize(9, 99), 50.0);
+    assert_eq!(popularity::normalize(3, 0), 0.0);
+}
+
+#[test]
+fn test_scores_the_repository_from_usage_data() {
+    let tree = SourceTree::load(".").expect("Failed to load source tree");
+    let index = AliasIndex::from_tree(&tree);
+    let counts = vec![
+        Counts::resolve(
+            Source::Popcon,
+            &index,
+            &tree.packages,
+            popularity::parse_popcon(&fixture("popcon-by_inst.txt")),
+        ),
+        Counts::resolve(
+            Source::GithubStars,
+            &index,
+            &tree.packages,
+            popularity::parse_stars_csv(&fixture("github-stars.csv")),
+        ),
+    ];
+    // fd-find is fd on apt; dpkg and libc6 are not in the database
+    let popcon: Vec<_> = counts[0].counts.keys().map(String::as_str).collect();
+    assert_eq!(popcon, vec!["curl", "fd", "git", "pandoc"]);
+    // Repositories match the package `source`, plain names the package
+    let stars: Vec<_> = counts[1].counts.keys().map(String::as_str).collect();
+    assert_eq!(stars, vec!["fd", "git", "helix", "pandoc"]);
+
+    let report = PopularityReport::compute(&tree.packages, &counts);
+    assert_eq!(report.scores.len(), 5);
+    assert_eq!(report.unscored.len(), tree.packages.len() - 5);
+    let helix = report.scores.iter().find(|s| s.package == "helix").unwrap();
+    assert_eq!((helix.current, helix.computed), (68, 96));
+    let git = report.scores.iter().find(|s| s.package == "git").unwrap();
+    assert_eq!(git.sources[&Source::GithubStars], 100.0);
+
+    let divergent: Vec<_> = report
+        .divergent(20)
+        .iter()
+        .map(|s| s.package.as_str())
+        .collect();
+    assert_eq!(divergent, vec!["helix"]);
+    assert!(report
+        .to_text(20)
+        .contains("  helix: 68 -> 96 (github-stars 96)\n"));
+}
+
+#[test]
+fn test_writes_scores_into_package_files() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::create_dir(dir.path().join("tools")).unwrap();
+    let path = dir.path().join("tools/pandoc.yaml");
+    fs::write(
+        &path,
+        "name: pandoc\ndescription: \"Document converter\"\npopularity: 82\n\ntags:\n  - docs\n",
+    )
+    .unwrap();
+
+    let scores = [score("pandoc", 82, 64), score("git", 100, 100)];
+    assert_eq!(popularity::write_scores(dir.path(), &scores).unwrap(), 1);
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "name: pandoc\ndescription: \"Document converter\"\npopularity: 64\n\ntags:\n  - docs\n"
+    );
+    assert!(popularity::write_scores(dir.path(), &[score("nope", 1, 2)]).is_err());
+}
@@ -0,0 +1,130 @@
+//! Reverse-dependency query tests
+
+use heimdal_packages::alias::AliasIndex;
+use heimdal_packages::database::CompiledDatabase;
+use heimdal_packages::graph::{DependencyGraph, DependencyKind};
+use heimdal_packages::loader::SourceTree;
+use heimdal_packages::model::{Package, SuggestionPriority};
+use heimdal_packages::rdeps::{RdepsError, ReverseDependencies};
+use heimdal_packages::resolve::Resolver;
+use std::collections::BTreeMap;
+
+/// Compile the repository the way `compile` does
+fn database() -> CompiledDatabase {
+    let tree = SourceTree::load(".").expect("Failed to load source tree");
+    let graph = DependencyGraph::from_tree(&tree);
+    let aliases = AliasIndex::from_tree(&tree);
+    let resolver = Resolver::from_tree(&tree);
+    let mut packages = tree.packages.clone();
+    graph.apply(&mut packages);
+
+    CompiledDatabase::build(packages, tree.groups.clone())
+        .with_aliases(&aliases)
+        .with_dependencies(&graph)
+        .with_profiles(&resolver, &tree.profiles, &tree.templates)
+        .expect("Profiles should resolve")
+        .with_suggestions(tree.suggestions.clone())
+}
+
+fn package(name: &str, dependencies: &str) -> Package {
+    serde_yaml::from_str(&format!(
+        r#"
+name: {}
+description: Test package
+category: utilities
+popularity: 50
+platforms:
+  apt: {}
+dependencies:
+{}
+tags: [test]
+"#,
+        name, name, dependencies
+    ))
+    .expect("Invalid package YAML")
+}
+
+#[test]
+fn test_lists_everything_referring_to_kubectl() {
+    let db = database();
+    let why = ReverseDependencies::query(&db, "kubectl").unwrap();
+
+    let dependents: Vec<_> = why.dependents.iter().map(|d| d.package.as_str()).collect();
+    assert_eq!(dependents, vec!["helm", "k9s"]);
+    assert!(why
+        .dependents
+        .iter()
+        .all(|d| d.is_direct() && d.kind == DependencyKind::Required));
+    assert!(why.groups.iter().any(|g| g.group == "devops" && g.required));
+    assert!(why.profiles.contains(&"devops".to_string()));
+    assert!(why.templates.contains(&"linux-server".to_string()));
+    assert!(why
+        .suggestions
+        .iter()
+        .any(|s| s.files.contains(&"Chart.yaml".to_string())
+            && s.priority == SuggestionPriority::Required));
+    assert!(why.related_to.contains(&"helm".to_string()));
+}
+
+#[test]
+fn test_walks_transitive_dependents() {
+    let packages = vec![
+        package("lib", "  required: []"),
+        package(
+            "tool",
+            "  required:\n    - package: lib\n      reason: links it",
+        ),
+        package(
+            "app",
+            "  required:\n    - package: tool\n      reason: wraps it",
+        ),
+        package(
+            "plugin",
+            "  optional:\n    - package: app\n      reason: extends it",
+        ),
+    ];
+    let graph = DependencyGraph::build(&packages, &BTreeMap::new());
+    let db = CompiledDatabase::build(packages, Vec::new()).with_dependencies(&graph);
+
+    let why = ReverseDependencies::query(&db, "lib").unwrap();
+    let dependents: Vec<_> = why
+        .dependents
+        .iter()
+        .map(|d| (d.package.as_str(), d.kind, d.path.join(" ")))
+        .collect();
+    assert_eq!(
+        dependents,
+        vec![
+            ("tool", DependencyKind::Required, "tool lib".to_string()),
+            ("app", DependencyKind::Required, "app tool lib".to_string()),
+            (
+                "plugin",
+                DependencyKind::Optional,
+                "plugin app tool lib".to_string()
+            ),
+        ]
+    );
+    assert!(why.to_text().contains("plugin (optional, via app → tool)"));
+
+    let why = ReverseDependencies::query(&db, "plugin").unwrap();
+    assert!(why.is_empty());
+    assert_eq!(why.to_text(), "Why plugin:\n  nothing refers to it\n");
+}
+
+#[test]
+fn test_resolves_aliases_and_rejects_unknown_packages() {
+    let db = database();
+    let db = CompiledDatabase::from_bytes(&db.to_bytes().unwrap()).unwrap();
+
+    let why = ReverseDependencies::query(&db, "git-core").unwrap();
+    assert_eq!(why.package, "git");
+    assert!(why
+        .dependents
+        .iter()
+        .any(|d| d.package == "neovim" && d.kind == DependencyKind::Required));
+
+    assert_eq!(
+        ReverseDependencies::query(&db, "nope"),
+        Err(RdepsError::UnknownPackage("nope".to_string()))
+    );
+}
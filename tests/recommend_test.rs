@@ -0,0 +1,124 @@
+//! Recommendation engine tests
+
+use heimdal_packages::alias::AliasIndex;
+use heimdal_packages::database::CompiledDatabase;
+use heimdal_packages::loader::SourceTree;
+use heimdal_packages::model::{Package, PackageGroup};
+use heimdal_packages::recommend::{Reason, Recommendations};
+use heimdal_packages::resolve::Resolver;
+
+/// Compile the repository the way `compile` does
+fn database() -> CompiledDatabase {
+    let tree = SourceTree::load(".").expect("Failed to load source tree");
+    let aliases = AliasIndex::from_tree(&tree);
+    let resolver = Resolver::from_tree(&tree);
+
+    CompiledDatabase::build(tree.packages.clone(), tree.groups.clone())
+        .with_aliases(&aliases)
+        .with_profiles(&resolver, &tree.profiles, &tree.templates)
+        .expect("Profiles should resolve")
+}
+
+fn package(name: &str, popularity: u8, tag: &str, extra: &str) -> Package {
+    serde_yaml::from_str(&format!(
+        r#"
+name: {}
+description: Test package
+category: utilities
+popularity: {}
+platforms:
+  apt: {}
+tags: [{}]
+{}
+"#,
+        name, popularity, name, tag, extra
+    ))
+    .expect("Invalid package YAML")
+}
+
+#[test]
+fn test_recommends_related_tools_for_the_repository() {
+    let db = database();
+    let result = Recommendations::query(&db, ["fzf", "git-core", "nope"], 5);
+
+    assert_eq!(result.installed, vec!["fzf", "git"]);
+    assert_eq!(result.unknown, vec!["nope"]);
+    let top = &result.recommendations[0];
+    assert_eq!(top.package, "ripgrep");
+    assert_eq!(
+        top.reasons[0],
+        Reason::Related {
+            installed: "fzf".to_string()
+        }
+    );
+
+    // Alternatives of an installed editor are never suggested
+    let result = Recommendations::query(&db, ["vim", "git"], 50);
+    assert!(result
+        .recommendations
+        .iter()
+        .all(|r| !["neovim", "emacs", "helix"].contains(&r.package.as_str())));
+}
+
+#[test]
+fn test_scores_and_explains_each_signal() {
+    let group: PackageGroup = serde_yaml::from_str(
+        r#"
+id: tools
+name: Tools
+description: Tools
+category: utilities
+packages:
+  required: [a, b, c, d]
+"#,
+    )
+    .unwrap();
+    let packages = vec![
+        package("a", 0, "test", "related: [b]"),
+        package("b", 50, "test", ""),
+        package("c", 20, "test", ""),
+        package("d", 70, "other", ""),
+        package("e", 100, "elsewhere", ""),
+    ];
+    let db = CompiledDatabase::build(packages, vec![group]);
+
+    let result = Recommendations::query(&db, ["a"], 10);
+    let scores: Vec<_> = result
+        .recommendations
+        .iter()
+        .map(|r| (r.package.as_str(), r.score))
+        .collect();
+    // related 3 + group 2 * 1/4 + shared tag 0.5 + popularity; `e` shares
+    // nothing with `a`
+    assert_eq!(scores, vec![("b", 4.5), ("c", 1.2), ("d", 1.2)]);
+    assert_eq!(
+        result.recommendations[0].explanation(),
+        "suggested because b is related to a, both are in tools and it shares the 'test' tag with a"
+    );
+    assert_eq!(
+        result.recommendations[1].explanation(),
+        "suggested because it is in tools with a and it shares the 'test' tag with a"
+    );
+}
+
+#[test]
+fn test_skips_deprecated_and_conflicting_packages() {
+    let packages = vec![
+        package("a", 50, "test", "related: [b, c, d]\nconflicts: [d]"),
+        package("b", 50, "test", ""),
+        package("c", 50, "test", "deprecated: true"),
+        package("d", 50, "test", "conflicts: [a]"),
+    ];
+    let db = CompiledDatabase::build(packages, Vec::new());
+
+    let result = Recommendations::query(&db, ["a"], 10);
+    let names: Vec<_> = result
+        .recommendations
+        .iter()
+        .map(|r| r.package.as_str())
+        .collect();
+    assert_eq!(names, vec!["b"]);
+    assert!(Recommendations::query(&db, ["nope"], 10)
+        .to_text()
+        .contains("No recommendations"));
+}
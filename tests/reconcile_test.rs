@@ -0,0 +1,113 @@
+//! Mapping reconciliation tests
+
+use heimdal_packages::loader::SourceTree;
+use heimdal_packages::model::{Manager, Mapping};
+use heimdal_packages::reconcile::{self, merged_platforms, reconcile, Discrepancy};
+
+fn mapping(yaml: &str) -> Mapping {
+    serde_yaml::from_str(yaml).expect("Failed to parse test mapping")
+}
+
+#[test]
+fn test_repository_mappings_agree_with_packages() {
+    let tree = SourceTree::load(".").expect("Failed to load source tree");
+    let discrepancies = reconcile(&tree.packages, &tree.mappings);
+
+    // Mappings may describe packages that have no file yet, but never
+    // contradict one that does
+    assert!(discrepancies
+        .iter()
+        .all(|d| matches!(d, Discrepancy::OrphanMapping { .. })));
+    assert!(discrepancies.contains(&Discrepancy::OrphanMapping {
+        canonical: "gcc".to_string()
+    }));
+}
+
+#[test]
+fn test_reports_conflicts_and_gaps() {
+    let tree = SourceTree::load(".").expect("Failed to load source tree");
+    let mappings = vec![
+        mapping("{canonical: make, platforms: {apt: build-essential, brew: make}}"),
+        mapping("{canonical: helm, platforms: {apt: helm, brew: helm}}"),
+    ];
+
+    let discrepancies = reconcile(&tree.packages, &mappings);
+    assert_eq!(
+        discrepancies,
+        vec![
+            Discrepancy::Conflict {
+                package: "make".to_string(),
+                manager: Manager::Apt,
+                package_name: "make".to_string(),
+                mapping_name: "build-essential".to_string(),
+            },
+            Discrepancy::MappingOnly {
+                package: "helm".to_string(),
+                manager: Manager::Apt,
+                mapping_name: "helm".to_string(),
+            },
+        ]
+    );
+    assert_eq!(
+        discrepancies[0].to_string(),
+        "'make' is 'make' on apt but its mapping says 'build-essential' (package file wins)"
+    );
+}
+
+#[test]
+fn test_merge_keeps_package_names_and_fills_gaps() {
+    let mut tree = SourceTree::load(".").expect("Failed to load source tree");
+    let mappings = vec![
+        mapping("{canonical: make, platforms: {apt: build-essential, brew: make}}"),
+        mapping("{canonical: helm, platforms: {apt: helm, brew: helm, apk: helm}}"),
+    ];
+
+    let make = tree.package("make").unwrap();
+    assert_eq!(
+        merged_platforms(make, Some(&mappings[0])).apt.as_deref(),
+        Some("make")
+    );
+
+    reconcile::merge(&mut tree.packages, &mappings);
+    let helm = tree.package("helm").unwrap();
+    assert_eq!(helm.platforms.apt.as_deref(), Some("helm"));
+    assert_eq!(helm.platforms.brew.as_deref(), Some("helm"));
+    assert!(reconcile(&tree.packages, &mappings)
+        .iter()
+        .all(|d| !matches!(d, Discrepancy::MappingOnly { .. })));
+}
+
+#[test]
+fn test_package_file_wins_over_the_original_make_and_go_mappings() {
+    let mut tree = SourceTree::load(".").expect("Failed to load source tree");
+    // The mappings as they stood before they were fixed to match the
+    // package files
+    let mappings = vec![
+        mapping(
+            "{canonical: make, platforms: {apt: build-essential, brew: make, dnf: make, pacman: base-devel}, aliases: [gmake]}",
+        ),
+        mapping("{canonical: go, platforms: {apt: golang, brew: go, dnf: golang, pacman: go}}"),
+    ];
+
+    let conflicts: Vec<String> = reconcile(&tree.packages, &mappings)
+        .iter()
+        .filter(|d| matches!(d, Discrepancy::Conflict { .. }))
+        .map(|d| d.to_string())
+        .collect();
+    assert_eq!(
+        conflicts,
+        vec![
+            "'make' is 'make' on apt but its mapping says 'build-essential' (package file wins)",
+            "'make' is 'make' on pacman but its mapping says 'base-devel' (package file wins)",
+            "'go' is 'golang-go' on apt but its mapping says 'golang' (package file wins)",
+        ]
+    );
+
+    reconcile::merge(&mut tree.packages, &mappings);
+    let make = tree.package("make").unwrap();
+    assert_eq!(make.platforms.apt.as_deref(), Some("make"));
+    assert_eq!(make.platforms.pacman.as_deref(), Some("make"));
+    let go = tree.package("go").unwrap();
+    assert_eq!(go.platforms.apt.as_deref(), Some("golang-go"));
+    assert_eq!(go.platforms.dnf.as_deref(), Some("golang"));
+}
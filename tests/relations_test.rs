@@ -0,0 +1,158 @@
+//! Alternatives and related-package checks
+
+use heimdal_packages::loader::{load_packages, SourceTree};
+use heimdal_packages::model::Package;
+use heimdal_packages::relations::{self, append_to_list, BackLink, RelationError};
+use std::fs;
+
+fn package(name: &str, category: &str, alternatives: &[&str], related: &[&str]) -> Package {
+    serde_yaml::from_str(&format!(
+        r#"
+name: {}
+description: Test package
+category: {}
+popularity: 50
+platforms:
+  apt: {}
+alternatives: [{}]
+related: [{}]
+tags: [test]
+"#,
+        name,
+        category,
+        name,
+        alternatives.join(", "),
+        related.join(", ")
+    ))
+    .expect("Invalid package YAML")
+}
+
+#[test]
+fn test_repository_alternatives_are_symmetric() {
+    let tree = SourceTree::load(".").expect("Failed to load source tree");
+    assert_eq!(relations::check(&tree.packages), vec![]);
+    assert!(relations::missing_back_links(&tree.packages).is_empty());
+
+    let mismatches: Vec<_> = relations::category_mismatches(&tree.packages)
+        .iter()
+        .map(|m| (m.package.clone(), m.alternative.clone()))
+        .collect();
+    assert_eq!(
+        mismatches,
+        vec![
+            ("npm".to_string(), "yarn".to_string()),
+            ("pip".to_string(), "pipenv".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn test_reports_self_references_and_missing_back_links() {
+    let packages = [
+        package("a", "editor", &["b", "a"], &["a"]),
+        package("b", "editor", &[], &[]),
+        package("c", "editor", &["unknown"], &[]),
+    ];
+
+    let errors = relations::check(&packages);
+    assert_eq!(
+        errors,
+        vec![
+            RelationError::SelfReference {
+                package: "a".to_string(),
+                list: "alternatives",
+            },
+            RelationError::SelfReference {
+                package: "a".to_string(),
+                list: "related",
+            },
+            RelationError::Asymmetric {
+                package: "a".to_string(),
+                alternative: "b".to_string(),
+            },
+        ]
+    );
+    assert_eq!(
+        errors[2].to_string(),
+        "'a' lists 'b' as an alternative, but 'b' does not list 'a' back"
+    );
+    assert_eq!(
+        relations::missing_back_links(&packages),
+        vec![BackLink {
+            package: "b".to_string(),
+            alternative: "a".to_string(),
+        }]
+    );
+}
+
+#[test]
+fn test_appends_to_block_flow_and_missing_lists() {
+    let block = "name: vim\nalternatives:\n  - neovim\n  # editors\n\nrelated: []\n";
+    assert_eq!(
+        append_to_list(block, "alternatives", "helix"),
+        "name: vim\nalternatives:\n  - neovim\n  - helix\n  # editors\n\nrelated: []\n"
+    );
+
+    let flush = "alternatives:\n- neovim\nrelated: []\n";
+    assert_eq!(
+        append_to_list(flush, "alternatives", "helix"),
+        "alternatives:\n- neovim\n- helix\nrelated: []\n"
+    );
+
+    assert_eq!(
+        append_to_list("alternatives: []\nrelated: []\n", "alternatives", "pipenv"),
+        "alternatives:\n  - pipenv\nrelated: []\n"
+    );
+    assert_eq!(
+        append_to_list("alternatives: [a, b]\n", "alternatives", "c"),
+        "alternatives: [a, b, c]\n"
+    );
+    assert_eq!(
+        append_to_list("name: x", "alternatives", "y"),
+        "name: x\nalternatives:\n  - y\n"
+    );
+}
+
+#[test]
+fn test_appends_to_flow_lists_with_trailing_comments() {
+    let alternatives = |yaml: &str| -> Vec<String> {
+        let value: serde_yaml::Value = serde_yaml::from_str(yaml).unwrap();
+        serde_yaml::from_value(value["alternatives"].clone()).unwrap()
+    };
+
+    let empty = append_to_list(
+        "alternatives: [] # none\nrelated: []\n",
+        "alternatives",
+        "vim",
+    );
+    assert_eq!(empty, "alternatives: # none\n  - vim\nrelated: []\n");
+    assert_eq!(alternatives(&empty), vec!["vim"]);
+
+    let items = append_to_list("alternatives: [a]  # editors\n", "alternatives", "b");
+    assert_eq!(items, "alternatives: [a, b]  # editors\n");
+    assert_eq!(alternatives(&items), vec!["a", "b"]);
+}
+
+#[test]
+fn test_writes_back_links_into_package_files() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::create_dir(dir.path().join("editors")).unwrap();
+    for (name, alternatives) in [("a", "[b]"), ("b", "[]")] {
+        fs::write(
+            dir.path().join(format!("editors/{}.yaml", name)),
+            format!(
+                "name: {}\ndescription: Test package\ncategory: editor\npopularity: 50\nplatforms:\n  apt: {}\nalternatives: {}\ntags: [test]\n",
+                name, name, alternatives
+            ),
+        )
+        .unwrap();
+    }
+
+    let packages = load_packages(dir.path()).unwrap();
+    let links = relations::missing_back_links(&packages);
+    assert_eq!(relations::add_back_links(dir.path(), &links).unwrap(), 1);
+
+    let packages = load_packages(dir.path()).unwrap();
+    assert_eq!(packages[1].alternatives, vec!["a".to_string()]);
+    assert!(relations::check(&packages).is_empty());
+}
@@ -0,0 +1,138 @@
+//! Profile inheritance tests
+
+use heimdal_packages::loader::SourceTree;
+use heimdal_packages::model::Profile;
+use heimdal_packages::resolve::{Origin, ResolveError, Resolver};
+
+fn profile(yaml: &str) -> Profile {
+    serde_yaml::from_str(yaml).expect("Failed to parse test profile")
+}
+
+#[test]
+fn test_profiles_inherit_essentials() {
+    let tree = SourceTree::load(".").expect("Failed to load source tree");
+    let resolver = Resolver::from_tree(&tree);
+
+    let backend = resolver.resolve_profile("backend").unwrap();
+    assert_eq!(backend.chain, vec!["minimal", "backend"]);
+    assert_eq!(
+        backend.names().collect::<Vec<_>>(),
+        vec![
+            "git",
+            "curl",
+            "wget",
+            "tmux",
+            "ripgrep",
+            "jq",
+            "htop",
+            "neovim",
+            "python",
+            "pip",
+            "node",
+            "npm",
+            "go",
+            "docker",
+            "docker-compose",
+            "postgresql",
+            "redis",
+        ]
+    );
+    assert_eq!(
+        backend.package("git").unwrap().origin(),
+        &Origin::Profile {
+            id: "minimal".to_string(),
+            section: "essential"
+        }
+    );
+    assert!(backend.package("vim").is_none());
+
+    let devops = resolver.resolve_profile("devops").unwrap();
+    assert_eq!(
+        devops.package("vim").unwrap().origin().to_string(),
+        "profile minimal (editor)"
+    );
+}
+
+#[test]
+fn test_groups_excludes_and_diamonds() {
+    let tree = SourceTree::load(".").expect("Failed to load source tree");
+    let profiles = vec![
+        profile("{id: base, name: Base, description: Base profile, type: custom, packages: {essential: [git, curl]}, dotfiles: [{source: .gitconfig, target: ~/.gitconfig}]}"),
+        profile("{id: ops, name: Ops, description: Ops profile, type: custom, extends: [base], include_groups: [devops]}"),
+        profile("{id: web, name: Web, description: Web profile, type: custom, extends: [base], packages: {languages: [node]}}"),
+        profile("{id: full, name: Full, description: Full profile, type: custom, extends: [ops, web], exclude: [curl, docker], packages: {other: [docker]}, dotfiles: [{source: work/.gitconfig, target: ~/.gitconfig}]}"),
+    ];
+    let resolver = Resolver::new(&profiles, &tree.groups);
+
+    let full = resolver.resolve_profile("full").unwrap();
+    assert_eq!(full.chain, vec!["base", "ops", "web", "full"]);
+    assert_eq!(
+        full.names().collect::<Vec<_>>(),
+        vec!["git", "docker-compose", "kubectl", "node", "docker"]
+    );
+    assert_eq!(
+        full.package("kubectl").unwrap().origin(),
+        &Origin::Group {
+            id: "devops".to_string()
+        }
+    );
+    // base reaches full through both parents but is recorded once
+    assert_eq!(
+        full.package("git")
+            .unwrap()
+            .sources
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>(),
+        vec!["profile base (essential)", "group devops"]
+    );
+    // Excluded, then listed again by the child itself
+    assert_eq!(
+        full.package("docker").unwrap().origin().to_string(),
+        "profile full (other)"
+    );
+    assert_eq!(full.dotfiles.len(), 1);
+    assert_eq!(full.dotfiles[0].source, "work/.gitconfig");
+}
+
+#[test]
+fn test_reports_cycles_and_unknown_references() {
+    let profiles = vec![
+        profile("{id: a, name: AAA, description: First profile, type: custom, extends: [b]}"),
+        profile("{id: b, name: BBB, description: Second profile, type: custom, extends: [c]}"),
+        profile("{id: c, name: CCC, description: Third profile, type: custom, extends: [a]}"),
+        profile("{id: d, name: DDD, description: Fourth profile, type: custom, extends: [zzz]}"),
+        profile(
+            "{id: e, name: EEE, description: Fifth profile, type: custom, include_groups: [zzz]}",
+        ),
+    ];
+    let resolver = Resolver::new(&profiles, &[]);
+
+    let cycle = resolver.resolve_profile("b").unwrap_err();
+    assert_eq!(
+        cycle,
+        ResolveError::Cycle(vec![
+            "b".to_string(),
+            "c".to_string(),
+            "a".to_string(),
+            "b".to_string()
+        ])
+    );
+    assert_eq!(
+        cycle.to_string(),
+        "profile inheritance cycle: b -> c -> a -> b"
+    );
+
+    assert!(matches!(
+        resolver.resolve_profile("d"),
+        Err(ResolveError::UnknownParent { .. })
+    ));
+    assert!(matches!(
+        resolver.resolve_profile("e"),
+        Err(ResolveError::UnknownGroup { .. })
+    ));
+    assert!(matches!(
+        resolver.resolve_profile("zzz"),
+        Err(ResolveError::UnknownProfile(_))
+    ));
+}
@@ -61,15 +61,17 @@ fn test_all_packages_validate_against_schema() {
         .filter(|e| e.path().extension().and_then(|s| s.to_str()) == Some("yaml"))
     {
         let yaml_content = fs::read_to_string(entry.path())
-            .expect(&format!("Failed to read {}", entry.path().display()));
+            .unwrap_or_else(|_| panic!("Failed to read {}", entry.path().display()));
 
         let yaml_value: serde_yaml::Value = serde_yaml::from_str(&yaml_content)
-            .expect(&format!("Invalid YAML in {}", entry.path().display()));
+            .unwrap_or_else(|_| panic!("Invalid YAML in {}", entry.path().display()));
 
-        let json_value: Value = serde_json::to_value(&yaml_value).expect(&format!(
-            "Failed to convert YAML to JSON for {}",
-            entry.path().display()
-        ));
+        let json_value: Value = serde_json::to_value(&yaml_value).unwrap_or_else(|_| {
+            panic!(
+                "Failed to convert YAML to JSON for {}",
+                entry.path().display()
+            )
+        });
 
         let validation_result = compiled_schema.validate(&json_value);
         if let Err(errors) = validation_result {
@@ -104,15 +106,17 @@ fn test_all_groups_validate_against_schema() {
         .filter(|e| e.path().extension().and_then(|s| s.to_str()) == Some("yaml"))
     {
         let yaml_content = fs::read_to_string(entry.path())
-            .expect(&format!("Failed to read {}", entry.path().display()));
+            .unwrap_or_else(|_| panic!("Failed to read {}", entry.path().display()));
 
         let yaml_value: serde_yaml::Value = serde_yaml::from_str(&yaml_content)
-            .expect(&format!("Invalid YAML in {}", entry.path().display()));
+            .unwrap_or_else(|_| panic!("Invalid YAML in {}", entry.path().display()));
 
-        let json_value: Value = serde_json::to_value(&yaml_value).expect(&format!(
-            "Failed to convert YAML to JSON for {}",
-            entry.path().display()
-        ));
+        let json_value: Value = serde_json::to_value(&yaml_value).unwrap_or_else(|_| {
+            panic!(
+                "Failed to convert YAML to JSON for {}",
+                entry.path().display()
+            )
+        });
 
         let validation_result = compiled_schema.validate(&json_value);
         if let Err(errors) = validation_result {
@@ -147,15 +151,17 @@ fn test_all_profiles_validate_against_schema() {
         .filter(|e| e.path().extension().and_then(|s| s.to_str()) == Some("yaml"))
     {
         let yaml_content = fs::read_to_string(entry.path())
-            .expect(&format!("Failed to read {}", entry.path().display()));
+            .unwrap_or_else(|_| panic!("Failed to read {}", entry.path().display()));
 
         let yaml_value: serde_yaml::Value = serde_yaml::from_str(&yaml_content)
-            .expect(&format!("Invalid YAML in {}", entry.path().display()));
+            .unwrap_or_else(|_| panic!("Invalid YAML in {}", entry.path().display()));
 
-        let json_value: Value = serde_json::to_value(&yaml_value).expect(&format!(
-            "Failed to convert YAML to JSON for {}",
-            entry.path().display()
-        ));
+        let json_value: Value = serde_json::to_value(&yaml_value).unwrap_or_else(|_| {
+            panic!(
+                "Failed to convert YAML to JSON for {}",
+                entry.path().display()
+            )
+        });
 
         let validation_result = compiled_schema.validate(&json_value);
         if let Err(errors) = validation_result {
@@ -172,3 +178,24 @@ fn test_all_profiles_validate_against_schema() {
 
     assert!(validated_count > 0, "No profiles were validated");
 }
+
+#[test]
+fn test_dotfile_package_is_optional() {
+    let schema_content =
+        fs::read_to_string("schemas/profile.schema.json").expect("Failed to read profile schema");
+    let schema_value: Value =
+        serde_json::from_str(&schema_content).expect("Profile schema is not valid JSON");
+    let compiled_schema = JSONSchema::options()
+        .compile(&schema_value)
+        .expect("Failed to compile profile schema");
+
+    let yaml = "id: shell\nname: Shell\ndescription: Shell configuration\ntype: minimal\nextends: [minimal]\n\
+                dotfiles:\n  - source: .inputrc\n    target: ~/.inputrc\n";
+    let profile: heimdal_packages::model::Profile =
+        serde_yaml::from_str(yaml).expect("Profile should deserialize");
+    assert_eq!(profile.dotfiles[0].package, None);
+
+    let json_value = serde_json::to_value(serde_yaml::from_str::<serde_yaml::Value>(yaml).unwrap())
+        .expect("Failed to convert YAML to JSON");
+    assert!(compiled_schema.is_valid(&json_value));
+}
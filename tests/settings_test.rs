@@ -0,0 +1,96 @@
+//! Template settings tests
+
+use heimdal_packages::loader::SourceTree;
+use heimdal_packages::model::{Frequency, Settings, Shell, Toggle};
+use jsonschema::JSONSchema;
+use serde_json::Value;
+use std::fs;
+
+fn settings_schema_errors(settings: &str) -> Vec<String> {
+    let schema: Value = serde_json::from_str(
+        &fs::read_to_string("schemas/template.schema.json").expect("Failed to read schema"),
+    )
+    .unwrap();
+    let schema = JSONSchema::compile(&schema).expect("Template schema cannot be compiled");
+
+    let settings: Value = serde_yaml::from_str(settings).unwrap();
+    let template = serde_json::json!({
+        "id": "test",
+        "name": "Test template",
+        "description": "Template used by the settings tests",
+        "platform": "linux",
+        "recommended_for": ["Tests"],
+        "settings": settings,
+    });
+    let errors = match schema.validate(&template) {
+        Ok(()) => Vec::new(),
+        Err(errors) => errors.map(|e| e.to_string()).collect(),
+    };
+    errors
+}
+
+#[test]
+fn test_template_settings_are_typed() {
+    let tree = SourceTree::load(".").expect("Failed to load source tree");
+
+    let server = &tree.template("linux-server").unwrap().settings;
+    assert_eq!(
+        server.security.as_ref().unwrap().firewall,
+        Some(Toggle::Enabled)
+    );
+    assert_eq!(
+        server.updates.as_ref().unwrap().frequency,
+        Some(Frequency::Daily)
+    );
+
+    let macos = &tree.template("macos-developer").unwrap().settings;
+    assert_eq!(macos.shell.as_ref().unwrap().default, Some(Shell::Zsh));
+    assert_eq!(
+        macos.packages(),
+        vec![
+            ("shell.default", "zsh"),
+            ("shell.theme", "starship"),
+            ("editor.default", "neovim")
+        ]
+    );
+}
+
+#[test]
+fn test_unknown_keys_and_values_are_rejected() {
+    let typo = "updates:\n  frequncy: daily\n";
+    let error = serde_yaml::from_str::<Settings>(typo)
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("unknown field `frequncy`"), "{}", error);
+    assert_eq!(
+        settings_schema_errors(typo),
+        vec!["Additional properties are not allowed ('frequncy' was unexpected)"]
+    );
+
+    let value = "updates:\n  frequency: hourly\n";
+    let error = serde_yaml::from_str::<Settings>(value)
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("unknown variant `hourly`"), "{}", error);
+    assert_eq!(settings_schema_errors(value).len(), 1);
+
+    let section = "firewall: enabled\n";
+    assert!(serde_yaml::from_str::<Settings>(section).is_err());
+    assert_eq!(settings_schema_errors(section).len(), 1);
+}
+
+#[test]
+fn test_git_identity_settings() {
+    let yaml = "git:\n  name: Ada Lovelace\n  email: ada@example.com\n  default_branch: main\n";
+    let settings: Settings = serde_yaml::from_str(yaml).unwrap();
+    assert_eq!(
+        settings.git.unwrap().email.as_deref(),
+        Some("ada@example.com")
+    );
+    assert!(settings_schema_errors(yaml).is_empty());
+
+    assert_eq!(
+        settings_schema_errors("git:\n  email: not-an-address\n").len(),
+        1
+    );
+}
@@ -0,0 +1,92 @@
+//! Database signature tests
+
+use heimdal_packages::database::CompiledDatabase;
+use heimdal_packages::loader::SourceTree;
+use heimdal_packages::signature::{
+    verify, verify_database, PublicKey, SecretKey, Signature, SignatureError,
+};
+
+fn test_key() -> SecretKey {
+    SecretKey::from_seed([7; 32], *b"heimdal!")
+}
+
+fn compiled_database() -> Vec<u8> {
+    let tree = SourceTree::load(".").expect("Failed to load source tree");
+    CompiledDatabase::build(tree.packages, tree.groups)
+        .to_bytes()
+        .expect("Failed to serialize database")
+}
+
+#[test]
+fn test_keys_round_trip_through_minisign_format() {
+    let key = test_key();
+
+    let secret = SecretKey::from_minisign(&key.to_minisign()).expect("Secret key should parse");
+    assert_eq!(secret.public_key(), key.public_key());
+
+    let public = key.public_key().to_minisign();
+    assert!(public.starts_with("untrusted comment: minisign public key "));
+    assert_eq!(
+        PublicKey::from_minisign(&public).expect("Public key should parse"),
+        key.public_key()
+    );
+}
+
+#[test]
+fn test_signed_database_verifies_before_loading() {
+    let key = test_key();
+    let data = compiled_database();
+
+    let signature = key.sign(&data, "timestamp:0\tfile:packages.db");
+    let signature = Signature::from_minisign(&signature.to_minisign()).unwrap();
+
+    let db = verify_database(&data, &signature, &key.public_key())
+        .expect("Valid signature should verify");
+    assert!(!db.packages.is_empty());
+
+    let mut tampered = data.clone();
+    let last = tampered.len() - 1;
+    tampered[last] ^= 1;
+    assert!(matches!(
+        verify_database(&tampered, &signature, &key.public_key()),
+        Err(SignatureError::Invalid)
+    ));
+}
+
+#[test]
+fn test_rejects_other_keys_and_edited_comments() {
+    let key = test_key();
+    let other = SecretKey::from_seed([9; 32], *b"intruder");
+    let data = b"packages";
+
+    let signature = other.sign(data, "timestamp:0");
+    assert!(matches!(
+        verify(data, &signature, &key.public_key()),
+        Err(SignatureError::KeyMismatch { .. })
+    ));
+
+    let text = key
+        .sign(data, "timestamp:0")
+        .to_minisign()
+        .replace("timestamp:0", "timestamp:1");
+    let edited = Signature::from_minisign(&text).unwrap();
+    assert!(matches!(
+        verify(data, &edited, &key.public_key()),
+        Err(SignatureError::Invalid)
+    ));
+}
+
+#[test]
+fn test_signatures_are_minisign_compatible() {
+    let key = test_key();
+    let data = compiled_database();
+    let signature = key.sign(&data, "timestamp:0\tfile:packages.db");
+
+    let public = minisign_verify::PublicKey::decode(&key.public_key().to_minisign())
+        .expect("minisign should accept the public key");
+    let signature = minisign_verify::Signature::decode(&signature.to_minisign())
+        .expect("minisign should accept the signature");
+    public
+        .verify(&data, &signature, false)
+        .expect("minisign should verify the signature");
+}
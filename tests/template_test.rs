@@ -0,0 +1,111 @@
+//! Template rendering tests
+
+use heimdal_packages::loader::SourceTree;
+use heimdal_packages::model::{EditorSettings, Manager};
+use heimdal_packages::resolve::{Origin, ResolveError};
+use heimdal_packages::template::{RenderError, RenderedTemplate};
+
+#[test]
+fn test_renders_linux_template() {
+    let tree = SourceTree::load(".").expect("Failed to load source tree");
+    let rendered = RenderedTemplate::render(&tree, "linux-server").unwrap();
+
+    assert_eq!(rendered.profiles, vec!["minimal"]);
+    let docker = rendered
+        .package("docker")
+        .expect("devops group is included");
+    assert_eq!(docker.platforms[&Manager::Apt], "docker.io");
+    assert!(!docker.platforms.contains_key(&Manager::Brew));
+    assert_eq!(
+        rendered.package("jq").unwrap().sources,
+        vec![Origin::Template {
+            id: "linux-server".to_string()
+        }]
+    );
+    assert!(rendered.settings.security.is_some());
+}
+
+#[test]
+fn test_renders_profile_chain_for_macos() {
+    let tree = SourceTree::load(".").expect("Failed to load source tree");
+    let rendered = RenderedTemplate::render(&tree, "macos-developer").unwrap();
+
+    assert_eq!(rendered.profiles, vec!["minimal", "developer"]);
+    assert_eq!(
+        rendered.package("git").unwrap().sources[0],
+        Origin::Profile {
+            id: "minimal".to_string(),
+            section: "essential"
+        }
+    );
+    assert!(rendered
+        .packages
+        .iter()
+        .all(|p| p.platforms.keys().all(|m| *m == Manager::Brew)));
+    assert!(rendered.platform_overrides["macos"]
+        .casks
+        .contains(&"visual-studio-code".to_string()));
+    assert!(!rendered.platform_overrides.contains_key("linux"));
+    assert!(rendered
+        .dotfiles
+        .iter()
+        .any(|d| d.target == "~/.config/nvim"));
+
+    let json = serde_json::to_value(&rendered).unwrap();
+    assert_eq!(json["platform"], "macos");
+    assert_eq!(json["packages"][0]["platforms"]["brew"], "git");
+}
+
+#[test]
+fn test_merges_overrides_of_groups_included_by_the_base_profile() {
+    let mut tree = SourceTree::load(".").expect("Failed to load source tree");
+    let minimal = tree
+        .profiles
+        .iter_mut()
+        .find(|p| p.id == "minimal")
+        .unwrap();
+    minimal.include_groups.push("rust-dev".to_string());
+
+    let rendered = RenderedTemplate::render(&tree, "linux-server").unwrap();
+    assert_eq!(
+        rendered.platform_overrides["linux"].packages,
+        vec!["pkg-config".to_string()]
+    );
+    assert!(rendered.package("rust").is_some());
+}
+
+#[test]
+fn test_reports_unknown_templates_and_packages() {
+    let mut tree = SourceTree::load(".").expect("Failed to load source tree");
+    assert!(matches!(
+        RenderedTemplate::render(&tree, "nope"),
+        Err(RenderError::Resolve(ResolveError::UnknownTemplate(_)))
+    ));
+
+    let template = tree
+        .templates
+        .iter_mut()
+        .find(|t| t.id == "linux-server")
+        .unwrap();
+    template.custom_packages.push("not-a-package".to_string());
+    template.settings.editor = Some(EditorSettings {
+        default: Some("emacs".to_string()),
+    });
+    let error = RenderedTemplate::render(&tree, "linux-server").unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "unknown package 'not-a-package' (from template linux-server)"
+    );
+
+    tree.templates
+        .iter_mut()
+        .find(|t| t.id == "linux-server")
+        .unwrap()
+        .custom_packages
+        .pop();
+    let error = RenderedTemplate::render(&tree, "linux-server").unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "settings.editor.default is 'emacs', which the template does not install"
+    );
+}
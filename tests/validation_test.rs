@@ -31,12 +31,10 @@ fn test_no_duplicate_package_names() {
         .filter(|e| e.path().extension().and_then(|s| s.to_str()) == Some("yaml"))
     {
         let yaml_content = fs::read_to_string(entry.path())
-            .expect(&format!("Failed to read {}", entry.path().display()));
+            .unwrap_or_else(|_| panic!("Failed to read {}", entry.path().display()));
 
-        let package: Package = serde_yaml::from_str(&yaml_content).expect(&format!(
-            "Failed to parse package from {}",
-            entry.path().display()
-        ));
+        let package: Package = serde_yaml::from_str(&yaml_content)
+            .unwrap_or_else(|_| panic!("Failed to parse package from {}", entry.path().display()));
 
         if !package_names.insert(package.name.clone()) {
             duplicates.push(package.name);
@@ -60,12 +58,10 @@ fn test_package_names_match_filenames() {
         .filter(|e| e.path().extension().and_then(|s| s.to_str()) == Some("yaml"))
     {
         let yaml_content = fs::read_to_string(entry.path())
-            .expect(&format!("Failed to read {}", entry.path().display()));
+            .unwrap_or_else(|_| panic!("Failed to read {}", entry.path().display()));
 
-        let package: Package = serde_yaml::from_str(&yaml_content).expect(&format!(
-            "Failed to parse package from {}",
-            entry.path().display()
-        ));
+        let package: Package = serde_yaml::from_str(&yaml_content)
+            .unwrap_or_else(|_| panic!("Failed to parse package from {}", entry.path().display()));
 
         let filename = entry
             .path()
@@ -116,12 +112,10 @@ fn test_all_packages_have_valid_categories() {
         .filter(|e| e.path().extension().and_then(|s| s.to_str()) == Some("yaml"))
     {
         let yaml_content = fs::read_to_string(entry.path())
-            .expect(&format!("Failed to read {}", entry.path().display()));
+            .unwrap_or_else(|_| panic!("Failed to read {}", entry.path().display()));
 
-        let package: Package = serde_yaml::from_str(&yaml_content).expect(&format!(
-            "Failed to parse package from {}",
-            entry.path().display()
-        ));
+        let package: Package = serde_yaml::from_str(&yaml_content)
+            .unwrap_or_else(|_| panic!("Failed to parse package from {}", entry.path().display()));
 
         if !valid_categories.contains(&package.category.as_str()) {
             invalid_packages.push(format!(
@@ -148,12 +142,10 @@ fn test_all_packages_have_platform_coverage() {
         .filter(|e| e.path().extension().and_then(|s| s.to_str()) == Some("yaml"))
     {
         let yaml_content = fs::read_to_string(entry.path())
-            .expect(&format!("Failed to read {}", entry.path().display()));
+            .unwrap_or_else(|_| panic!("Failed to read {}", entry.path().display()));
 
-        let package: Package = serde_yaml::from_str(&yaml_content).expect(&format!(
-            "Failed to parse package from {}",
-            entry.path().display()
-        ));
+        let package: Package = serde_yaml::from_str(&yaml_content)
+            .unwrap_or_else(|_| panic!("Failed to parse package from {}", entry.path().display()));
 
         let platform_count = [
             package.platforms.apt.is_some(),
@@ -191,12 +183,10 @@ fn test_all_tags_follow_pattern() {
         .filter(|e| e.path().extension().and_then(|s| s.to_str()) == Some("yaml"))
     {
         let yaml_content = fs::read_to_string(entry.path())
-            .expect(&format!("Failed to read {}", entry.path().display()));
+            .unwrap_or_else(|_| panic!("Failed to read {}", entry.path().display()));
 
-        let package: Package = serde_yaml::from_str(&yaml_content).expect(&format!(
-            "Failed to parse package from {}",
-            entry.path().display()
-        ));
+        let package: Package = serde_yaml::from_str(&yaml_content)
+            .unwrap_or_else(|_| panic!("Failed to parse package from {}", entry.path().display()));
 
         for tag in &package.tags {
             if !tag_pattern.is_match(tag) {